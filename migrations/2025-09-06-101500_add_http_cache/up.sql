@@ -0,0 +1,6 @@
+CREATE TABLE http_cache (
+  url VARCHAR PRIMARY KEY NOT NULL,
+  etag VARCHAR NOT NULL,
+  body TEXT NOT NULL,
+  updated_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,19 @@
+CREATE TABLE notifications_old (
+  id VARCHAR PRIMARY KEY NOT NULL,
+  title VARCHAR NOT NULL,
+  repo VARCHAR NOT NULL,
+  url VARCHAR NOT NULL,
+  type VARCHAR NOT NULL,
+  state VARCHAR NOT NULL,
+  author VARCHAR NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  unread BOOLEAN NOT NULL,
+  done BOOLEAN NOT NULL,
+  score INTEGER NOT NULL,
+  score_boost INTEGER NOT NULL,
+  reason VARCHAR NOT NULL DEFAULT ''
+);
+INSERT OR IGNORE INTO notifications_old (id, title, repo, url, type, state, author, updated_at, unread, done, score, score_boost, reason)
+  SELECT id, title, repo, url, type, state, author, updated_at, unread, done, score, score_boost, reason FROM notifications;
+DROP TABLE notifications;
+ALTER TABLE notifications_old RENAME TO notifications;
@@ -0,0 +1,22 @@
+-- a thread can be in the inbox of several accounts, sqlite can't alter a primary key
+CREATE TABLE notifications_new (
+  id VARCHAR NOT NULL,
+  account VARCHAR NOT NULL DEFAULT 'default',
+  title VARCHAR NOT NULL,
+  repo VARCHAR NOT NULL,
+  url VARCHAR NOT NULL,
+  type VARCHAR NOT NULL,
+  state VARCHAR NOT NULL,
+  author VARCHAR NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  unread BOOLEAN NOT NULL,
+  done BOOLEAN NOT NULL,
+  score INTEGER NOT NULL,
+  score_boost INTEGER NOT NULL,
+  reason VARCHAR NOT NULL DEFAULT '',
+  PRIMARY KEY (id, account)
+);
+INSERT INTO notifications_new (id, title, repo, url, type, state, author, updated_at, unread, done, score, score_boost, reason)
+  SELECT id, title, repo, url, type, state, author, updated_at, unread, done, score, score_boost, reason FROM notifications;
+DROP TABLE notifications;
+ALTER TABLE notifications_new RENAME TO notifications;
@@ -0,0 +1,9 @@
+CREATE TABLE pending_actions (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  notification_id VARCHAR NOT NULL,
+  account VARCHAR NOT NULL,
+  action VARCHAR NOT NULL,
+  attempts INTEGER NOT NULL DEFAULT 0,
+  last_error TEXT,
+  created_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1 @@
+ALTER TABLE notifications ADD COLUMN muted BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,13 @@
+CREATE TABLE sync_state (
+  account VARCHAR PRIMARY KEY NOT NULL,
+  synced_at TIMESTAMP NOT NULL,
+  succeeded_at TIMESTAMP,
+  since TIMESTAMP,
+  last_modified VARCHAR,
+  poll_interval INTEGER,
+  ratelimit_remaining INTEGER,
+  ratelimit_used INTEGER,
+  duration_ms INTEGER NOT NULL,
+  fetched INTEGER NOT NULL,
+  error TEXT
+);
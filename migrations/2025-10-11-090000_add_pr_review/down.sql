@@ -0,0 +1,3 @@
+ALTER TABLE notifications DROP COLUMN ci_state;
+ALTER TABLE notifications DROP COLUMN review_decision;
+ALTER TABLE notifications DROP COLUMN review_requested;
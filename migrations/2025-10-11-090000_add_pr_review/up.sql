@@ -0,0 +1,3 @@
+ALTER TABLE notifications ADD COLUMN review_requested BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE notifications ADD COLUMN review_decision VARCHAR;
+ALTER TABLE notifications ADD COLUMN ci_state VARCHAR;
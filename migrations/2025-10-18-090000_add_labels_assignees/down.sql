@@ -0,0 +1,3 @@
+ALTER TABLE notifications DROP COLUMN milestone;
+DROP TABLE notification_assignees;
+DROP TABLE notification_labels;
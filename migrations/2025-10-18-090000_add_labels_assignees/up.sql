@@ -0,0 +1,13 @@
+CREATE TABLE notification_labels (
+  notification_id VARCHAR NOT NULL,
+  account VARCHAR NOT NULL,
+  name VARCHAR NOT NULL,
+  PRIMARY KEY (notification_id, account, name)
+);
+CREATE TABLE notification_assignees (
+  notification_id VARCHAR NOT NULL,
+  account VARCHAR NOT NULL,
+  login VARCHAR NOT NULL,
+  PRIMARY KEY (notification_id, account, login)
+);
+ALTER TABLE notifications ADD COLUMN milestone VARCHAR;
@@ -0,0 +1,4 @@
+ALTER TABLE notifications DROP COLUMN comment_url;
+ALTER TABLE notifications DROP COLUMN comment_excerpt;
+ALTER TABLE notifications DROP COLUMN comment_at;
+ALTER TABLE notifications DROP COLUMN comment_author;
@@ -0,0 +1,4 @@
+ALTER TABLE notifications ADD COLUMN comment_author VARCHAR;
+ALTER TABLE notifications ADD COLUMN comment_at TIMESTAMP;
+ALTER TABLE notifications ADD COLUMN comment_excerpt TEXT;
+ALTER TABLE notifications ADD COLUMN comment_url VARCHAR;
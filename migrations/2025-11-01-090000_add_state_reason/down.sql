@@ -0,0 +1,2 @@
+ALTER TABLE notifications DROP COLUMN duplicate_of;
+ALTER TABLE notifications DROP COLUMN state_reason;
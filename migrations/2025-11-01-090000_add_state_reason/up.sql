@@ -0,0 +1,2 @@
+ALTER TABLE notifications ADD COLUMN state_reason VARCHAR;
+ALTER TABLE notifications ADD COLUMN duplicate_of VARCHAR;
@@ -0,0 +1,3 @@
+ALTER TABLE notifications DROP COLUMN published_at;
+ALTER TABLE notifications DROP COLUMN prerelease;
+ALTER TABLE notifications DROP COLUMN release_tag;
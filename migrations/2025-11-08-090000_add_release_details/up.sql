@@ -0,0 +1,3 @@
+ALTER TABLE notifications ADD COLUMN release_tag VARCHAR;
+ALTER TABLE notifications ADD COLUMN prerelease BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE notifications ADD COLUMN published_at TIMESTAMP;
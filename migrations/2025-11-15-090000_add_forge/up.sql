@@ -0,0 +1 @@
+ALTER TABLE notifications ADD COLUMN forge VARCHAR NOT NULL DEFAULT 'GitHub';
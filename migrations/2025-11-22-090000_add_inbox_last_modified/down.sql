@@ -0,0 +1 @@
+ALTER TABLE sync_state DROP COLUMN inbox_last_modified;
@@ -0,0 +1 @@
+ALTER TABLE sync_state ADD COLUMN inbox_last_modified VARCHAR;
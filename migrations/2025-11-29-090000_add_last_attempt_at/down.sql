@@ -0,0 +1 @@
+ALTER TABLE pending_actions DROP COLUMN last_attempt_at;
@@ -0,0 +1 @@
+ALTER TABLE pending_actions ADD COLUMN last_attempt_at TIMESTAMP;
@@ -1,31 +1,267 @@
 use crate::dirs::Directories;
+use crate::models::ForgeKind;
+use anyhow::{Context, Result};
+use log::error;
+use serde::Deserialize;
 use std::{
-    path::PathBuf,
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
     sync::{Mutex, OnceLock},
 };
 
 #[derive(Debug, Clone)]
 pub struct Config {
-    pub github_base_url: String,
+    pub profiles: Vec<Profile>,
     pub db_path: String,
+    pub enrichment: Enrichment,
+    pub retry: RetryPolicy,
+    pub http: HttpConfig,
+    pub feeds: Vec<Subscription>,
+    /// set with `--record` or `--replay`
+    pub recording: Option<Recording>,
+}
+
+/// A GitHub, GitLab or Gitea account: its server, token and rules
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    /// stored as the `account` of its notifications
+    pub name: String,
+    pub forge: ForgeKind,
+    /// REST API, eg `https://ghe.corp/api/v3` for an enterprise server,
+    /// `https://gitlab.corp/api/v4` or `https://codeberg.org/api/v1`
+    pub api_url: String,
+    /// web pages, eg `https://ghe.corp`
+    pub web_url: String,
+    /// `GH_TOKEN`, `GITLAB_TOKEN` or `GITEA_TOKEN` depending on the forge is used if not set
+    pub token: Option<String>,
+    /// shell command printing the token, eg `pass show github`
+    pub token_command: Option<String>,
+    pub rules_path: PathBuf,
+}
+
+/// An RSS or Atom feed, its entries are stored with the feed name as account and repo
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subscription {
+    pub name: String,
+    pub url: String,
     pub rules_path: PathBuf,
 }
 
+/// Retries of HTTP requests failing with a connection error or a 5xx status, the `[retry]`
+/// section of `config.toml`
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay_ms: 500,
+            max_delay_ms: 10_000,
+        }
+    }
+}
+
+/// Network settings shared by every profile, eg for a corporate proxy with a private root CA
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct HttpConfig {
+    /// eg `http://proxy.corp:3128`, `HTTPS_PROXY` and `ALL_PROXY` are used if not set
+    pub proxy: Option<String>,
+    /// comma-separated hosts reached without `proxy`, eg `localhost,.corp`
+    pub no_proxy: Option<String>,
+    /// PEM bundle of root certificates, trusted on top of the system ones
+    pub ca_cert: Option<PathBuf>,
+    /// PEM client certificate and its PKCS#8 key, for servers requiring mutual TLS
+    pub client_cert: Option<PathBuf>,
+    pub client_key: Option<PathBuf>,
+    pub connect_timeout_secs: Option<u64>,
+    /// whole request, body included
+    pub timeout_secs: Option<u64>,
+}
+
+/// HTTP exchanges written to or served from a directory, see `gh::recording`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Recording {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// How PR, issue and release details are fetched
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Enrichment {
+    /// batched GraphQL queries, with REST as fallback
+    Graphql,
+    /// one REST call per notification
+    Rest,
+}
+
+/// `config.toml`, every field is optional
+#[derive(Deserialize, Debug, Default)]
+struct ConfigFile {
+    /// the default profile, when there is no `profiles`
+    github: Option<ProfileSection>,
+    profiles: Option<BTreeMap<String, ProfileSection>>,
+    http: Option<HttpConfig>,
+    retry: Option<RetryPolicy>,
+    feeds: Option<BTreeMap<String, FeedSection>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct FeedSection {
+    url: String,
+    rules: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct ProfileSection {
+    /// `github` by default
+    forge: Option<ForgeKind>,
+    api_url: Option<String>,
+    web_url: Option<String>,
+    token: Option<String>,
+    token_command: Option<String>,
+    rules: Option<PathBuf>,
+}
+
+impl ConfigFile {
+    /// No file is the defaults, an invalid one is an error rather than settings silently ignored
+    fn load(path: &Path) -> Result<ConfigFile> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(ConfigFile::default()),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("cannot read config file {}", path.display()));
+            }
+        };
+        toml::from_str(&content).with_context(|| format!("invalid config file {}", path.display()))
+    }
+
+    fn profiles(&mut self) -> Vec<Profile> {
+        match self.profiles.take() {
+            Some(profiles) if !profiles.is_empty() => profiles
+                .into_iter()
+                .map(|(name, section)| Profile::from_section(name, section))
+                .collect(),
+            _ => vec![Profile::from_section(
+                DEFAULT_PROFILE.into(),
+                self.github.take().unwrap_or_default(),
+            )],
+        }
+    }
+
+    /// A feed named like a profile would mix their notifications, it is left out
+    fn feeds(&mut self, profiles: &[Profile]) -> Vec<Subscription> {
+        self.feeds
+            .take()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(name, _)| {
+                let clash = profiles.iter().any(|profile| profile.name == *name);
+                if clash {
+                    error!("feed {name} is named like a profile, it is ignored");
+                }
+                !clash
+            })
+            .map(|(name, section)| Subscription {
+                name,
+                url: section.url,
+                rules_path: section.rules.unwrap_or(rules_path()),
+            })
+            .collect()
+    }
+}
+
 static GITHUB_BASE_URL: &str = "https://api.github.com";
+static GITHUB_WEB_URL: &str = "https://github.com";
+static GITLAB_BASE_URL: &str = "https://gitlab.com/api/v4";
+static GITEA_BASE_URL: &str = "https://codeberg.org/api/v1";
+pub static DEFAULT_PROFILE: &str = "default";
+
+impl Profile {
+    pub fn new(name: String, api_url: String, rules_path: PathBuf) -> Profile {
+        Profile {
+            name,
+            forge: ForgeKind::GitHub,
+            web_url: web_url(&api_url),
+            api_url,
+            token: None,
+            token_command: None,
+            rules_path,
+        }
+    }
 
+    fn from_section(name: String, section: ProfileSection) -> Profile {
+        let forge = section.forge.unwrap_or_default();
+        let default_api_url = match forge {
+            ForgeKind::GitHub => GITHUB_BASE_URL,
+            ForgeKind::GitLab => GITLAB_BASE_URL,
+            ForgeKind::Gitea => GITEA_BASE_URL,
+        };
+        let api_url = section
+            .api_url
+            .map(|url| url.trim_end_matches('/').to_string())
+            .unwrap_or(default_api_url.to_string());
+        let web_url = section
+            .web_url
+            .map(|url| url.trim_end_matches('/').to_string())
+            .unwrap_or(web_url(&api_url));
+        Profile {
+            name,
+            forge,
+            api_url,
+            web_url,
+            token: section.token,
+            token_command: section.token_command,
+            rules_path: section.rules.unwrap_or(rules_path()),
+        }
+    }
+
+    /// API url on the configured server
+    pub fn rewrite_url(&self, url: &str) -> String {
+        replace_prefix(url, GITHUB_BASE_URL, &self.api_url)
+    }
+
+    /// Web page url on the configured server
+    pub fn rewrite_html_url(&self, url: &str) -> String {
+        replace_prefix(url, GITHUB_WEB_URL, &self.web_url)
+    }
+}
+
+/// The built-in settings, `Config::load` reads them from `config.toml`
 impl Default for Config {
     fn default() -> Config {
-        Config {
-            github_base_url: GITHUB_BASE_URL.to_string(),
-            db_path: database_url(),
-            rules_path: rules_path(),
-        }
+        Config::from_file(ConfigFile::default())
     }
 }
 
 static CONFIG: OnceLock<Mutex<Config>> = OnceLock::new();
 
 impl Config {
+    /// `config.toml` at `path` completed with the environment
+    pub fn load(path: &Path) -> Result<Config> {
+        Ok(Config::from_file(ConfigFile::load(path)?))
+    }
+
+    fn from_file(mut config_file: ConfigFile) -> Config {
+        let profiles = config_file.profiles();
+        Config {
+            feeds: config_file.feeds(&profiles),
+            profiles,
+            db_path: database_url(),
+            enrichment: enrichment(),
+            retry: retry_policy(config_file.retry),
+            http: config_file.http.unwrap_or_default(),
+            recording: None,
+        }
+    }
+
     pub fn get() -> Config {
         CONFIG
             .get_or_init(|| Mutex::new(Config::default()))
@@ -34,35 +270,78 @@ impl Config {
             .clone()
     }
 
-    pub fn init_for_test(github_base_url: String, db_path: String, rule_path: String) -> Config {
-        let new_config = Config {
-            github_base_url,
+    pub fn init_for_test(api_url: String, db_path: String, rule_path: String) -> Config {
+        Config::set_for_test(Config {
+            profiles: vec![Profile::new(
+                DEFAULT_PROFILE.into(),
+                api_url,
+                rule_path.into(),
+            )],
             db_path,
-            rules_path: rule_path.into(),
-        };
+            enrichment: enrichment(),
+            retry: retry_policy(None),
+            http: HttpConfig::default(),
+            feeds: vec![],
+            recording: None,
+        })
+    }
+
+    pub fn reset() -> Config {
         let mut config = CONFIG
             .get_or_init(|| Mutex::new(Config::default()))
             .lock()
             .unwrap();
-        *config = new_config;
+        *config = Config::default();
         config.clone()
     }
 
-    pub fn reset() -> Config {
+    /// Replace the config of the session, eg with command line options
+    pub fn set(new_config: Config) -> Config {
         let mut config = CONFIG
             .get_or_init(|| Mutex::new(Config::default()))
             .lock()
             .unwrap();
-        *config = Config::default();
+        *config = new_config;
         config.clone()
     }
 
-    pub fn rewrite_url(&self, url: &str) -> String {
-        if url.starts_with(GITHUB_BASE_URL) {
-            url.replace(GITHUB_BASE_URL, &self.github_base_url)
-        } else {
-            url.to_string()
+    pub fn set_for_test(new_config: Config) -> Config {
+        Config::set(new_config)
+    }
+
+    /// Profile of the notifications of `account`
+    pub fn profile(&self, account: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.name == account)
+    }
+
+    /// Feed of the entries of `account`
+    pub fn feed(&self, account: &str) -> Option<&Subscription> {
+        self.feeds.iter().find(|feed| feed.name == account)
+    }
+}
+
+fn replace_prefix(url: &str, prefix: &str, new_prefix: &str) -> String {
+    match url.strip_prefix(prefix) {
+        Some(path) if path.is_empty() || path.starts_with(['/', '?']) => {
+            format!("{new_prefix}{path}")
         }
+        _ => url.to_string(),
+    }
+}
+
+/// Web url of an API url: github.com for api.github.com, the host for an enterprise server, a
+/// GitLab or a Gitea instance
+fn web_url(api_url: &str) -> String {
+    if api_url == GITHUB_BASE_URL {
+        return GITHUB_WEB_URL.to_string();
+    }
+    match api_url
+        .strip_suffix("/api/v3")
+        .or_else(|| api_url.strip_suffix("/api/v4"))
+        .or_else(|| api_url.strip_suffix("/api/v1"))
+    {
+        Some(host) => host.to_string(),
+        None => api_url.to_string(),
     }
 }
 
@@ -77,11 +356,35 @@ fn database_url() -> String {
     }
 }
 
+fn enrichment() -> Enrichment {
+    match dotenvy::var("RIIRVIEW_ENRICHMENT") {
+        Ok(val) if val.eq_ignore_ascii_case("rest") => Enrichment::Rest,
+        _ => Enrichment::Graphql,
+    }
+}
+
+/// `RIIRVIEW_HTTP_RETRIES` overrides the number of retries of the config file
+fn retry_policy(section: Option<RetryPolicy>) -> RetryPolicy {
+    let policy = section.unwrap_or_default();
+    RetryPolicy {
+        max_retries: dotenvy::var("RIIRVIEW_HTTP_RETRIES")
+            .ok()
+            .and_then(|val| val.parse().ok())
+            .unwrap_or(policy.max_retries),
+        ..policy
+    }
+}
+
 fn rules_path() -> PathBuf {
     let directories = Directories::new();
     directories.config.join("rules.toml")
 }
 
+pub fn config_path() -> PathBuf {
+    let directories = Directories::new();
+    directories.config.join("config.toml")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -89,7 +392,7 @@ mod tests {
     #[test]
     fn test_config_default() {
         let config = Config::reset();
-        assert_eq!(config.github_base_url, "https://api.github.com");
+        assert_eq!(config.profiles[0].api_url, "https://api.github.com");
     }
 
     #[test]
@@ -99,19 +402,19 @@ mod tests {
             "/tmp/test.db".to_string(),
             "/tmp/rules.toml".to_string(),
         );
-        assert_eq!(config.github_base_url, "http://localhost:1234");
+        assert_eq!(config.profiles[0].api_url, "http://localhost:1234");
     }
 
     #[test]
     fn test_config_change() {
         let config = Config::reset();
-        assert_eq!(config.github_base_url, "https://api.github.com");
+        assert_eq!(config.profiles[0].api_url, "https://api.github.com");
         let config = Config::init_for_test(
             "http://localhost:1234".to_string(),
             "/tmp/test.db".to_string(),
             "/tmp/rules.toml".to_string(),
         );
-        assert_eq!(config.github_base_url, "http://localhost:1234");
+        assert_eq!(config.profiles[0].api_url, "http://localhost:1234");
     }
 
     #[test]
@@ -119,7 +422,7 @@ mod tests {
         let test_url = "https://api.github.com/repos/rust-lang/rust";
 
         let config = Config::reset();
-        let rewritten_url = config.rewrite_url(test_url);
+        let rewritten_url = config.profiles[0].rewrite_url(test_url);
         assert_eq!(rewritten_url, test_url);
 
         let config = Config::init_for_test(
@@ -127,7 +430,226 @@ mod tests {
             "/tmp/test.db".to_string(),
             "/tmp/rules.toml".to_string(),
         );
-        let rewritten_url = config.rewrite_url(test_url);
+        let rewritten_url = config.profiles[0].rewrite_url(test_url);
         assert_eq!(rewritten_url, "http://localhost:1234/repos/rust-lang/rust");
+
+        assert_eq!(
+            config.profiles[0].rewrite_url("https://api.github.com.evil.com/repos"),
+            "https://api.github.com.evil.com/repos"
+        );
+    }
+
+    #[test]
+    fn test_web_url() {
+        assert_eq!(web_url("https://api.github.com"), "https://github.com");
+        assert_eq!(web_url("https://ghe.corp/api/v3"), "https://ghe.corp");
+        assert_eq!(web_url("http://localhost:1234"), "http://localhost:1234");
+    }
+
+    #[test]
+    fn test_rewrite_html_url() {
+        let config = Profile::new(
+            "work".into(),
+            "https://ghe.corp/api/v3".into(),
+            "/tmp/rules.toml".into(),
+        );
+        assert_eq!(config.web_url, "https://ghe.corp");
+        assert_eq!(
+            config.rewrite_url("https://api.github.com/repos/rust-lang/rust"),
+            "https://ghe.corp/api/v3/repos/rust-lang/rust"
+        );
+        assert_eq!(
+            config.rewrite_html_url("https://github.com/rust-lang/rust/pull/1"),
+            "https://ghe.corp/rust-lang/rust/pull/1"
+        );
+        assert_eq!(
+            config.rewrite_html_url("https://ghe.corp/rust-lang/rust/pull/1"),
+            "https://ghe.corp/rust-lang/rust/pull/1"
+        );
+    }
+
+    #[test]
+    fn test_config_file() {
+        let config: ConfigFile = toml::from_str(
+            r#"
+            [github]
+            api_url = "https://ghe.corp/api/v3"
+            "#,
+        )
+        .unwrap();
+        let github = config.github.unwrap();
+        assert_eq!(github.api_url.unwrap(), "https://ghe.corp/api/v3");
+        assert!(github.web_url.is_none());
+
+        let mut config: ConfigFile = toml::from_str("").unwrap();
+        assert!(config.github.is_none());
+        assert!(config.http.is_none());
+        let profiles = config.profiles();
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].name, DEFAULT_PROFILE);
+        assert_eq!(profiles[0].api_url, "https://api.github.com");
+    }
+
+    #[test]
+    fn test_config_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.profiles[0].api_url, "https://api.github.com");
+
+        fs::write(&path, "[github]\napi_url = \"https://ghe.corp/api/v3\"\n").unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.profiles[0].api_url, "https://ghe.corp/api/v3");
+
+        fs::write(&path, "[github\napi_url = 1\n").unwrap();
+        let err = format!("{:#}", Config::load(&path).unwrap_err());
+        assert!(err.contains(path.to_str().unwrap()), "{err}");
+        assert!(err.contains("TOML parse error"), "{err}");
+    }
+
+    #[test]
+    fn test_config_file_profiles() {
+        let mut config: ConfigFile = toml::from_str(
+            r#"
+            [github]
+            api_url = "https://ignored.corp/api/v3"
+
+            [profiles.personal]
+            token = "ghp_personal"
+
+            [profiles.oss]
+            token_command = "pass show github"
+
+            [profiles.work]
+            api_url = "https://ghe.corp/api/v3/"
+            token = "ghp_work"
+            rules = "/home/me/work.toml"
+            "#,
+        )
+        .unwrap();
+        let profiles = config.profiles();
+        assert_eq!(profiles.len(), 3);
+
+        assert_eq!(profiles[0].name, "oss");
+        assert_eq!(profiles[0].token, None);
+        assert_eq!(
+            profiles[0].token_command.as_deref(),
+            Some("pass show github")
+        );
+
+        assert_eq!(profiles[1].name, "personal");
+        assert_eq!(profiles[1].api_url, "https://api.github.com");
+        assert_eq!(profiles[1].web_url, "https://github.com");
+        assert_eq!(profiles[1].token.as_deref(), Some("ghp_personal"));
+        assert_eq!(profiles[1].rules_path, rules_path());
+
+        assert_eq!(profiles[2].name, "work");
+        assert_eq!(profiles[2].api_url, "https://ghe.corp/api/v3");
+        assert_eq!(profiles[2].web_url, "https://ghe.corp");
+        assert_eq!(profiles[2].token.as_deref(), Some("ghp_work"));
+        assert_eq!(profiles[2].rules_path, PathBuf::from("/home/me/work.toml"));
+    }
+
+    #[test]
+    fn test_config_file_forges() {
+        let mut config: ConfigFile = toml::from_str(
+            r#"
+            [profiles.gitlab]
+            forge = "gitlab"
+
+            [profiles.corp]
+            forge = "gitlab"
+            api_url = "https://gitlab.corp/api/v4"
+
+            [profiles.forgejo]
+            forge = "forgejo"
+            api_url = "https://git.corp/api/v1"
+            token = "gitea_token"
+            "#,
+        )
+        .unwrap();
+        let profiles = config.profiles();
+        assert_eq!(profiles[0].name, "corp");
+        assert_eq!(profiles[0].forge, ForgeKind::GitLab);
+        assert_eq!(profiles[0].web_url, "https://gitlab.corp");
+
+        assert_eq!(profiles[1].name, "forgejo");
+        assert_eq!(profiles[1].forge, ForgeKind::Gitea);
+        assert_eq!(profiles[1].web_url, "https://git.corp");
+        assert_eq!(profiles[1].token.as_deref(), Some("gitea_token"));
+
+        assert_eq!(profiles[2].forge, ForgeKind::GitLab);
+        assert_eq!(profiles[2].api_url, "https://gitlab.com/api/v4");
+        assert_eq!(profiles[2].web_url, "https://gitlab.com");
+    }
+
+    #[test]
+    fn test_config_file_feeds() {
+        let mut config: ConfigFile = toml::from_str(
+            r#"
+            [profiles.work]
+
+            [feeds."rust-lang/rust"]
+            url = "https://github.com/rust-lang/rust/releases.atom"
+
+            [feeds.blog]
+            url = "https://blog.rust-lang.org/feed.xml"
+            rules = "/home/me/blogs.toml"
+
+            [feeds.work]
+            url = "https://ignored.corp/feed.xml"
+            "#,
+        )
+        .unwrap();
+        let profiles = config.profiles();
+        let feeds = config.feeds(&profiles);
+        assert_eq!(feeds.len(), 2);
+        assert_eq!(feeds[0].name, "blog");
+        assert_eq!(feeds[0].rules_path, PathBuf::from("/home/me/blogs.toml"));
+        assert_eq!(feeds[1].name, "rust-lang/rust");
+        assert_eq!(
+            feeds[1].url,
+            "https://github.com/rust-lang/rust/releases.atom"
+        );
+        assert_eq!(feeds[1].rules_path, rules_path());
+    }
+
+    #[test]
+    fn test_config_file_http() {
+        let config: ConfigFile = toml::from_str(
+            r#"
+            [http]
+            proxy = "http://proxy.corp:3128"
+            ca_cert = "/etc/ssl/corp-root.pem"
+            timeout_secs = 30
+            "#,
+        )
+        .unwrap();
+        let http = config.http.unwrap();
+        assert_eq!(http.proxy.as_deref(), Some("http://proxy.corp:3128"));
+        assert_eq!(http.ca_cert, Some(PathBuf::from("/etc/ssl/corp-root.pem")));
+        assert_eq!(http.timeout_secs, Some(30));
+        assert!(http.no_proxy.is_none() && http.client_cert.is_none());
+        assert!(http.connect_timeout_secs.is_none());
+    }
+
+    #[test]
+    fn test_config_file_retry() {
+        let config: ConfigFile = toml::from_str(
+            r#"
+            [retry]
+            base_delay_ms = 100
+            max_delay_ms = 2000
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.retry,
+            Some(RetryPolicy {
+                max_retries: 3,
+                base_delay_ms: 100,
+                max_delay_ms: 2000,
+            })
+        );
     }
 }
@@ -0,0 +1,272 @@
+//! RSS and Atom feeds: release feeds, blogs, security advisories. Parsed with feed-rs, only the
+//! few elements needed for a notification are kept.
+
+use crate::config::Subscription;
+use crate::models::{ForgeKind, Notification, NotificationState, NotificationType};
+use anyhow::{Context, Result};
+use chrono::{NaiveDateTime, Utc};
+use feed_rs::model::{Link, Person, Text};
+use feed_rs::parser;
+use reqwest::StatusCode;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Feed {
+    pub title: String,
+    pub author: Option<String>,
+    pub updated: Option<NaiveDateTime>,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Entry {
+    /// Atom `id` or RSS `guid`, else the link
+    pub id: String,
+    pub title: String,
+    pub link: String,
+    pub author: Option<String>,
+    pub updated: Option<NaiveDateTime>,
+}
+
+/// Outcome of a conditional GET
+pub enum Fetched {
+    NotModified,
+    Modified { body: String, etag: Option<String> },
+}
+
+/// GET the feed, with the ETag of the last fetch
+pub async fn fetch(http: &reqwest::Client, url: &str, etag: Option<&str>) -> Result<Fetched> {
+    let mut builder = http.get(url).header("User-Agent", "riirview");
+    if let Some(etag) = etag {
+        builder = builder.header("If-None-Match", etag);
+    }
+    let resp = builder.send().await?;
+    if resp.status() == StatusCode::NOT_MODIFIED {
+        return Ok(Fetched::NotModified);
+    }
+    let resp = resp.error_for_status()?;
+    let etag = resp
+        .headers()
+        .get("etag")
+        .and_then(|etag| etag.to_str().ok())
+        .map(String::from);
+    Ok(Fetched::Modified {
+        body: resp.text().await?,
+        etag,
+    })
+}
+
+/// Atom, RSS or JSON feed, with only the elements needed for a notification
+pub fn parse(xml: &str) -> Result<Feed> {
+    // entries without id are identified by their link
+    let parsed = parser::Builder::new()
+        .id_generator(|links, _, _| link(links))
+        .build()
+        .parse(xml.as_bytes())
+        .context("not an RSS or Atom feed")?;
+    Ok(Feed {
+        title: parsed.title.map(text).unwrap_or_default(),
+        author: author(&parsed.authors),
+        updated: parsed
+            .updated
+            .or(parsed.published)
+            .map(|date| date.naive_utc()),
+        entries: parsed
+            .entries
+            .into_iter()
+            .map(|entry| Entry {
+                link: link(&entry.links),
+                id: entry.id,
+                title: entry.title.map(text).unwrap_or_default(),
+                author: author(&entry.authors),
+                updated: entry
+                    .updated
+                    .or(entry.published)
+                    .map(|date| date.naive_utc()),
+            })
+            .collect(),
+    })
+}
+
+/// Entries of the feed as notifications of the `feed` account, unscored. Undated entries take
+/// the date of the feed.
+pub fn notifications(subscription: &Subscription, feed: &Feed) -> Vec<Notification> {
+    let now = Utc::now().naive_utc();
+    feed.entries
+        .iter()
+        .map(|entry| Notification {
+            id: entry.id.clone(),
+            account: subscription.name.clone(),
+            title: entry.title.clone(),
+            repo: subscription.name.clone(),
+            url: entry.link.clone(),
+            type_: NotificationType::FeedEntry,
+            unread: true,
+            updated_at: entry.updated.or(feed.updated).unwrap_or(now),
+            done: false,
+            score: -1, // computed by the scorer
+            score_boost: 0,
+            state: NotificationState::Open,
+            author: entry
+                .author
+                .clone()
+                .or(feed.author.clone())
+                .unwrap_or_default(),
+            reason: "feed".into(),
+            muted: false,
+            review_requested: false,
+            review_decision: None,
+            ci_state: None,
+            milestone: None,
+            comment_author: None,
+            comment_at: None,
+            comment_excerpt: None,
+            comment_url: None,
+            state_reason: None,
+            duplicate_of: None,
+            release_tag: None,
+            prerelease: false,
+            published_at: None,
+            // unused, `forge:` filters leave feed entries out
+            forge: ForgeKind::default(),
+        })
+        .collect()
+}
+
+/// The alternate link is the page, others are enclosures, comments...
+fn link(links: &[Link]) -> String {
+    links
+        .iter()
+        .find(|link| link.rel.as_deref().is_none_or(|rel| rel == "alternate"))
+        .map(|link| link.href.clone())
+        .unwrap_or_default()
+}
+
+/// Atom `author/name` or `dc:creator`. An RSS `author` is an email, with the name in parentheses:
+/// feed-rs keeps it as the email of a person named after the element.
+fn author(authors: &[Person]) -> Option<String> {
+    let person = authors.first()?;
+    let author = match &person.email {
+        Some(email) if person.name == "author" => match email.split_once('(') {
+            Some((_, name)) => name.trim_end_matches(')').to_string(),
+            None => email.clone(),
+        },
+        _ => person.name.clone(),
+    };
+    Some(collapse(&author)).filter(|author| !author.is_empty())
+}
+
+/// Titles spanning several lines
+fn text(text: Text) -> String {
+    collapse(&text.content)
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_atom() {
+        let feed = parse(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xml:lang="en-US">
+  <id>tag:github.com,2008:https://github.com/rust-lang/rust/releases</id>
+  <link type="text/html" rel="alternate" href="https://github.com/rust-lang/rust/releases"/>
+  <title>Release notes from rust</title>
+  <updated>2025-10-30T15:00:00Z</updated>
+  <entry>
+    <id>tag:github.com,2008:Repository/724712/1.91.0</id>
+    <updated>2025-10-30T16:27:13+01:00</updated>
+    <link rel="alternate" type="text/html" href="https://github.com/rust-lang/rust/releases/tag/1.91.0"/>
+    <title>Rust 1.91.0 &amp; friends</title>
+    <content type="html">&lt;p&gt;Language&lt;/p&gt;</content>
+    <author>
+      <name>cuviper</name>
+    </author>
+  </entry>
+  <entry>
+    <id>tag:github.com,2008:Repository/724712/1.90.0</id>
+    <link rel="enclosure" href="https://example.com/1.90.0.tar.gz"/>
+    <link href="https://github.com/rust-lang/rust/releases/tag/1.90.0"/>
+    <title type="html"><![CDATA[Rust <b>1.90.0</b>]]></title>
+  </entry>
+</feed>"#,
+        )
+        .unwrap();
+        assert_eq!(feed.title, "Release notes from rust");
+        assert_eq!(feed.updated.unwrap().to_string(), "2025-10-30 15:00:00");
+        assert_eq!(feed.entries.len(), 2);
+        assert_eq!(
+            feed.entries[0],
+            Entry {
+                id: "tag:github.com,2008:Repository/724712/1.91.0".into(),
+                title: "Rust 1.91.0 & friends".into(),
+                link: "https://github.com/rust-lang/rust/releases/tag/1.91.0".into(),
+                author: Some("cuviper".into()),
+                updated: feed.entries[0].updated,
+            }
+        );
+        assert_eq!(
+            feed.entries[0].updated.unwrap().to_string(),
+            "2025-10-30 15:27:13"
+        );
+        assert_eq!(feed.entries[1].title, "Rust <b>1.90.0</b>");
+        assert_eq!(
+            feed.entries[1].link,
+            "https://github.com/rust-lang/rust/releases/tag/1.90.0"
+        );
+        assert_eq!(feed.entries[1].author, None);
+        assert_eq!(feed.entries[1].updated, None);
+    }
+
+    #[test]
+    fn test_parse_rss() {
+        let feed = parse(
+            r#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:atom="http://www.w3.org/2005/Atom">
+  <channel>
+    <title>Security advisories</title>
+    <atom:link href="https://example.com/feed.xml" rel="self"/>
+    <link>https://example.com</link>
+    <item>
+      <title>CVE-2025-0001:
+        heap overflow</title>
+      <link>https://example.com/advisories/1</link>
+      <guid isPermaLink="false">advisory-1</guid>
+      <author>security@example.com (Security Team)</author>
+      <pubDate>Thu, 30 Oct 2025 10:00:00 GMT</pubDate>
+    </item>
+    <item>
+      <title>Weekly news</title>
+      <link>https://example.com/news/2</link>
+      <dc:creator><![CDATA[Jane Doe]]></dc:creator>
+    </item>
+  </channel>
+</rss>"#,
+        )
+        .unwrap();
+        assert_eq!(feed.title, "Security advisories");
+        assert_eq!(feed.entries.len(), 2);
+        assert_eq!(feed.entries[0].id, "advisory-1");
+        assert_eq!(feed.entries[0].title, "CVE-2025-0001: heap overflow");
+        assert_eq!(feed.entries[0].link, "https://example.com/advisories/1");
+        assert_eq!(feed.entries[0].author.as_deref(), Some("Security Team"));
+        assert_eq!(
+            feed.entries[0].updated.unwrap().to_string(),
+            "2025-10-30 10:00:00"
+        );
+        assert_eq!(feed.entries[1].id, "https://example.com/news/2");
+        assert_eq!(feed.entries[1].author.as_deref(), Some("Jane Doe"));
+
+        assert!(parse("<html><body>not found</body></html>").is_err());
+        assert!(
+            parse(r#"<rss version="2.0"><channel></channel></rss>"#)
+                .unwrap()
+                .entries
+                .is_empty()
+        );
+    }
+}
@@ -6,6 +6,16 @@ pub struct Filter {
     pub author: String,
     pub repo: String,
     pub state: String,
+    pub account: String,
+    /// `is:muted`
+    pub muted: bool,
+    /// `requested` or a review decision
+    pub review: String,
+    pub ci: String,
+    pub label: String,
+    pub assignee: String,
+    /// `GitHub`, `GitLab` or `Gitea`
+    pub forge: String,
 }
 
 impl Filter {
@@ -18,6 +28,13 @@ impl Filter {
                     author: String::new(),
                     repo: String::new(),
                     state: String::new(),
+                    account: String::new(),
+                    muted: false,
+                    review: String::new(),
+                    ci: String::new(),
+                    label: String::new(),
+                    assignee: String::new(),
+                    forge: String::new(),
                 },
                 // Accumulator for title parts
                 Vec::new(),
@@ -37,6 +54,39 @@ impl Filter {
                         "canceled" => "Canceled".to_string(),
                         _ => String::new(), // Invalid state values are ignored
                     };
+                } else if word.starts_with("account:") {
+                    filter.account = word.trim_start_matches("account:").to_string();
+                } else if word == "is:muted" {
+                    filter.muted = true;
+                } else if word.starts_with("review:") {
+                    let review_str = word.trim_start_matches("review:").to_ascii_lowercase();
+                    filter.review = match review_str.as_str() {
+                        "requested" => "requested".to_string(),
+                        "approved" => "Approved".to_string(),
+                        "changes_requested" => "ChangesRequested".to_string(),
+                        "review_required" => "ReviewRequired".to_string(),
+                        _ => String::new(),
+                    };
+                } else if word.starts_with("ci:") {
+                    let ci_str = word.trim_start_matches("ci:").to_ascii_lowercase();
+                    filter.ci = match ci_str.as_str() {
+                        "success" => "Success".to_string(),
+                        "failure" => "Failure".to_string(),
+                        "pending" => "Pending".to_string(),
+                        _ => String::new(),
+                    };
+                } else if word.starts_with("label:") {
+                    filter.label = word.trim_start_matches("label:").to_string();
+                } else if word.starts_with("assignee:") {
+                    filter.assignee = word.trim_start_matches("assignee:").to_string();
+                } else if word.starts_with("forge:") {
+                    let forge_str = word.trim_start_matches("forge:").to_ascii_lowercase();
+                    filter.forge = match forge_str.as_str() {
+                        "github" => "GitHub".to_string(),
+                        "gitlab" => "GitLab".to_string(),
+                        "gitea" | "forgejo" => "Gitea".to_string(),
+                        _ => String::new(),
+                    };
                 } else if word.starts_with("title:") {
                     // Explicit title keyword, the value is added to title parts
                     title_parts.push(word.trim_start_matches("title:").to_string());
@@ -56,6 +106,13 @@ impl Filter {
             author: filter_accumulator.author,
             repo: filter_accumulator.repo,
             state: filter_accumulator.state,
+            account: filter_accumulator.account,
+            muted: filter_accumulator.muted,
+            review: filter_accumulator.review,
+            ci: filter_accumulator.ci,
+            label: filter_accumulator.label,
+            assignee: filter_accumulator.assignee,
+            forge: filter_accumulator.forge,
         })
     }
 }
@@ -127,5 +184,38 @@ mod test {
         assert!(filters.author.is_empty());
         assert!(filters.repo.is_empty());
         assert!(filters.state.is_empty());
+
+        let filters = Filter::parse("account:work state:open fix").unwrap();
+        assert_eq!(filters.title, "fix");
+        assert_eq!(filters.account, "work");
+        assert_eq!(filters.state, "Open");
+        assert!(filters.author.is_empty());
+        assert!(!filters.muted);
+
+        let filters = Filter::parse("is:muted repo:rust").unwrap();
+        assert!(filters.muted);
+        assert_eq!(filters.repo, "rust");
+        assert!(filters.title.is_empty());
+
+        let filters = Filter::parse("review:requested ci:failure").unwrap();
+        assert_eq!(filters.review, "requested");
+        assert_eq!(filters.ci, "Failure");
+        assert!(filters.title.is_empty());
+
+        let filters = Filter::parse("review:Changes_Requested ci:green").unwrap();
+        assert_eq!(filters.review, "ChangesRequested");
+        assert!(filters.ci.is_empty());
+
+        let filters = Filter::parse("label:P0 assignee:JohnDoe crash").unwrap();
+        assert_eq!(filters.label, "P0");
+        assert_eq!(filters.assignee, "JohnDoe");
+        assert_eq!(filters.title, "crash");
+
+        let filters = Filter::parse("forge:GITLAB forge").unwrap();
+        assert_eq!(filters.forge, "GitLab");
+        assert_eq!(filters.title, "forge");
+
+        let filters = Filter::parse("forge:forgejo").unwrap();
+        assert_eq!(filters.forge, "Gitea");
     }
 }
@@ -0,0 +1,161 @@
+//! Servers the notifications are fetched from: GitHub notifications, GitLab To-Do items or Gitea
+//! and Forgejo notifications. A `Forge` lists the threads of a profile, turns them into
+//! notifications and sends the actions back, scoring and storage are the same for every forge.
+
+mod gitea;
+mod github;
+mod gitlab;
+
+pub use gitea::Gitea;
+pub use github::GitHub;
+pub use gitlab::GitLab;
+
+use crate::DbConnection;
+use crate::config::Profile;
+use crate::gh::{Clients, ListingInfo, UpdateStatus};
+use crate::models::{Action, Details, ForgeKind, Notification};
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+use std::future::Future;
+
+pub trait Forge {
+    /// Thread as listed by the server, before enrichment
+    type Item: Send;
+
+    /// Threads updated since `since`, the unread ones without it
+    fn fetch(
+        &self,
+        since: Option<NaiveDateTime>,
+    ) -> impl Future<Output = Result<(Vec<Self::Item>, ListingInfo)>> + Send;
+
+    /// Notifications of the threads with their details, not scored yet
+    fn enrich(
+        &self,
+        connection: &mut DbConnection,
+        items: Vec<Self::Item>,
+    ) -> impl Future<Output = Result<Vec<(Notification, Details)>>> + Send;
+
+    /// Threads of the inbox updated after `since`, read or unread, none when the inbox didn't
+    /// change since `last_modified`
+    fn inbox(
+        &self,
+        since: NaiveDateTime,
+        last_modified: Option<&str>,
+    ) -> impl Future<Output = Result<Option<Inbox>>> + Send;
+
+    fn mark_done(&self, id: &str) -> impl Future<Output = Result<()>> + Send;
+
+    fn mark_read(&self, id: &str) -> impl Future<Output = Result<()>> + Send;
+
+    fn mute(&self, id: &str) -> impl Future<Output = Result<()>> + Send;
+
+    fn unmute(&self, id: &str) -> impl Future<Output = Result<()>> + Send;
+
+    /// Whether the inbox changed since `last_update`, and how long to wait before asking again
+    fn check_update(
+        &self,
+        last_update: NaiveDateTime,
+        last_modified: Option<&str>,
+    ) -> impl Future<Output = Result<UpdateStatus>> + Send;
+
+    /// Login the token authenticates as
+    fn login(&self) -> impl Future<Output = Result<String>> + Send;
+}
+
+/// Threads of the inbox by id with their unread flag
+#[derive(Debug, Default)]
+pub struct Inbox {
+    pub threads: HashMap<String, bool>,
+    /// server `Last-Modified`, sent back as `If-Modified-Since` by the next reconcile
+    pub last_modified: Option<String>,
+}
+
+/// Forge of a profile, according to its `forge` setting
+pub enum ProfileForge {
+    GitHub(GitHub),
+    GitLab(GitLab),
+    Gitea(Gitea),
+}
+
+impl ProfileForge {
+    pub fn new(clients: &Clients, profile: &Profile) -> Result<ProfileForge> {
+        let client = clients.get(profile)?;
+        Ok(match profile.forge {
+            ForgeKind::GitHub => ProfileForge::GitHub(GitHub::new(client, profile.clone())),
+            ForgeKind::GitLab => ProfileForge::GitLab(GitLab::new(client, profile.clone())),
+            ForgeKind::Gitea => ProfileForge::Gitea(Gitea::new(client, profile.clone())),
+        })
+    }
+
+    /// Fetched and enriched, not scored yet
+    pub async fn fetch_notifications(
+        &self,
+        connection: &mut DbConnection,
+        since: Option<NaiveDateTime>,
+    ) -> Result<(Vec<(Notification, Details)>, ListingInfo)> {
+        match self {
+            ProfileForge::GitHub(forge) => fetch_notifications(forge, connection, since).await,
+            ProfileForge::GitLab(forge) => fetch_notifications(forge, connection, since).await,
+            ProfileForge::Gitea(forge) => fetch_notifications(forge, connection, since).await,
+        }
+    }
+
+    pub async fn inbox(
+        &self,
+        since: NaiveDateTime,
+        last_modified: Option<&str>,
+    ) -> Result<Option<Inbox>> {
+        match self {
+            ProfileForge::GitHub(forge) => forge.inbox(since, last_modified).await,
+            ProfileForge::GitLab(forge) => forge.inbox(since, last_modified).await,
+            ProfileForge::Gitea(forge) => forge.inbox(since, last_modified).await,
+        }
+    }
+
+    pub async fn apply(&self, action: Action, id: &str) -> Result<()> {
+        match self {
+            ProfileForge::GitHub(forge) => apply(forge, action, id).await,
+            ProfileForge::GitLab(forge) => apply(forge, action, id).await,
+            ProfileForge::Gitea(forge) => apply(forge, action, id).await,
+        }
+    }
+
+    pub async fn check_update(
+        &self,
+        last_update: NaiveDateTime,
+        last_modified: Option<&str>,
+    ) -> Result<UpdateStatus> {
+        match self {
+            ProfileForge::GitHub(forge) => forge.check_update(last_update, last_modified).await,
+            ProfileForge::GitLab(forge) => forge.check_update(last_update, last_modified).await,
+            ProfileForge::Gitea(forge) => forge.check_update(last_update, last_modified).await,
+        }
+    }
+
+    pub async fn login(&self) -> Result<String> {
+        match self {
+            ProfileForge::GitHub(forge) => forge.login().await,
+            ProfileForge::GitLab(forge) => forge.login().await,
+            ProfileForge::Gitea(forge) => forge.login().await,
+        }
+    }
+}
+
+async fn fetch_notifications<F: Forge>(
+    forge: &F,
+    connection: &mut DbConnection,
+    since: Option<NaiveDateTime>,
+) -> Result<(Vec<(Notification, Details)>, ListingInfo)> {
+    let (items, info) = forge.fetch(since).await?;
+    Ok((forge.enrich(connection, items).await?, info))
+}
+
+async fn apply<F: Forge>(forge: &F, action: Action, id: &str) -> Result<()> {
+    match action {
+        Action::Done => forge.mark_done(id).await,
+        Action::Read => forge.mark_read(id).await,
+        Action::Mute => forge.mute(id).await,
+        Action::Unmute => forge.unmute(id).await,
+    }
+}
@@ -0,0 +1,372 @@
+//! Gitea and Forgejo notifications, eg Codeberg. The API is close to GitHub's, but pages are
+//! numbered with `page` and `limit` and counted by `X-Total-Count`, threads have no reason and
+//! their subject already carries its state. Gitea has no done state nor thread subscription:
+//! marking a thread as done marks it as read, muting is only applied locally.
+
+use super::{Forge, Inbox};
+use crate::DbConnection;
+use crate::config::Profile;
+use crate::gh::{self, ListingInfo, UpdateStatus};
+use crate::models::{Details, ForgeKind, Notification, NotificationState, NotificationType};
+use anyhow::Result;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use futures::StreamExt;
+use futures::stream::iter;
+use log::error;
+use serde::Deserialize;
+
+/// Default maximum page size of Gitea
+const PAGE_LIMIT: usize = 50;
+/// No polling hint either, see `Gitea::check_update`
+const POLL_INTERVAL_SEC: u64 = 60;
+
+pub struct Gitea {
+    client: gh::Client,
+    profile: Profile,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Thread {
+    id: u64,
+    repository: Repository,
+    subject: Subject,
+    unread: bool,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Repository {
+    full_name: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct Subject {
+    title: String,
+    /// API url of the issue or the pull request
+    #[serde(default)]
+    url: String,
+    #[serde(default)]
+    html_url: String,
+    /// `Issue`, `Pull`, `Commit` or `Repository`
+    r#type: String,
+    /// `open`, `closed` or `merged`
+    #[serde(default)]
+    state: String,
+}
+
+/// Issue or pull request, only the fields shared by both
+#[derive(Deserialize, Debug)]
+struct Issue {
+    user: gh::User,
+    #[serde(default)]
+    labels: Vec<gh::Label>,
+    /// `null` without assignee
+    #[serde(default)]
+    assignees: Option<Vec<gh::User>>,
+    #[serde(default)]
+    milestone: Option<gh::Milestone>,
+    /// pull requests of Forgejo and recent Gitea versions
+    #[serde(default)]
+    draft: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct NewCount {
+    new: u64,
+}
+
+impl Gitea {
+    pub fn new(client: gh::Client, profile: Profile) -> Gitea {
+        Gitea { client, profile }
+    }
+
+    /// Every page of `/notifications` with the `query` params, and the server values of the first
+    async fn threads(&self, query: &str) -> Result<(Vec<Thread>, ListingInfo)> {
+        let mut threads = vec![];
+        let mut info = None;
+        for page in 1.. {
+            let url = format!(
+                "{}/notifications?{}limit={}&page={}",
+                self.client.base_url(),
+                query,
+                PAGE_LIMIT,
+                page
+            );
+            let resp = self.client.get(url).await?;
+            info.get_or_insert_with(|| ListingInfo::from_headers(resp.headers()));
+            let total: Option<usize> = resp
+                .headers()
+                .get("x-total-count")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok());
+            let page_threads: Vec<Thread> = resp.json().await?;
+            let last = match total {
+                Some(total) => {
+                    page_threads.is_empty() || threads.len() + page_threads.len() >= total
+                }
+                None => page_threads.len() < PAGE_LIMIT,
+            };
+            threads.extend(page_threads);
+            if last {
+                break;
+            }
+        }
+        Ok((threads, info.unwrap_or_default()))
+    }
+
+    async fn fetch_issue(&self, thread: &Thread) -> Result<Option<Issue>> {
+        if !matches!(thread.subject.r#type.as_str(), "Issue" | "Pull")
+            || thread.subject.url.is_empty()
+        {
+            return Ok(None);
+        }
+        let issue = self
+            .client
+            .get(thread.subject.url.clone())
+            .await?
+            .json()
+            .await?;
+        Ok(Some(issue))
+    }
+}
+
+/// Read and unread threads updated after `since`
+fn since_query(since: NaiveDateTime) -> String {
+    format!(
+        "all=true&since={}&",
+        since.and_utc().to_rfc3339().replace("+00:00", "Z")
+    )
+}
+
+impl Thread {
+    fn notification_type(&self) -> NotificationType {
+        match self.subject.r#type.as_str() {
+            "Issue" => NotificationType::Issue,
+            "Pull" => NotificationType::PullRequest,
+            "Commit" => NotificationType::Commit,
+            _ => NotificationType::Discussion,
+        }
+    }
+
+    /// Closed pull requests are canceled, closed issues resolved. Drafts are flagged by the API
+    /// or by a `WIP:` title.
+    fn state(&self, draft: bool) -> NotificationState {
+        match (self.subject.state.as_str(), self.notification_type()) {
+            ("merged", _) => NotificationState::Resolved,
+            ("closed", NotificationType::PullRequest) => NotificationState::Canceled,
+            ("closed", _) => NotificationState::Resolved,
+            ("open", NotificationType::PullRequest)
+                if draft || self.subject.title.to_lowercase().starts_with("wip:") =>
+            {
+                NotificationState::Draft
+            }
+            _ => NotificationState::Open,
+        }
+    }
+}
+
+impl Forge for Gitea {
+    type Item = Thread;
+
+    async fn fetch(&self, since: Option<NaiveDateTime>) -> Result<(Vec<Thread>, ListingInfo)> {
+        let query = since.map(since_query).unwrap_or_default();
+        self.threads(&query).await
+    }
+
+    async fn enrich(
+        &self,
+        _connection: &mut DbConnection,
+        threads: Vec<Thread>,
+    ) -> Result<Vec<(Notification, Details)>> {
+        // futures are built upfront, a closure in the stream would make it !Send
+        let fetches: Vec<_> = threads
+            .iter()
+            .map(|thread| self.fetch_issue(thread))
+            .collect();
+        let issues: Vec<Result<Option<Issue>>> =
+            iter(fetches).buffered(gh::NB_TASK).collect().await;
+
+        let mut notifications = vec![];
+        for (thread, issue) in threads.into_iter().zip(issues) {
+            // the thread alone is still worth showing
+            let issue = issue.unwrap_or_else(|err| {
+                error!("cannot fetch {}: {:?}", thread.subject.url, err);
+                None
+            });
+            let details = issue
+                .as_ref()
+                .map(|issue| {
+                    Details::new(
+                        issue
+                            .labels
+                            .iter()
+                            .map(|label| label.name.clone())
+                            .collect(),
+                        issue
+                            .assignees
+                            .iter()
+                            .flatten()
+                            .map(|user| user.login.clone())
+                            .collect(),
+                    )
+                })
+                .unwrap_or_default();
+            let notification = Notification {
+                id: thread.id.to_string(),
+                account: self.profile.name.clone(),
+                title: thread.subject.title.trim().into(),
+                repo: thread.repository.full_name.clone(),
+                url: thread.subject.html_url.clone(),
+                type_: thread.notification_type(),
+                unread: thread.unread,
+                updated_at: thread.updated_at.naive_utc(),
+                done: false,
+                score: -1, // computed by the scorer
+                score_boost: 0,
+                state: thread.state(issue.as_ref().is_some_and(|issue| issue.draft)),
+                author: issue
+                    .as_ref()
+                    .map(|issue| issue.user.login.clone())
+                    .unwrap_or_default(),
+                // threads have no reason
+                reason: "subscribed".into(),
+                muted: false,
+                review_requested: false,
+                review_decision: None,
+                ci_state: None,
+                milestone: issue
+                    .as_ref()
+                    .and_then(|issue| issue.milestone.as_ref())
+                    .map(|milestone| milestone.title.clone()),
+                comment_author: None,
+                comment_at: None,
+                comment_excerpt: None,
+                comment_url: None,
+                state_reason: None,
+                duplicate_of: None,
+                release_tag: None,
+                prerelease: false,
+                published_at: None,
+                forge: ForgeKind::Gitea,
+            };
+            notifications.push((notification, details));
+        }
+        Ok(notifications)
+    }
+
+    async fn inbox(
+        &self,
+        since: NaiveDateTime,
+        _last_modified: Option<&str>,
+    ) -> Result<Option<Inbox>> {
+        let (threads, _) = self.threads(&since_query(since)).await?;
+        Ok(Some(Inbox {
+            threads: threads
+                .into_iter()
+                .map(|thread| (thread.id.to_string(), thread.unread))
+                .collect(),
+            last_modified: None,
+        }))
+    }
+
+    /// Read is the closest to done: the thread leaves the unread list, and stays done locally
+    /// until it's unread again
+    async fn mark_done(&self, id: &str) -> Result<()> {
+        self.mark_read(id).await
+    }
+
+    async fn mark_read(&self, id: &str) -> Result<()> {
+        let url = format!(
+            "{}/notifications/threads/{}?to-status=read",
+            self.client.base_url(),
+            id
+        );
+        self.client.patch(url).await?;
+        Ok(())
+    }
+
+    async fn mute(&self, _id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unmute(&self, _id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Synced while there are unread threads, Gitea has no conditional listing
+    async fn check_update(
+        &self,
+        last_update: NaiveDateTime,
+        _last_modified: Option<&str>,
+    ) -> Result<UpdateStatus> {
+        let new_count: NewCount = self
+            .client
+            .get(format!("{}/notifications/new", self.client.base_url()))
+            .await?
+            .json()
+            .await?;
+        Ok(UpdateStatus {
+            need_update: new_count.new > 0,
+            last_update,
+            poll_interval: POLL_INTERVAL_SEC,
+            ratelimit_remaining: u64::MAX,
+            ratelimit_used: 0,
+        })
+    }
+
+    async fn login(&self) -> Result<String> {
+        let user: gh::User = self
+            .client
+            .get(format!("{}/user", self.client.base_url()))
+            .await?
+            .json()
+            .await?;
+        Ok(user.login)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thread(r#type: &str, state: &str, title: &str) -> Thread {
+        serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "repository": {"full_name": "forgejo/forgejo"},
+            "subject": {"title": title, "type": r#type, "state": state},
+            "unread": true,
+            "updated_at": "2025-11-01T10:00:00Z"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_thread_state() {
+        let pull = thread("Pull", "closed", "Fix the build");
+        assert!(matches!(
+            pull.notification_type(),
+            NotificationType::PullRequest
+        ));
+        assert!(matches!(pull.state(false), NotificationState::Canceled));
+        assert!(matches!(
+            thread("Pull", "merged", "Fix").state(false),
+            NotificationState::Resolved
+        ));
+        assert!(matches!(
+            thread("Pull", "open", "WIP: Fix").state(false),
+            NotificationState::Draft
+        ));
+        assert!(matches!(
+            thread("Pull", "open", "Fix").state(true),
+            NotificationState::Draft
+        ));
+        assert!(matches!(
+            thread("Issue", "closed", "Crash").state(false),
+            NotificationState::Resolved
+        ));
+        assert!(matches!(
+            thread("Repository", "", "Transfer").notification_type(),
+            NotificationType::Discussion
+        ));
+    }
+}
@@ -0,0 +1,476 @@
+//! GitHub notifications, enriched with their pull request, issue, release, commit, discussion or
+//! security alert and latest comment
+
+use super::{Forge, Inbox};
+use crate::DbConnection;
+use crate::config::Profile;
+use crate::gh::{self, ListingInfo, UpdateStatus};
+use crate::models::{
+    self, CiState, Details, ForgeKind, HttpCache, Notification, NotificationState, ReviewDecision,
+    StateReason,
+};
+use crate::schema;
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use diesel::dsl::insert_into;
+use diesel::prelude::*;
+use log::{debug, error, info};
+use std::sync::{Arc, Mutex};
+
+pub struct GitHub {
+    client: gh::Client,
+    profile: Profile,
+}
+
+impl GitHub {
+    pub fn new(client: gh::Client, profile: Profile) -> GitHub {
+        GitHub { client, profile }
+    }
+}
+
+impl Forge for GitHub {
+    type Item = gh::Notification;
+
+    async fn fetch(
+        &self,
+        since: Option<NaiveDateTime>,
+    ) -> Result<(Vec<gh::Notification>, ListingInfo)> {
+        gh::fetch_notifications(&self.client, since).await
+    }
+
+    async fn enrich(
+        &self,
+        connection: &mut DbConnection,
+        gh_notifications: Vec<gh::Notification>,
+    ) -> Result<Vec<(Notification, Details)>> {
+        let (client, profile) = (&self.client, &self.profile);
+        let cache = load_http_cache(connection, &gh_notifications)?;
+        let (gh_prs, gh_releases, gh_issues, gh_commits, gh_comments, gh_discussions, gh_alerts) = tokio::join!(
+            gh::fetch_prs(client, &gh_notifications, &cache),
+            gh::fetch_releases(client, &gh_notifications, &cache),
+            gh::fetch_issues(client, &gh_notifications, &cache),
+            gh::fetch_commits(client, &gh_notifications, &cache),
+            gh::fetch_comments(client, &gh_notifications, &cache),
+            gh::fetch_discussions(client, &gh_notifications),
+            gh::fetch_security_alerts(client, &gh_notifications)
+        );
+        let (gh_prs, gh_releases, gh_issues, gh_commits, gh_comments) =
+            (gh_prs?, gh_releases?, gh_issues?, gh_commits?, gh_comments?);
+        let (gh_discussions, gh_alerts) = (gh_discussions?, gh_alerts?);
+        save_http_cache(connection, &cache)?;
+
+        let identity = if gh_prs.iter().any(gh::PullRequest::has_review_requests) {
+            // review requests are unknown without it, not worth failing the sync
+            gh::fetch_identity(client)
+                .await
+                .inspect_err(|err| error!("cannot fetch the user of {}: {:?}", profile.name, err))
+                .ok()
+        } else {
+            None
+        };
+
+        let mut db_notifications = vec![];
+        for gh_notification in gh_notifications {
+            let notif_url = &gh_notification.subject.url.clone().unwrap_or_default();
+            let (mut review_requested, mut review_decision, mut ci_state) = (false, None, None);
+            let (mut details, mut milestone) = (Details::default(), None);
+            let (mut state_reason, mut duplicate_of) = (None, None);
+            let (mut release_tag, mut prerelease, mut published_at) = (None, false, None);
+            let (url, type_, author, state) = match gh_notification.subject.r#type {
+                gh::NotificationType::PullRequest => 'pr: {
+                    let Some(pr) = gh_prs.iter().find(|pr| pr.url == *notif_url) else {
+                        break 'pr unfetched(
+                            &gh_notification,
+                            models::NotificationType::PullRequest,
+                        );
+                    };
+                    if let Some(identity) = &identity {
+                        let owner = gh_notification
+                            .repository
+                            .full_name
+                            .split('/')
+                            .next()
+                            .unwrap_or_default();
+                        review_requested = pr.review_requested(identity, owner);
+                    }
+                    review_decision = pr.review_decision.as_deref().and_then(to_review_decision);
+                    ci_state = pr.ci_state.as_deref().and_then(to_ci_state);
+                    details = self::details(&pr.labels, &pr.assignees);
+                    milestone = pr.milestone.as_ref().map(|m| m.title.clone());
+
+                    (
+                        pr.html_url.clone(),
+                        models::NotificationType::PullRequest,
+                        pr.user.login.clone(),
+                        if pr.state.as_ref() as &str == "closed" {
+                            if pr.merged {
+                                NotificationState::Resolved
+                            } else {
+                                NotificationState::Canceled
+                            }
+                        } else if pr.draft {
+                            NotificationState::Draft
+                        } else {
+                            NotificationState::Open
+                        },
+                    )
+                }
+                gh::NotificationType::Release => 'release: {
+                    let Some(release) =
+                        gh_releases.iter().find(|release| release.url == *notif_url)
+                    else {
+                        break 'release unfetched(
+                            &gh_notification,
+                            models::NotificationType::Release,
+                        );
+                    };
+                    release_tag = Some(release.tag_name.clone()).filter(|tag| !tag.is_empty());
+                    prerelease = release.prerelease;
+                    published_at = release.published_at.map(|date| date.naive_utc());
+                    (
+                        release.html_url.clone(),
+                        models::NotificationType::Release,
+                        release.author.login.clone(),
+                        if release.draft {
+                            NotificationState::Draft
+                        } else {
+                            NotificationState::Open
+                        },
+                    )
+                }
+                gh::NotificationType::Issue => 'issue: {
+                    let Some(issue) = gh_issues.iter().find(|issue| issue.url == *notif_url) else {
+                        break 'issue unfetched(&gh_notification, models::NotificationType::Issue);
+                    };
+                    details = self::details(&issue.labels, &issue.assignees);
+                    milestone = issue.milestone.as_ref().map(|m| m.title.clone());
+                    state_reason = issue.state_reason.as_deref().and_then(to_state_reason);
+                    duplicate_of = issue
+                        .duplicate_of
+                        .as_ref()
+                        .map(|canonical| profile.rewrite_html_url(canonical));
+                    (
+                        issue.html_url.clone(),
+                        models::NotificationType::Issue,
+                        issue.user.login.clone(),
+                        issue_state(&issue.state, state_reason),
+                    )
+                }
+                gh::NotificationType::Commit => 'commit: {
+                    let Some(commit) = gh_commits.iter().find(|commit| commit.url == *notif_url)
+                    else {
+                        break 'commit unfetched(
+                            &gh_notification,
+                            models::NotificationType::Commit,
+                        );
+                    };
+                    (
+                        commit.html_url.clone(),
+                        models::NotificationType::Commit,
+                        commit
+                            .author
+                            .as_ref()
+                            .map(|user| user.login.clone())
+                            .unwrap_or_default(),
+                        NotificationState::Open,
+                    )
+                }
+                gh::NotificationType::Discussion => {
+                    match gh_discussions
+                        .iter()
+                        .find(|discussion| discussion.thread_id == gh_notification.id)
+                    {
+                        Some(discussion) => (
+                            discussion.html_url.clone(),
+                            models::NotificationType::Discussion,
+                            discussion.author.login.clone(),
+                            discussion_state(discussion),
+                        ),
+                        // not found among the latest discussions, or GraphQL is disabled
+                        None => (
+                            gh_notification.fallback_html_url(),
+                            models::NotificationType::Discussion,
+                            "".into(),
+                            NotificationState::Open,
+                        ),
+                    }
+                }
+                gh::NotificationType::CheckSuite => (
+                    gh_notification.fallback_html_url(),
+                    models::NotificationType::CheckSuite,
+                    "".into(),
+                    check_suite_state(&gh_notification.subject.title),
+                ),
+                gh::NotificationType::SecurityAlert => {
+                    match gh_alerts
+                        .iter()
+                        .find(|alert| alert.thread_id == gh_notification.id)
+                    {
+                        Some(alert) => (
+                            format!(
+                                "{}/security/dependabot/{}",
+                                gh_notification.repository.html_url, alert.number
+                            ),
+                            models::NotificationType::SecurityAlert,
+                            "dependabot".into(),
+                            alert_state(&alert.state),
+                        ),
+                        // reading the alerts needs the `security_events` scope or to be an admin
+                        // of the repository
+                        None => (
+                            gh_notification.fallback_html_url(),
+                            models::NotificationType::SecurityAlert,
+                            "".into(),
+                            NotificationState::Open,
+                        ),
+                    }
+                }
+                gh::NotificationType::Unknown => {
+                    info!(
+                        "skip notification {} with unsupported type",
+                        gh_notification.id
+                    );
+                    continue;
+                }
+            };
+            let comment = gh_notification
+                .subject
+                .latest_comment_url
+                .as_ref()
+                .and_then(|latest| gh_comments.iter().find(|c| c.url == *latest));
+            let db_notification = Notification {
+                id: gh_notification.id,
+                account: profile.name.clone(),
+                reason: gh_notification.reason,
+                muted: false,
+                title: gh_notification.subject.title.trim().into(),
+                repo: gh_notification.repository.full_name,
+                unread: gh_notification.unread,
+                updated_at: gh_notification.updated_at,
+                done: false,
+                score: -1, // computed by the scorer
+                score_boost: 0,
+                url: profile.rewrite_html_url(&url),
+                type_,
+                author,
+                state,
+                review_requested,
+                review_decision,
+                ci_state,
+                milestone,
+                comment_author: comment.and_then(|c| c.user.as_ref().map(|u| u.login.clone())),
+                comment_at: comment.and_then(|c| c.date()).map(|date| date.naive_utc()),
+                comment_excerpt: comment.map(gh::Comment::excerpt),
+                comment_url: comment.map(|c| profile.rewrite_html_url(&c.html_url)),
+                state_reason,
+                duplicate_of,
+                release_tag,
+                prerelease,
+                published_at,
+                forge: ForgeKind::GitHub,
+            };
+            db_notifications.push((db_notification, details));
+        }
+        Ok(db_notifications)
+    }
+
+    async fn inbox(
+        &self,
+        since: NaiveDateTime,
+        last_modified: Option<&str>,
+    ) -> Result<Option<Inbox>> {
+        Ok(gh::fetch_inbox(&self.client, since, last_modified)
+            .await?
+            .map(|(threads, info)| Inbox {
+                threads: threads
+                    .into_iter()
+                    .map(|thread| (thread.id, thread.unread))
+                    .collect(),
+                last_modified: info.last_modified,
+            }))
+    }
+
+    async fn mark_done(&self, id: &str) -> Result<()> {
+        gh::mark_as_done(&self.client, &id.to_string()).await
+    }
+
+    async fn mark_read(&self, id: &str) -> Result<()> {
+        gh::mark_as_read(&self.client, &id.to_string()).await
+    }
+
+    async fn mute(&self, id: &str) -> Result<()> {
+        gh::mute_thread(&self.client, &id.to_string()).await
+    }
+
+    async fn unmute(&self, id: &str) -> Result<()> {
+        gh::unmute_thread(&self.client, &id.to_string()).await
+    }
+
+    async fn check_update(
+        &self,
+        last_update: NaiveDateTime,
+        last_modified: Option<&str>,
+    ) -> Result<UpdateStatus> {
+        gh::check_update_and_limit(&self.client, last_update, last_modified).await
+    }
+
+    async fn login(&self) -> Result<String> {
+        Ok(gh::fetch_identity(&self.client).await?.login)
+    }
+}
+
+/// The subject couldn't be fetched, eg a deleted release or a commit gone after a force-push: the
+/// thread alone is still worth showing
+fn unfetched(
+    gh_notification: &gh::Notification,
+    type_: models::NotificationType,
+) -> (String, models::NotificationType, String, NotificationState) {
+    error!(
+        "no subject found for {}: {:?}",
+        gh_notification.id, gh_notification.subject.url
+    );
+    (
+        gh_notification.fallback_html_url(),
+        type_,
+        String::new(),
+        NotificationState::Open,
+    )
+}
+
+/// Sorted and deduplicated, as stored
+fn details(labels: &[gh::Label], assignees: &[gh::User]) -> Details {
+    Details::new(
+        labels.iter().map(|label| label.name.clone()).collect(),
+        assignees.iter().map(|user| user.login.clone()).collect(),
+    )
+}
+
+fn load_http_cache(
+    connection: &mut DbConnection,
+    gh_notifications: &[gh::Notification],
+) -> Result<gh::HttpCache> {
+    let urls: Vec<&String> = gh_notifications
+        .iter()
+        .flat_map(|notif| [&notif.subject.url, &notif.subject.latest_comment_url])
+        .flatten()
+        .collect();
+    let entries = schema::http_cache::table
+        .select(HttpCache::as_select())
+        .filter(schema::http_cache::url.eq_any(urls))
+        .load(connection)?;
+    debug!("{} objects in http cache", entries.len());
+
+    Ok(Arc::new(Mutex::new(
+        entries
+            .into_iter()
+            .map(|entry| {
+                (
+                    entry.url,
+                    gh::CacheEntry {
+                        etag: entry.etag,
+                        body: entry.body,
+                    },
+                )
+            })
+            .collect(),
+    )))
+}
+
+fn save_http_cache(connection: &mut DbConnection, cache: &gh::HttpCache) -> Result<()> {
+    let now = Utc::now().naive_utc();
+    let entries: Vec<HttpCache> = cache
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(cache_url, entry)| HttpCache {
+            url: cache_url.clone(),
+            etag: entry.etag.clone(),
+            body: entry.body.clone(),
+            updated_at: now,
+        })
+        .collect();
+    for entry in entries {
+        insert_into(schema::http_cache::table)
+            .values(&entry)
+            .on_conflict(schema::http_cache::url)
+            .do_update()
+            .set((
+                schema::http_cache::etag.eq(&entry.etag),
+                schema::http_cache::body.eq(&entry.body),
+                schema::http_cache::updated_at.eq(entry.updated_at),
+            ))
+            .execute(connection)?;
+    }
+    Ok(())
+}
+
+/// `reviewDecision` of the pull request, none when the base branch requires no review
+fn to_review_decision(decision: &str) -> Option<ReviewDecision> {
+    match decision {
+        "APPROVED" => Some(ReviewDecision::Approved),
+        "CHANGES_REQUESTED" => Some(ReviewDecision::ChangesRequested),
+        "REVIEW_REQUIRED" => Some(ReviewDecision::ReviewRequired),
+        _ => None,
+    }
+}
+
+/// Rollup of the checks and commit statuses
+fn to_ci_state(rollup: &str) -> Option<CiState> {
+    match rollup {
+        "SUCCESS" => Some(CiState::Success),
+        "FAILURE" | "ERROR" => Some(CiState::Failure),
+        "PENDING" | "EXPECTED" => Some(CiState::Pending),
+        _ => None,
+    }
+}
+
+fn to_state_reason(issue_reason: &str) -> Option<StateReason> {
+    match issue_reason {
+        "completed" => Some(StateReason::Completed),
+        "not_planned" => Some(StateReason::NotPlanned),
+        "duplicate" => Some(StateReason::Duplicate),
+        "reopened" => Some(StateReason::Reopened),
+        _ => None,
+    }
+}
+
+/// Issues closed as not planned or as duplicate are canceled, not resolved
+fn issue_state(issue_state: &str, issue_reason: Option<StateReason>) -> NotificationState {
+    match (issue_state, issue_reason) {
+        ("open", _) => NotificationState::Open,
+        (_, Some(StateReason::NotPlanned | StateReason::Duplicate)) => NotificationState::Canceled,
+        _ => NotificationState::Resolved,
+    }
+}
+
+/// Answered questions are resolved, discussions closed as outdated or duplicate are canceled
+fn discussion_state(discussion: &gh::Discussion) -> NotificationState {
+    match (discussion.closed, discussion.state_reason.as_deref()) {
+        (true, Some("outdated" | "duplicate")) => NotificationState::Canceled,
+        (true, _) => NotificationState::Resolved,
+        (false, _) if discussion.answered == Some(true) => NotificationState::Resolved,
+        _ => NotificationState::Open,
+    }
+}
+
+/// Fixed alerts are resolved, dismissed ones canceled
+fn alert_state(alert_state: &str) -> NotificationState {
+    match alert_state {
+        "fixed" => NotificationState::Resolved,
+        "dismissed" | "auto_dismissed" => NotificationState::Canceled,
+        _ => NotificationState::Open,
+    }
+}
+
+/// Check suites have no API object, their outcome is only found in the title, eg
+/// "CI workflow run failed for main branch"
+fn check_suite_state(subject_title: &str) -> NotificationState {
+    let subject_title = subject_title.to_lowercase();
+    if subject_title.contains("succeeded") {
+        NotificationState::Resolved
+    } else if subject_title.contains("failed") || subject_title.contains("cancelled") {
+        NotificationState::Canceled
+    } else {
+        NotificationState::Open
+    }
+}
@@ -0,0 +1,347 @@
+//! GitLab To-Do items: the pending ones are the inbox, marking one as done removes it. GitLab has
+//! no read state nor subscription on to-do items, reading and muting are only applied locally.
+
+use super::{Forge, Inbox};
+use crate::DbConnection;
+use crate::config::Profile;
+use crate::gh::{self, ListingInfo, UpdateStatus};
+use crate::models::{
+    CiState, Details, ForgeKind, Notification, NotificationState, NotificationType,
+};
+use anyhow::Result;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Deserialize;
+
+/// GitLab has no polling hint, its rate limit is generous enough for this interval
+const POLL_INTERVAL_SEC: u64 = 60;
+
+pub struct GitLab {
+    client: gh::Client,
+    profile: Profile,
+}
+
+impl GitLab {
+    pub fn new(client: gh::Client, profile: Profile) -> GitLab {
+        GitLab { client, profile }
+    }
+
+    fn pending_url(&self) -> String {
+        format!(
+            "{}/todos?state=pending&per_page=100",
+            self.client.base_url()
+        )
+    }
+
+    async fn pending_todos(&self) -> Result<(Vec<Todo>, ListingInfo)> {
+        gh::fetch_pages(&self.client, self.pending_url()).await
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Todo {
+    id: u64,
+    #[serde(default)]
+    project: Option<Project>,
+    #[serde(default)]
+    author: Option<User>,
+    /// `assigned`, `mentioned`, `review_requested`, `build_failed`, `marked`...
+    action_name: String,
+    /// `MergeRequest`, `Issue`, `Commit`, `Epic`...
+    target_type: String,
+    #[serde(default)]
+    target: Option<Target>,
+    target_url: String,
+    #[serde(default)]
+    body: Option<String>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Project {
+    path_with_namespace: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct User {
+    username: String,
+}
+
+/// Merge request, issue or epic the item is about, commits only have some of these fields
+#[derive(Deserialize, Debug, Default)]
+struct Target {
+    #[serde(default)]
+    title: Option<String>,
+    /// `opened`, `closed`, `merged` or `locked`
+    #[serde(default)]
+    state: Option<String>,
+    #[serde(default)]
+    author: Option<User>,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    labels: Vec<String>,
+    #[serde(default)]
+    assignees: Vec<User>,
+    #[serde(default)]
+    milestone: Option<gh::Milestone>,
+    #[serde(default)]
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl Todo {
+    /// The item, or its target when it changed since the item was created
+    fn updated_at(&self) -> NaiveDateTime {
+        self.target
+            .as_ref()
+            .and_then(|target| target.updated_at)
+            .map_or(self.updated_at, |date| date.max(self.updated_at))
+            .naive_utc()
+    }
+
+    fn notification_type(&self) -> NotificationType {
+        match self.target_type.as_str() {
+            "MergeRequest" => NotificationType::PullRequest,
+            "Issue" | "WorkItem" | "Epic" => NotificationType::Issue,
+            "Commit" => NotificationType::Commit,
+            _ => NotificationType::Discussion,
+        }
+    }
+
+    /// Closed merge requests are canceled, closed issues resolved
+    fn state(&self, target: &Target) -> NotificationState {
+        match (target.state.as_deref(), &self.notification_type()) {
+            (Some("merged"), _) => NotificationState::Resolved,
+            (Some("closed"), NotificationType::PullRequest) => NotificationState::Canceled,
+            (Some("closed"), _) => NotificationState::Resolved,
+            _ if target.draft => NotificationState::Draft,
+            _ => NotificationState::Open,
+        }
+    }
+
+    /// Same names as the reasons of GitHub notifications, so that the rules apply to both
+    fn reason(&self) -> String {
+        match self.action_name.as_str() {
+            "assigned" => "assign",
+            "mentioned" | "directly_addressed" => "mention",
+            "build_failed" => "ci_activity",
+            "marked" => "manual",
+            action => action,
+        }
+        .to_string()
+    }
+}
+
+impl Forge for GitLab {
+    type Item = Todo;
+
+    /// Every pending item is listed, the ones not updated since `since` are left out
+    async fn fetch(&self, since: Option<NaiveDateTime>) -> Result<(Vec<Todo>, ListingInfo)> {
+        let (todos, info) = self.pending_todos().await?;
+        let todos = todos
+            .into_iter()
+            .filter(|todo| since.is_none_or(|since| todo.updated_at() > since))
+            .collect();
+        Ok((todos, info))
+    }
+
+    async fn enrich(
+        &self,
+        _connection: &mut DbConnection,
+        todos: Vec<Todo>,
+    ) -> Result<Vec<(Notification, Details)>> {
+        Ok(todos
+            .into_iter()
+            .map(|todo| {
+                let target = todo.target.as_ref();
+                let default_target = Target::default();
+                let target_or_default = target.unwrap_or(&default_target);
+                let details = Details::new(
+                    target_or_default.labels.clone(),
+                    target_or_default
+                        .assignees
+                        .iter()
+                        .map(|user| user.username.clone())
+                        .collect(),
+                );
+                let notification = Notification {
+                    id: todo.id.to_string(),
+                    account: self.profile.name.clone(),
+                    title: target
+                        .and_then(|target| target.title.clone())
+                        .or(todo.body.clone())
+                        .unwrap_or_default()
+                        .trim()
+                        .into(),
+                    repo: todo
+                        .project
+                        .as_ref()
+                        .map(|project| project.path_with_namespace.clone())
+                        .unwrap_or_default(),
+                    url: todo.target_url.clone(),
+                    type_: todo.notification_type(),
+                    unread: true,
+                    updated_at: todo.updated_at(),
+                    done: false,
+                    score: -1, // computed by the scorer
+                    score_boost: 0,
+                    state: todo.state(target_or_default),
+                    author: target
+                        .and_then(|target| target.author.as_ref())
+                        .or(todo.author.as_ref())
+                        .map(|user| user.username.clone())
+                        .unwrap_or_default(),
+                    reason: todo.reason(),
+                    muted: false,
+                    review_requested: todo.action_name == "review_requested"
+                        && target_or_default.state.as_deref() == Some("opened"),
+                    review_decision: None,
+                    ci_state: (todo.action_name == "build_failed").then_some(CiState::Failure),
+                    milestone: target_or_default
+                        .milestone
+                        .as_ref()
+                        .map(|milestone| milestone.title.clone()),
+                    comment_author: None,
+                    comment_at: None,
+                    comment_excerpt: None,
+                    comment_url: None,
+                    state_reason: None,
+                    duplicate_of: None,
+                    release_tag: None,
+                    prerelease: false,
+                    published_at: None,
+                    forge: ForgeKind::GitLab,
+                };
+                (notification, details)
+            })
+            .collect())
+    }
+
+    /// Pending items only: done ones are gone from the inbox, none is read
+    async fn inbox(
+        &self,
+        _since: NaiveDateTime,
+        _last_modified: Option<&str>,
+    ) -> Result<Option<Inbox>> {
+        let (todos, _) = self.pending_todos().await?;
+        Ok(Some(Inbox {
+            threads: todos
+                .into_iter()
+                .map(|todo| (todo.id.to_string(), true))
+                .collect(),
+            last_modified: None,
+        }))
+    }
+
+    async fn mark_done(&self, id: &str) -> Result<()> {
+        let url = format!("{}/todos/{}/mark_as_done", self.client.base_url(), id);
+        self.client.post(url, &serde_json::json!({})).await?;
+        Ok(())
+    }
+
+    async fn mark_read(&self, _id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn mute(&self, _id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unmute(&self, _id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// No conditional request on the to-do list: items come newest first, a sync is needed when
+    /// the first page holds one updated since `last_update`
+    async fn check_update(
+        &self,
+        last_update: NaiveDateTime,
+        _last_modified: Option<&str>,
+    ) -> Result<UpdateStatus> {
+        let todos: Vec<Todo> = self.client.get(self.pending_url()).await?.json().await?;
+        Ok(UpdateStatus {
+            need_update: todos
+                .iter()
+                .any(|todo| todo.updated_at.naive_utc() > last_update),
+            last_update,
+            poll_interval: POLL_INTERVAL_SEC,
+            ratelimit_remaining: u64::MAX,
+            ratelimit_used: 0,
+        })
+    }
+
+    async fn login(&self) -> Result<String> {
+        let user: User = self
+            .client
+            .get(format!("{}/user", self.client.base_url()))
+            .await?
+            .json()
+            .await?;
+        Ok(user.username)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_todo() {
+        let todo: Todo = serde_json::from_str(
+            r#"{
+                "id": 102,
+                "project": {"id": 2, "path_with_namespace": "gitlab-org/gitlab"},
+                "author": {"username": "root"},
+                "action_name": "review_requested",
+                "target_type": "MergeRequest",
+                "target": {
+                    "title": "Add a to-do API",
+                    "state": "closed",
+                    "author": {"username": "maxine"},
+                    "draft": false,
+                    "labels": ["backend"],
+                    "assignees": [],
+                    "updated_at": "2025-11-02T10:00:00.000Z"
+                },
+                "target_url": "https://gitlab.com/gitlab-org/gitlab/-/merge_requests/7",
+                "state": "pending",
+                "updated_at": "2025-11-01T10:00:00.000Z"
+            }"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            todo.notification_type(),
+            NotificationType::PullRequest
+        ));
+        assert!(matches!(
+            todo.state(todo.target.as_ref().unwrap()),
+            NotificationState::Canceled
+        ));
+        assert_eq!(todo.reason(), "review_requested");
+        assert_eq!(
+            todo.updated_at().to_string(),
+            "2025-11-02 10:00:00".to_string()
+        );
+
+        let todo: Todo = serde_json::from_str(
+            r#"{
+                "id": 103,
+                "action_name": "directly_addressed",
+                "target_type": "Issue",
+                "target": {"title": "Crash", "state": "closed"},
+                "target_url": "https://gitlab.com/group/project/-/issues/1",
+                "updated_at": "2025-11-01T10:00:00.000Z"
+            }"#,
+        )
+        .unwrap();
+        assert!(matches!(todo.notification_type(), NotificationType::Issue));
+        assert!(matches!(
+            todo.state(todo.target.as_ref().unwrap()),
+            NotificationState::Resolved
+        ));
+        assert_eq!(todo.reason(), "mention");
+        assert_eq!(
+            todo.updated_at().to_string(),
+            "2025-11-01 10:00:00".to_string()
+        );
+    }
+}
@@ -1,18 +1,35 @@
-use crate::config::Config;
+mod graphql;
+mod link;
+mod ratelimit;
+pub mod recording;
+mod retry;
+mod token;
+
+use crate::config::{Config, Enrichment, HttpConfig, Profile, Recording};
+use crate::models::ForgeKind;
 use anyhow::Result;
 use anyhow::anyhow;
-use chrono::{NaiveDateTime, Utc};
+use anyhow::{Context, bail};
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
 use core::fmt;
 use futures::StreamExt;
 use futures::TryStreamExt;
 use futures::stream::iter;
 use log::{debug, error, info};
-use regex::Regex;
+use ratelimit::Scheduler;
+use recording::Tape;
+use reqwest::RequestBuilder;
 use reqwest::Response;
 use reqwest::StatusCode;
 use reqwest::header::HeaderMap;
-use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::OnceCell;
 use url::Url;
 
 #[derive(Deserialize, Debug)]
@@ -42,11 +59,20 @@ mod my_date_format {
     }
 }
 
-#[derive(Deserialize, Debug, PartialEq)]
+#[derive(Deserialize, Debug, PartialEq, Clone, Copy)]
 pub enum NotificationType {
     PullRequest,
     Release,
     Issue,
+    Discussion,
+    CheckSuite,
+    Commit,
+    #[serde(
+        rename = "RepositoryVulnerabilityAlert",
+        alias = "RepositoryDependabotAlertsThread",
+        alias = "SecurityAdvisory"
+    )]
+    SecurityAlert,
     #[serde(other)]
     Unknown,
 }
@@ -55,15 +81,39 @@ pub enum NotificationType {
 pub struct Subject {
     pub title: String,
     pub url: Option<String>,
+    /// the subject itself when nobody commented since it was created or updated
+    pub latest_comment_url: Option<String>,
     pub r#type: NotificationType,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct Repository {
     pub full_name: String,
+    pub html_url: String,
 }
 
-#[derive(Deserialize, Debug)]
+impl Notification {
+    /// Web page for subjects that have no API object to fetch (discussions, check suites and
+    /// security alerts come with a null `subject.url`)
+    pub fn fallback_html_url(&self) -> String {
+        let repo_url = &self.repository.html_url;
+        match self.subject.r#type {
+            NotificationType::Discussion => match Url::parse(&format!("{repo_url}/discussions")) {
+                Ok(mut url) => {
+                    url.query_pairs_mut()
+                        .append_pair("discussions_q", &self.subject.title);
+                    url.to_string()
+                }
+                Err(_) => repo_url.clone(),
+            },
+            NotificationType::CheckSuite => format!("{repo_url}/actions"),
+            NotificationType::SecurityAlert => format!("{repo_url}/security/dependabot"),
+            _ => repo_url.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
 pub struct PullRequest {
     pub url: String,
     pub html_url: String,
@@ -72,28 +122,214 @@ pub struct PullRequest {
     pub draft: bool,
     pub merged: bool,
     pub user: User,
+    #[serde(default)]
+    pub requested_reviewers: Vec<User>,
+    #[serde(default)]
+    pub requested_teams: Vec<Team>,
+    /// `APPROVED`, `CHANGES_REQUESTED` or `REVIEW_REQUIRED`, only known through GraphQL
+    #[serde(default)]
+    pub review_decision: Option<String>,
+    /// check rollup of the last commit (`SUCCESS`, `FAILURE`, `PENDING`...), only known through
+    /// GraphQL
+    #[serde(default)]
+    pub ci_state: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    #[serde(default)]
+    pub assignees: Vec<User>,
+    #[serde(default)]
+    pub milestone: Option<Milestone>,
 }
 
-#[derive(Deserialize, Debug)]
+impl PullRequest {
+    pub fn has_review_requests(&self) -> bool {
+        !self.requested_reviewers.is_empty() || !self.requested_teams.is_empty()
+    }
+
+    /// `identity` or one of its teams is still asked for a review, `owner` is the owner of the
+    /// repository, the organization of teams missing theirs
+    pub fn review_requested(&self, identity: &Identity, owner: &str) -> bool {
+        self.requested_reviewers
+            .iter()
+            .any(|user| user.login.eq_ignore_ascii_case(&identity.login))
+            || self.requested_teams.iter().any(|team| {
+                let team = team.full_slug(owner);
+                identity
+                    .teams
+                    .iter()
+                    .any(|slug| slug.eq_ignore_ascii_case(&team))
+            })
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Team {
+    pub slug: String,
+    #[serde(default)]
+    pub organization: Option<User>,
+}
+
+impl Team {
+    /// `org/slug`
+    fn full_slug(&self, default_org: &str) -> String {
+        let org = self
+            .organization
+            .as_ref()
+            .map_or(default_org, |org| org.login.as_str());
+        format!("{org}/{}", self.slug)
+    }
+}
+
+/// The authenticated user and its teams as `org/slug`
+#[derive(Debug, Default, Clone)]
+pub struct Identity {
+    pub login: String,
+    pub teams: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
 pub struct User {
     pub login: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct Release {
     pub url: String,
     pub html_url: String,
     pub author: User,
+    #[serde(default)]
+    pub tag_name: String,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub published_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Commit {
+    pub url: String,
+    pub html_url: String,
+    pub author: Option<User>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
 pub struct Issue {
     pub url: String,
     pub html_url: String,
     pub user: User,
     pub state: String,
+    /// `completed`, `not_planned`, `duplicate` or `reopened`
+    #[serde(default)]
+    pub state_reason: Option<String>,
+    /// html url of the canonical issue, only known through GraphQL
+    #[serde(default)]
+    pub duplicate_of: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    #[serde(default)]
+    pub assignees: Vec<User>,
+    #[serde(default)]
+    pub milestone: Option<Milestone>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Label {
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Milestone {
+    pub title: String,
 }
 
+/// Discussions have no `subject.url` nor REST API, they are found by title with GraphQL
+#[derive(Debug)]
+pub struct Discussion {
+    pub thread_id: String,
+    pub html_url: String,
+    pub author: User,
+    pub closed: bool,
+    /// `resolved`, `outdated`, `duplicate` or `reopened`
+    pub state_reason: Option<String>,
+    /// `None` outside of the question and answer categories
+    pub answered: Option<bool>,
+}
+
+/// The Dependabot alert of the package named in the title of a security alert notification
+#[derive(Debug)]
+pub struct SecurityAlert {
+    pub thread_id: String,
+    pub number: i64,
+    /// `open`, `fixed`, `dismissed` or `auto_dismissed`
+    pub state: String,
+}
+
+/// An issue comment, a review comment or a review: whatever `latest_comment_url` points to
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Comment {
+    pub url: String,
+    pub html_url: String,
+    pub user: Option<User>,
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    /// reviews have no creation date
+    #[serde(default)]
+    pub submitted_at: Option<DateTime<Utc>>,
+}
+
+const EXCERPT_LEN: usize = 120;
+
+impl Comment {
+    pub fn date(&self) -> Option<DateTime<Utc>> {
+        self.created_at.or(self.submitted_at)
+    }
+
+    /// Beginning of the body as plain text, without quotes, html comments and markdown emphasis
+    pub fn excerpt(&self) -> String {
+        let mut body = self.body.as_deref().unwrap_or_default();
+        let mut text = String::new();
+        // html comments are used by templates and bots
+        while let Some(start) = body.find("<!--") {
+            text.push_str(&body[..start]);
+            body = match body[start..].find("-->") {
+                Some(end) => &body[start + end + 3..],
+                None => "",
+            };
+        }
+        text.push_str(body);
+
+        let words: Vec<String> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('>') && !line.starts_with("```"))
+            .flat_map(str::split_whitespace)
+            .map(|word| word.replace("**", "").replace('`', ""))
+            .map(|word| word.trim_matches(['*', '_']).to_string())
+            .filter(|word| !word.is_empty() && !word.chars().all(|c| c == '#'))
+            .collect();
+        let text = words.join(" ");
+        if text.chars().count() > EXCERPT_LEN {
+            let truncated: String = text.chars().take(EXCERPT_LEN - 1).collect();
+            format!("{}…", truncated.trim_end())
+        } else {
+            text
+        }
+    }
+}
+
+/// ETag and trimmed body of an API object, by url
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub etag: String,
+    pub body: String,
+}
+
+pub type HttpCache = Arc<Mutex<HashMap<String, CacheEntry>>>;
+
 #[derive(Debug)]
 pub struct UpdateStatus {
     pub need_update: bool,
@@ -103,32 +339,236 @@ pub struct UpdateStatus {
     pub ratelimit_used: u64,
 }
 
-struct Client {
+impl UpdateStatus {
+    /// Status of two accounts: update if any needs it, with the most restrictive limits
+    pub fn merge(self, other: UpdateStatus) -> UpdateStatus {
+        UpdateStatus {
+            need_update: self.need_update || other.need_update,
+            last_update: self.last_update.max(other.last_update),
+            poll_interval: self.poll_interval.max(other.poll_interval),
+            ratelimit_remaining: self.ratelimit_remaining.min(other.ratelimit_remaining),
+            ratelimit_used: self.ratelimit_used.max(other.ratelimit_used),
+        }
+    }
+}
+
+/// Server values returned with the first page of notifications
+#[derive(Debug, Default, Clone)]
+pub struct ListingInfo {
+    /// `Date`, server clock when the listing was built
+    pub date: Option<NaiveDateTime>,
+    pub last_modified: Option<String>,
+    pub poll_interval: Option<u64>,
+    pub ratelimit_remaining: Option<u64>,
+    pub ratelimit_used: Option<u64>,
+}
+
+impl ListingInfo {
+    pub(crate) fn from_headers(headers: &HeaderMap) -> ListingInfo {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        ListingInfo {
+            date: header("date")
+                .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+                .map(|date| date.naive_utc()),
+            last_modified: header("last-modified").map(str::to_string),
+            poll_interval: header("x-poll-interval").and_then(|value| value.parse().ok()),
+            ratelimit_remaining: header("x-ratelimit-remaining")
+                .and_then(|value| value.parse().ok()),
+            ratelimit_used: header("x-ratelimit-used").and_then(|value| value.parse().ok()),
+        }
+    }
+}
+
+/// Authenticated client of a profile, cheap to clone: the connection pool is shared
+#[derive(Clone)]
+pub struct Client {
     base_url: String,
+    profile: Profile,
     headers: HeaderMap,
     client: reqwest::Client,
+    /// fetched on first use, it doesn't change during a session
+    identity: Arc<OnceCell<Identity>>,
+}
+
+/// HTTP client shared by the profiles, with the proxy, certificates and timeouts of the config
+pub fn http_client(config: &HttpConfig) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(proxy) = &config.proxy {
+        let no_proxy = config
+            .no_proxy
+            .as_deref()
+            .and_then(reqwest::NoProxy::from_string);
+        builder = builder.proxy(
+            reqwest::Proxy::all(proxy)
+                .with_context(|| format!("invalid proxy {proxy}"))?
+                .no_proxy(no_proxy),
+        );
+    }
+    for cert in ca_certs(config)? {
+        builder = builder.add_root_certificate(cert);
+    }
+    match (&config.client_cert, &config.client_key) {
+        (Some(cert), Some(key)) => {
+            let cert = fs::read(cert).with_context(|| format!("cannot read {}", cert.display()))?;
+            let key = fs::read(key).with_context(|| format!("cannot read {}", key.display()))?;
+            builder = builder.identity(
+                reqwest::Identity::from_pkcs8_pem(&cert, &key)
+                    .context("invalid client certificate or key")?,
+            );
+        }
+        (None, None) => {}
+        _ => bail!("client_cert and client_key must be set together"),
+    }
+    if let Some(secs) = config.connect_timeout_secs {
+        builder = builder.connect_timeout(Duration::from_secs(secs));
+    }
+    if let Some(secs) = config.timeout_secs {
+        builder = builder.timeout(Duration::from_secs(secs));
+    }
+    Ok(builder.build()?)
+}
+
+fn ca_certs(config: &HttpConfig) -> Result<Vec<reqwest::Certificate>> {
+    let Some(path) = &config.ca_cert else {
+        return Ok(vec![]);
+    };
+    let pem = fs::read(path).with_context(|| format!("cannot read {}", path.display()))?;
+    let certs = reqwest::Certificate::from_pem_bundle(&pem)
+        .with_context(|| format!("invalid PEM bundle {}", path.display()))?;
+    if certs.is_empty() {
+        bail!("no certificate in {}", path.display());
+    }
+    Ok(certs)
+}
+
+/// Network settings actually used, as `(setting, value)`: the proxy may come from the environment
+pub fn describe_http(config: &HttpConfig) -> Vec<(&'static str, String)> {
+    let env = |names: &[&str]| {
+        names.iter().find_map(|name| {
+            std::env::var(name)
+                .ok()
+                .map(|value| format!("{value} ({name})"))
+        })
+    };
+    let proxy = match &config.proxy {
+        Some(proxy) => format!("{proxy} (config)"),
+        None => {
+            env(&["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]).unwrap_or("none".into())
+        }
+    };
+    let no_proxy = match (&config.proxy, &config.no_proxy) {
+        (Some(_), Some(no_proxy)) => format!("{no_proxy} (config)"),
+        (Some(_), None) => "none".into(),
+        (None, _) => env(&["NO_PROXY", "no_proxy"]).unwrap_or("none".into()),
+    };
+    let ca = match ca_certs(config) {
+        Ok(certs) if certs.is_empty() => "system roots".into(),
+        Ok(certs) => format!(
+            "system roots and {} certificate(s) from {}",
+            certs.len(),
+            config.ca_cert.as_ref().unwrap().display()
+        ),
+        Err(err) => format!("error: {err:#}"),
+    };
+    let client_cert = match &config.client_cert {
+        Some(cert) => cert.display().to_string(),
+        None => "none".into(),
+    };
+    let timeout = |secs: Option<u64>| secs.map_or("none".into(), |secs| format!("{secs}s"));
+    vec![
+        ("proxy", proxy),
+        ("no proxy", no_proxy),
+        ("ca", ca),
+        ("client certificate", client_cert),
+        ("connect timeout", timeout(config.connect_timeout_secs)),
+        ("timeout", timeout(config.timeout_secs)),
+    ]
+}
+
+/// Clients of the profiles, created on first use and kept for the session so that connections and
+/// tokens are reused. Cheap to clone.
+#[derive(Clone)]
+pub struct Clients {
+    http: reqwest::Client,
+    clients: Arc<Mutex<HashMap<String, Client>>>,
+}
+
+impl Clients {
+    /// With the `[http]` settings of the config. Invalid ones are an error rather than reqwest
+    /// defaults: the proxy or the certificates may be required to reach the server.
+    pub fn from_config() -> Result<Clients> {
+        let http = http_client(&Config::get().http)
+            .context("invalid [http] settings, see riirview doctor")?;
+        Ok(Clients::new(http))
+    }
+
+    pub fn new(http: reqwest::Client) -> Clients {
+        Clients {
+            http,
+            clients: Arc::default(),
+        }
+    }
+
+    pub fn get(&self, profile: &Profile) -> Result<Client, Error> {
+        let mut clients = self.clients.lock().unwrap();
+        if let Some(client) = clients.get(&profile.name) {
+            return Ok(client.clone());
+        }
+        let client = Client::with_http(profile, self.http.clone())?;
+        clients.insert(profile.name.clone(), client.clone());
+        Ok(client)
+    }
+
+    /// Shared HTTP client, for the requests outside of a profile like feeds
+    pub fn http(&self) -> &reqwest::Client {
+        &self.http
+    }
 }
 
 impl Client {
-    pub fn new() -> Result<Client, Error> {
-        let client = reqwest::Client::new();
-        let token = dotenvy::var("GH_TOKEN").map_err(|_| Error::MissingToken)?;
+    pub fn new(profile: &Profile) -> Result<Client, Error> {
+        let http = http_client(&Config::get().http)
+            .map_err(|err| Error::InvalidHttpConfig(format!("{err:#}")))?;
+        Client::with_http(profile, http)
+    }
+
+    fn with_http(profile: &Profile, client: reqwest::Client) -> Result<Client, Error> {
+        // nothing is sent when replaying, the recording is shared without the token
+        let token = match Config::get().recording {
+            Some(Recording::Replay(_)) => "replay".to_string(),
+            _ => token::resolve(profile)?,
+        };
         let mut headers = HeaderMap::new();
         headers.insert("User-Agent", "riirview".parse().unwrap());
-        headers.insert("Accept", "application/vnd.github+json".parse().unwrap());
+        let accept = match profile.forge {
+            ForgeKind::GitHub => "application/vnd.github+json",
+            ForgeKind::GitLab | ForgeKind::Gitea => "application/json",
+        };
+        headers.insert("Accept", accept.parse().unwrap());
+        // Gitea only documents the `token` scheme for access tokens
+        let scheme = match profile.forge {
+            ForgeKind::Gitea => "token",
+            ForgeKind::GitHub | ForgeKind::GitLab => "Bearer",
+        };
         headers.insert(
             "Authorization",
-            format!("Bearer {}", token).parse().unwrap(),
+            format!("{} {}", scheme, token).parse().unwrap(),
         );
 
-        let config = Config::get();
         Ok(Client {
-            base_url: config.github_base_url,
+            base_url: profile.api_url.clone(),
+            profile: profile.clone(),
             headers,
             client,
+            identity: Arc::default(),
         })
     }
 
+    /// REST API of the profile
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
     pub async fn get_notifications(&self, last_update: Option<NaiveDateTime>) -> Result<Response> {
         let url = match last_update {
             Some(last_update) => {
@@ -149,10 +589,7 @@ impl Client {
 
     pub async fn mark_notification_done(&self, id: &String) -> Result<()> {
         let url = format!("{}/notifications/threads/{}", self.base_url, id);
-        let res = self.del(url).await;
-        if res.is_err() {
-            error!("error deleting notification: {}", res.unwrap_err());
-        }
+        self.del(url).await?;
         Ok(())
     }
 
@@ -162,59 +599,102 @@ impl Client {
         Ok(())
     }
 
-    pub async fn need_update(&self, last_update: NaiveDateTime) -> Result<Response> {
+    /// Ignore the thread: no more notifications for it
+    pub async fn mute_thread(&self, id: &String) -> Result<()> {
+        let url = format!(
+            "{}/notifications/threads/{}/subscription",
+            self.base_url, id
+        );
+        self.put(url, &serde_json::json!({ "ignored": true }))
+            .await?;
+        Ok(())
+    }
+
+    /// Notifications for the thread again. Deleting the subscription would stop them as well.
+    pub async fn unmute_thread(&self, id: &String) -> Result<()> {
+        let url = format!(
+            "{}/notifications/threads/{}/subscription",
+            self.base_url, id
+        );
+        self.put(url, &serde_json::json!({ "ignored": false }))
+            .await?;
+        Ok(())
+    }
+
+    /// `last_modified` is sent as is, otherwise `last_update` is formatted as an HTTP date
+    pub async fn need_update(
+        &self,
+        last_update: NaiveDateTime,
+        last_modified: Option<&str>,
+    ) -> Result<Response> {
         let url = format!("{}/notifications", self.base_url);
         let mut custom_headers = HeaderMap::new();
-        let since = last_update
-            .and_local_timezone(Utc)
-            .single()
-            .unwrap()
-            .to_rfc2822();
+        let since = match last_modified {
+            Some(last_modified) => last_modified.to_string(),
+            None => last_update.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+        };
         custom_headers.insert("If-Modified-Since", since.parse()?);
         self.head(url, Some(custom_headers)).await
     }
 
     pub async fn get(&self, url: String) -> Result<Response> {
-        let url = Config::get().rewrite_url(&url);
+        self.get_with_headers(url, None).await
+    }
+
+    async fn get_with_headers(&self, url: String, headers: Option<HeaderMap>) -> Result<Response> {
+        let url = self.profile.rewrite_url(&url);
         info!("GET {}", &url);
-        let resp = self
-            .client
-            .get(&url)
-            .headers(self.headers.clone())
-            .send()
-            .await?;
-        debug!("status {} for {}", resp.status(), &url);
-        Ok(resp.error_for_status()?)
+        let builder = self.client.get(&url).headers(self.headers.clone());
+
+        let builder = match headers {
+            Some(custom_header) => builder.headers(custom_header),
+            _ => builder,
+        };
+        self.send(builder, &url).await
     }
 
     async fn del(&self, url: String) -> Result<Response> {
-        let url = Config::get().rewrite_url(&url);
+        let url = self.profile.rewrite_url(&url);
         info!("DEL {}", &url);
-        let resp = self
+        let builder = self.client.delete(&url).headers(self.headers.clone());
+        self.send(builder, &url).await
+    }
+
+    pub(crate) async fn patch(&self, url: String) -> Result<Response> {
+        let url = self.profile.rewrite_url(&url);
+        info!("PATCH {}", &url);
+        let builder = self.client.patch(&url).headers(self.headers.clone());
+        self.send(builder, &url).await
+    }
+
+    pub(crate) async fn post<T: Serialize + ?Sized>(
+        &self,
+        url: String,
+        body: &T,
+    ) -> Result<Response> {
+        let url = self.profile.rewrite_url(&url);
+        info!("POST {}", &url);
+        let builder = self
             .client
-            .delete(&url)
+            .post(&url)
             .headers(self.headers.clone())
-            .send()
-            .await?;
-        debug!("status {} for {}", resp.status(), &url);
-        Ok(resp.error_for_status()?)
+            .json(body);
+        self.send(builder, &url).await
     }
 
-    async fn patch(&self, url: String) -> Result<Response> {
-        let url = Config::get().rewrite_url(&url);
-        info!("PATCH {}", &url);
-        let resp = self
+    async fn put<T: Serialize + ?Sized>(&self, url: String, body: &T) -> Result<Response> {
+        let url = self.profile.rewrite_url(&url);
+        info!("PUT {}", &url);
+        let builder = self
             .client
-            .patch(&url)
+            .put(&url)
             .headers(self.headers.clone())
-            .send()
-            .await?;
-        debug!("status {} for {}", resp.status(), &url);
-        Ok(resp.error_for_status()?)
+            .json(body);
+        self.send(builder, &url).await
     }
 
     async fn head(&self, url: String, headers: Option<HeaderMap>) -> Result<Response> {
-        let url = Config::get().rewrite_url(&url);
+        let url = self.profile.rewrite_url(&url);
         info!("HEAD {} {:?}", &url, headers);
         let builder = self.client.head(&url).headers(self.headers.clone());
 
@@ -222,29 +702,159 @@ impl Client {
             Some(custom_header) => builder.headers(custom_header),
             _ => builder,
         };
-        let resp = builder.send().await?;
-
-        debug!("status {} for {}", resp.status(), &url);
+        self.send(builder, &url).await
+    }
 
-        Ok(resp.error_for_status()?)
+    /// Send the request when the rate limit allows it. A secondary rate limit asking to wait for
+    /// a short time is retried, a longer one, or one still there after `MAX_RATELIMIT_RETRY`
+    /// retries, fails with `Error::RateLimited`. Connection errors, timeouts and 5xx are retried
+    /// according to the configured `RetryPolicy`, unless the method can't be repeated.
+    async fn send(&self, builder: RequestBuilder, url: &str) -> Result<Response> {
+        let config = Config::get();
+        if let Some(recording @ Recording::Replay(_)) = &config.recording {
+            let resp = Tape::get(recording)?.replay(&self.profile.name, &build(&builder)?)?;
+            return Ok(resp.error_for_status()?);
+        }
+        // the other forges don't announce their rate limit the GitHub way
+        let scheduler =
+            (self.profile.forge == ForgeKind::GitHub).then(|| Scheduler::get(&self.profile.name));
+        let policy = config.retry;
+        let retryable = retry::is_retryable(build(&builder)?.method());
+        let mut ratelimit_retry = 0;
+        let mut attempt = 0;
+        loop {
+            let request = builder.try_clone().ok_or(anyhow!("cannot clone request"))?;
+            let permit = match scheduler {
+                Some(scheduler) => Some(scheduler.acquire().await?),
+                None => None,
+            };
+            let resp = request.send().await;
+            drop(permit);
+
+            let resp = match resp {
+                Err(e)
+                    if retryable
+                        && retry::is_transient_error(&e)
+                        && attempt < policy.max_retries =>
+                {
+                    attempt += 1;
+                    let delay = retry::backoff(&policy, attempt);
+                    info!(
+                        "retry {}/{} in {:?} for {}: {}",
+                        attempt, policy.max_retries, delay, url, e
+                    );
+                    tokio::time::sleep(delay).await;
+                    continue;
+                }
+                resp => resp?,
+            };
+
+            debug!("status {} for {}", resp.status(), url);
+            match scheduler.and_then(|scheduler| scheduler.update(resp.status(), resp.headers())) {
+                Some(until) if ratelimit_retry < MAX_RATELIMIT_RETRY => {
+                    info!("rate limited until {} for {}", until, url);
+                    ratelimit_retry += 1;
+                }
+                Some(until) => {
+                    info!(
+                        "still rate limited after {} retries for {}",
+                        ratelimit_retry, url
+                    );
+                    return Err(Error::RateLimited(until).into());
+                }
+                _ if retryable
+                    && retry::is_transient_status(resp.status())
+                    && attempt < policy.max_retries =>
+                {
+                    attempt += 1;
+                    let delay = retry::backoff(&policy, attempt);
+                    info!(
+                        "retry {}/{} in {:?} for {}: status {}",
+                        attempt,
+                        policy.max_retries,
+                        delay,
+                        url,
+                        resp.status()
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                _ => {
+                    if attempt > 0 {
+                        info!("{} retries for {}", attempt, url);
+                    }
+                    let resp = match &config.recording {
+                        Some(recording @ Recording::Record(_)) => {
+                            Tape::get(recording)?
+                                .record(&self.profile.name, &build(&builder)?, resp)
+                                .await?
+                        }
+                        _ => resp,
+                    };
+                    return Ok(resp.error_for_status()?);
+                }
+            }
+        }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+fn build(builder: &RequestBuilder) -> Result<reqwest::Request> {
+    Ok(builder
+        .try_clone()
+        .ok_or(anyhow!("cannot clone request"))?
+        .build()?)
+}
+
+#[derive(Debug, Clone)]
 pub enum Error {
-    MissingToken,
+    /// with the token sources that were tried
+    MissingToken(Vec<String>),
+    RateLimited(DateTime<Utc>),
+    InvalidHttpConfig(String),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Error::MissingToken => write!(f, "Env variable GH_TOKEN is missing"),
+            Error::MissingToken(tried) => {
+                write!(f, "no token found, tried {}", tried.join(", "))
+            }
+            Error::RateLimited(until) => write!(
+                f,
+                "rate limited until {}",
+                until.with_timezone(&Local).format("%H:%M")
+            ),
+            Error::InvalidHttpConfig(msg) => write!(f, "invalid http settings: {msg}"),
         }
     }
 }
 
+fn is_rate_limited(err: &anyhow::Error) -> bool {
+    matches!(err.downcast_ref::<Error>(), Some(Error::RateLimited(_)))
+}
+
 impl std::error::Error for Error {}
 
+/// The object is gone or the request is invalid, sending it again will fail the same way. 401 and
+/// 403 are not permanent: the token can be fixed, or the limit lifted.
+pub fn is_permanent_error(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<reqwest::Error>()
+        .and_then(|err| err.status())
+        .is_some_and(|status| {
+            matches!(
+                status,
+                StatusCode::NOT_FOUND | StatusCode::GONE | StatusCode::UNPROCESSABLE_ENTITY
+            )
+        })
+}
+
+/// The server could not be reached or asked to wait, the request itself is not at fault
+pub fn is_transient_error(err: &anyhow::Error) -> bool {
+    match err.downcast_ref::<reqwest::Error>() {
+        Some(err) => retry::is_transient_error(err),
+        None => matches!(err.downcast_ref(), Some(Error::RateLimited(_))),
+    }
+}
+
 fn url_to_page(url: &str) -> Result<u32> {
     let url = Url::parse(url)?;
     let page = url
@@ -258,42 +868,56 @@ fn url_to_page(url: &str) -> Result<u32> {
     Ok(page)
 }
 
-fn pages_from_link(link: &str) -> Result<Vec<String>> {
-    let regex = Regex::new(r#"<(.*)>; rel="next", <(.*)>; rel="last""#);
+/// How the pages after the first one are fetched
+#[derive(Debug, PartialEq)]
+enum Pagination {
+    /// every page url is known, they are fetched concurrently
+    Pages(Vec<String>),
+    /// no `last` link, pages are followed one by one
+    Next(String),
+}
 
-    let matches = regex?.captures(link);
-    if let Some(matches) = matches {
-        let next = matches.get(1).ok_or(anyhow!("link parse error"))?.as_str();
-        let last = matches.get(2).ok_or(anyhow!("link parse error"))?.as_str();
-        // choose "next" as base url, then change "page" query param
-        let url = Url::parse(next)?;
+fn pagination(link: &str) -> Result<Pagination> {
+    let mut links = link::parse(link)?;
+    let Some(next) = links.remove("next") else {
+        // last page
+        return Ok(Pagination::Pages(vec![]));
+    };
+    let Some(last) = links.remove("last") else {
+        return Ok(Pagination::Next(next));
+    };
+    match (url_to_page(&next), url_to_page(&last)) {
+        (Ok(next_page), Ok(last_page)) => {
+            debug!("last page {}", last_page);
+            Ok(Pagination::Pages(page_urls(&next, next_page, last_page)?))
+        }
+        // not numbered with `page`
+        _ => Ok(Pagination::Next(next)),
+    }
+}
 
-        let next = url_to_page(next)?;
-        let last = url_to_page(last)?;
-        debug!("last page {}", last);
-        let mut urls = vec![];
-        for page in next..last + 1 {
-            let mut new_url = url.clone();
-            let query = url.query_pairs().filter(|(name, _)| name != "page");
+/// `url` with its `page` query param set to each page from `first` to `last`
+fn page_urls(url: &str, first: u32, last: u32) -> Result<Vec<String>> {
+    let url = Url::parse(url)?;
+    let mut urls = vec![];
+    for page in first..last + 1 {
+        let mut new_url = url.clone();
+        let query = url.query_pairs().filter(|(name, _)| name != "page");
 
-            new_url
-                .query_pairs_mut()
-                .clear()
-                .extend_pairs(query)
-                .extend_pairs([("page", format!("{}", page))]);
+        new_url
+            .query_pairs_mut()
+            .clear()
+            .extend_pairs(query)
+            .extend_pairs([("page", format!("{}", page))]);
 
-            debug!("add url {}", new_url);
+        debug!("add url {}", new_url);
 
-            urls.push(new_url.to_string());
-        }
-        Ok(urls)
-    } else {
-        Err(anyhow!("invalid link format"))
+        urls.push(new_url.to_string());
     }
+    Ok(urls)
 }
 
-async fn get_notifications(url: String) -> Result<Vec<Notification>> {
-    let client = Client::new()?;
+async fn get_notifications(client: &Client, url: String) -> Result<Vec<Notification>> {
     let resp = client.get(url).await?;
 
     let notifications = resp.json::<Vec<Notification>>().await;
@@ -306,44 +930,118 @@ async fn get_notifications(url: String) -> Result<Vec<Notification>> {
     }
 }
 
-async fn get_pr(url: String) -> Result<PullRequest> {
-    let client = Client::new()?;
-    let resp = client.get(url).await?;
-    Ok(resp.json::<PullRequest>().await?)
+/// Fetch the pages by following their `next` link
+async fn follow_pages<T: DeserializeOwned>(client: &Client, url: String) -> Result<Vec<T>> {
+    let mut items = vec![];
+    let mut visited = HashSet::new();
+    let mut next = Some(url);
+    while let Some(url) = next.take() {
+        if !visited.insert(url.clone()) {
+            error!("pagination loop on {}", url);
+            break;
+        }
+        let resp = client.get(url).await?;
+        next = next_link(resp.headers());
+        items.extend(resp.json::<Vec<T>>().await?);
+    }
+    Ok(items)
 }
 
-async fn get_release(url: String) -> Result<Release> {
-    let client = Client::new()?;
+/// Every page of a listing, with the server values returned with the first one
+pub(crate) async fn fetch_pages<T: DeserializeOwned>(
+    client: &Client,
+    url: String,
+) -> Result<(Vec<T>, ListingInfo)> {
     let resp = client.get(url).await?;
-    Ok(resp.json::<Release>().await?)
+    let info = ListingInfo::from_headers(resp.headers());
+    let next = next_link(resp.headers());
+    let mut items = resp.json::<Vec<T>>().await?;
+    if let Some(next) = next {
+        items.extend(follow_pages(client, next).await?);
+    }
+    Ok((items, info))
 }
 
-async fn get_issue(url: String) -> Result<Issue> {
-    let client = Client::new()?;
-    let resp = client.get(url).await?;
-    Ok(resp.json::<Issue>().await?)
+fn next_link(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("link")
+        .and_then(|link| link.to_str().ok())
+        .and_then(|link| link::parse(link).ok())
+        .and_then(|mut links| links.remove("next"))
+}
+
+/// Conditional GET: send the cached ETag, on 304 the cached body is used and the request isn't
+/// counted in the rate limit
+async fn get_object<T>(client: &Client, url: String, cache: HttpCache) -> Result<T>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let cached = cache.lock().unwrap().get(&url).cloned();
+
+    let headers = match &cached {
+        Some(entry) => {
+            let mut headers = HeaderMap::new();
+            headers.insert("If-None-Match", entry.etag.parse()?);
+            Some(headers)
+        }
+        None => None,
+    };
+    let resp = client.get_with_headers(url.clone(), headers).await?;
+
+    if resp.status() == StatusCode::NOT_MODIFIED
+        && let Some(entry) = cached
+    {
+        match serde_json::from_str(&entry.body) {
+            Ok(object) => {
+                debug!("cache hit for {}", &url);
+                return Ok(object);
+            }
+            Err(e) => {
+                // cached with an older format, fetch it again
+                error!("invalid cache for {}: {}", &url, e);
+                cache.lock().unwrap().remove(&url);
+                return Box::pin(get_object(client, url, cache)).await;
+            }
+        }
+    }
+
+    let etag = resp
+        .headers()
+        .get("etag")
+        .and_then(|etag| etag.to_str().ok())
+        .map(String::from);
+    let object = resp.json::<T>().await?;
+    if let Some(etag) = etag {
+        let body = serde_json::to_string(&object)?;
+        cache.lock().unwrap().insert(url, CacheEntry { etag, body });
+    }
+    Ok(object)
 }
 
-const NB_TASK: usize = 30;
+pub const NB_TASK: usize = 30;
+const MAX_RATELIMIT_RETRY: u32 = 3;
 
-pub async fn fetch_notifications(last_update: Option<NaiveDateTime>) -> Result<Vec<Notification>> {
-    let client = Client::new()?;
+pub async fn fetch_notifications(
+    client: &Client,
+    last_update: Option<NaiveDateTime>,
+) -> Result<(Vec<Notification>, ListingInfo)> {
     let resp = client.get_notifications(last_update).await?;
+    let info = ListingInfo::from_headers(resp.headers());
 
     let mut notifications = match resp.headers().get("link") {
-        Some(link) => {
-            let link = link.to_str()?;
-            let urls = pages_from_link(link)?;
-
-            iter(urls)
-                .map(get_notifications)
-                .buffer_unordered(NB_TASK)
-                .try_fold(vec![], |mut acc, x| async {
-                    acc.extend(x);
-                    Ok(acc)
-                })
-                .await?
-        }
+        Some(link) => match pagination(link.to_str()?)? {
+            Pagination::Pages(urls) => {
+                iter(urls)
+                    .map(|url| get_notifications(client, url))
+                    .buffer_unordered(NB_TASK)
+                    .try_fold(vec![], |mut acc, x| async {
+                        acc.extend(x);
+                        Ok(acc)
+                    })
+                    .await?
+            }
+            Pagination::Next(url) => follow_pages(client, url).await?,
+        },
         _ => {
             vec![]
         }
@@ -352,31 +1050,173 @@ pub async fn fetch_notifications(last_update: Option<NaiveDateTime>) -> Result<V
     let res = resp.json::<Vec<Notification>>().await?;
     notifications.extend(res);
 
-    Ok(notifications)
+    Ok((notifications, info))
+}
+
+/// Threads of the inbox updated after `since`, read or unread, without their details. None when
+/// the inbox didn't change since `last_modified`. Pages are fetched one after the other and any
+/// error fails the whole listing: it must be complete to be compared.
+pub async fn fetch_inbox(
+    client: &Client,
+    since: NaiveDateTime,
+    last_modified: Option<&str>,
+) -> Result<Option<(Vec<Notification>, ListingInfo)>> {
+    let url = format!(
+        "{}/notifications?all=true&per_page=50&since={}",
+        client.base_url,
+        since.format("%Y-%m-%dT%H:%M:%SZ")
+    );
+    let mut custom_headers = HeaderMap::new();
+    if let Some(last_modified) = last_modified {
+        custom_headers.insert("If-Modified-Since", last_modified.parse()?);
+    }
+    let resp = client.get_with_headers(url, Some(custom_headers)).await?;
+    if resp.status() == StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
+    let info = ListingInfo::from_headers(resp.headers());
+    let next = next_link(resp.headers());
+    let mut threads = resp.json::<Vec<Notification>>().await?;
+    if let Some(next) = next {
+        threads.extend(follow_pages(client, next).await?);
+    }
+    Ok(Some((threads, info)))
+}
+
+pub async fn fetch_prs(
+    client: &Client,
+    notifications: &[Notification],
+    cache: &HttpCache,
+) -> Result<Vec<PullRequest>> {
+    let urls = subject_urls(notifications, NotificationType::PullRequest);
+    fetch_batched(
+        urls,
+        NotificationType::PullRequest,
+        |urls| graphql::fetch_prs(client, urls),
+        |url| get_object(client, url, cache.clone()),
+        |pr| &pr.url,
+    )
+    .await
+}
+
+pub async fn fetch_releases(
+    client: &Client,
+    notifications: &[Notification],
+    cache: &HttpCache,
+) -> Result<Vec<Release>> {
+    let urls = subject_urls(notifications, NotificationType::Release);
+    fetch_batched(
+        urls,
+        NotificationType::Release,
+        |urls| graphql::fetch_releases(client, urls),
+        |url| get_object(client, url, cache.clone()),
+        |release| &release.url,
+    )
+    .await
+}
+
+pub async fn fetch_issues(
+    client: &Client,
+    notifications: &[Notification],
+    cache: &HttpCache,
+) -> Result<Vec<Issue>> {
+    let urls = subject_urls(notifications, NotificationType::Issue);
+    fetch_batched(
+        urls,
+        NotificationType::Issue,
+        |urls| graphql::fetch_issues(client, urls),
+        |url| get_object(client, url, cache.clone()),
+        |issue| &issue.url,
+    )
+    .await
 }
 
-pub async fn fetch_prs(notifications: &[Notification]) -> Result<Vec<PullRequest>> {
-    fetch_object(notifications, NotificationType::PullRequest, get_pr).await
+/// Latest comment of the notifications commented since their subject changed
+pub async fn fetch_comments(
+    client: &Client,
+    notifications: &[Notification],
+    cache: &HttpCache,
+) -> Result<Vec<Comment>> {
+    let urls: HashSet<String> = notifications
+        .iter()
+        .filter_map(|notif| {
+            notif
+                .subject
+                .latest_comment_url
+                .clone()
+                .filter(|url| Some(url) != notif.subject.url.as_ref())
+        })
+        .collect();
+    fetch_object(urls.into_iter().collect(), "comment", |url| {
+        get_object(client, url, cache.clone())
+    })
+    .await
 }
 
-pub async fn fetch_releases(notifications: &[Notification]) -> Result<Vec<Release>> {
-    fetch_object(notifications, NotificationType::Release, get_release).await
+pub async fn fetch_commits(
+    client: &Client,
+    notifications: &[Notification],
+    cache: &HttpCache,
+) -> Result<Vec<Commit>> {
+    let urls = subject_urls(notifications, NotificationType::Commit);
+    fetch_object(urls, NotificationType::Commit, |url| {
+        get_object(client, url, cache.clone())
+    })
+    .await
 }
 
-pub async fn fetch_issues(notifications: &[Notification]) -> Result<Vec<Issue>> {
-    fetch_object(notifications, NotificationType::Issue, get_issue).await
+pub async fn fetch_discussions(
+    client: &Client,
+    notifications: &[Notification],
+) -> Result<Vec<Discussion>> {
+    fetch_graphql_only(
+        notifications,
+        NotificationType::Discussion,
+        graphql::fetch_discussions(client, notifications),
+    )
+    .await
 }
 
-async fn fetch_object<F, Fut, T>(
+pub async fn fetch_security_alerts(
+    client: &Client,
+    notifications: &[Notification],
+) -> Result<Vec<SecurityAlert>> {
+    fetch_graphql_only(
+        notifications,
+        NotificationType::SecurityAlert,
+        graphql::fetch_security_alerts(client, notifications),
+    )
+    .await
+}
+
+/// Subjects only known through GraphQL: without it, or if it fails, they keep the data of their
+/// notification
+async fn fetch_graphql_only<T>(
     notifications: &[Notification],
     notification_type: NotificationType,
-    getter: F,
-) -> Result<Vec<T>>
-where
-    Fut: Future<Output = Result<T>>,
-    F: Fn(String) -> Fut,
-{
-    let urls: Vec<String> = notifications
+    batch: impl Future<Output = Result<Vec<T>>>,
+) -> Result<Vec<T>> {
+    let wanted = notifications
+        .iter()
+        .any(|notif| notif.subject.r#type == notification_type);
+    if !wanted || !matches!(Config::get().enrichment, Enrichment::Graphql) {
+        return Ok(vec![]);
+    }
+    match batch.await {
+        Ok(objects) => Ok(objects),
+        Err(e) if is_rate_limited(&e) => Err(e),
+        Err(e) => {
+            error!("graphql error: {} type {:?}", e, notification_type);
+            Ok(vec![])
+        }
+    }
+}
+
+fn subject_urls(
+    notifications: &[Notification],
+    notification_type: NotificationType,
+) -> Vec<String> {
+    notifications
         .iter()
         .filter_map(|notif| {
             if notif.subject.r#type == notification_type {
@@ -385,47 +1225,139 @@ where
                 None
             }
         })
+        .collect()
+}
+
+/// Resolve objects with batched GraphQL queries, then fetch whatever is left (or everything if
+/// GraphQL is disabled or failed) one by one with the REST API
+async fn fetch_batched<B, BFut, F, Fut, T>(
+    urls: Vec<String>,
+    notification_type: NotificationType,
+    batch: B,
+    getter: F,
+    object_url: fn(&T) -> &String,
+) -> Result<Vec<T>>
+where
+    BFut: Future<Output = Result<Vec<T>>>,
+    B: Fn(Vec<String>) -> BFut,
+    Fut: Future<Output = Result<T>>,
+    F: Fn(String) -> Fut,
+{
+    let mut objects = match Config::get().enrichment {
+        Enrichment::Graphql if !urls.is_empty() => match batch(urls.clone()).await {
+            Ok(objects) => objects,
+            Err(e) if is_rate_limited(&e) => return Err(e),
+            Err(e) => {
+                error!(
+                    "graphql error: {} type {:?}, fallback to rest",
+                    e, notification_type
+                );
+                vec![]
+            }
+        },
+        _ => vec![],
+    };
+
+    let missing: Vec<String> = urls
+        .into_iter()
+        .filter(|url| !objects.iter().any(|object| object_url(object) == url))
         .collect();
+    if !missing.is_empty() {
+        debug!(
+            "{} {:?} to fetch with rest",
+            missing.len(),
+            notification_type
+        );
+        objects.extend(fetch_object(missing, notification_type, getter).await?);
+    }
 
-    Ok(iter(urls)
+    Ok(objects)
+}
+
+async fn fetch_object<F, Fut, T>(
+    urls: Vec<String>,
+    kind: impl fmt::Debug,
+    getter: F,
+) -> Result<Vec<T>>
+where
+    Fut: Future<Output = Result<T>>,
+    F: Fn(String) -> Fut,
+{
+    iter(urls)
         .map(getter)
         .buffer_unordered(NB_TASK)
-        .fold(vec![], |mut acc, r| async {
-            match r {
-                Err(e) => {
-                    error!("error fetching object: {} type {:?}", e, notification_type);
-                    acc
+        .fold(Ok(vec![]), |acc, r| async {
+            match (acc, r) {
+                (Err(e), _) => Err(e),
+                // no need to continue, all the following requests would fail
+                (Ok(_), Err(e)) if is_rate_limited(&e) => Err(e),
+                (Ok(acc), Err(e)) => {
+                    error!("error fetching object: {} type {:?}", e, kind);
+                    Ok(acc)
                 }
-                Ok(v) => {
+                (Ok(mut acc), Ok(v)) => {
                     acc.push(v);
-                    acc
+                    Ok(acc)
                 }
             }
         })
-        .await)
+        .await
 }
 
-pub async fn mark_as_done(id: &String) -> Result<()> {
-    let client = Client::new()?;
+pub async fn mark_as_done(client: &Client, id: &String) -> Result<()> {
     client.mark_notification_done(id).await
 }
 
-pub async fn mark_as_done_multiple(ids: &Vec<String>) -> Result<()> {
-    iter(ids)
-        .map(mark_as_done)
-        .buffer_unordered(NB_TASK)
-        .try_collect()
+pub async fn mute_thread(client: &Client, id: &String) -> Result<()> {
+    client.mute_thread(id).await
+}
+
+pub async fn unmute_thread(client: &Client, id: &String) -> Result<()> {
+    client.unmute_thread(id).await
+}
+
+/// Listing the teams needs the `read:org` scope, without it only personal review requests are
+/// recognized
+pub async fn fetch_identity(client: &Client) -> Result<Identity> {
+    client
+        .identity
+        .get_or_try_init(|| get_identity(client))
         .await
+        .cloned()
+}
+
+async fn get_identity(client: &Client) -> Result<Identity> {
+    let user: User = client
+        .get(format!("{}/user", client.base_url))
+        .await?
+        .json()
+        .await?;
+    let teams = match client
+        .get(format!("{}/user/teams?per_page=100", client.base_url))
+        .await
+    {
+        Ok(resp) => resp.json::<Vec<Team>>().await?,
+        Err(err) => {
+            info!("cannot list the teams of {}: {}", user.login, err);
+            vec![]
+        }
+    };
+    Ok(Identity {
+        login: user.login,
+        teams: teams.iter().map(|team| team.full_slug("")).collect(),
+    })
 }
 
-pub async fn mark_as_read(id: &String) -> Result<()> {
-    let client = Client::new()?;
+pub async fn mark_as_read(client: &Client, id: &String) -> Result<()> {
     client.mark_notification_read(id).await
 }
 
-pub async fn check_update_and_limit(last_update: NaiveDateTime) -> Result<UpdateStatus> {
-    let client = Client::new()?;
-    let resp = client.need_update(last_update).await?;
+pub async fn check_update_and_limit(
+    client: &Client,
+    last_update: NaiveDateTime,
+    last_modified: Option<&str>,
+) -> Result<UpdateStatus> {
+    let resp = client.need_update(last_update, last_modified).await?;
     let headers = resp.headers();
     let poll_interval = headers
         .get("x-poll-interval")
@@ -461,12 +1393,12 @@ mod tests {
         let link = r#"<https://api.github.com/notifications?page=2>; rel="next", <https://api.github.com/notifications?page=4>; rel="last""#;
 
         assert_eq!(
-            pages_from_link(link).unwrap(),
-            vec![
-                "https://api.github.com/notifications?page=2",
-                "https://api.github.com/notifications?page=3",
-                "https://api.github.com/notifications?page=4"
-            ]
+            pagination(link).unwrap(),
+            Pagination::Pages(vec![
+                "https://api.github.com/notifications?page=2".into(),
+                "https://api.github.com/notifications?page=3".into(),
+                "https://api.github.com/notifications?page=4".into()
+            ])
         );
     }
     #[test]
@@ -474,8 +1406,8 @@ mod tests {
         let link = r#"<https://api.github.com/notifications?page=2>; rel="next", <https://api.github.com/notifications?page=2>; rel="last""#;
 
         assert_eq!(
-            pages_from_link(link).unwrap(),
-            vec!["https://api.github.com/notifications?page=2",]
+            pagination(link).unwrap(),
+            Pagination::Pages(vec!["https://api.github.com/notifications?page=2".into()])
         );
     }
 
@@ -483,7 +1415,7 @@ mod tests {
     fn test_link_invalid() {
         let link = "not a link";
 
-        assert!(pages_from_link(link).is_err());
+        assert!(pagination(link).is_err());
     }
 
     #[test]
@@ -491,12 +1423,42 @@ mod tests {
         let link = r#"<https://api.github.com/notifications?all=true&since=2023-11-06T00%3A00%3A00Z&page=2>; rel="next", <https://api.github.com/notifications?all=true&since=2023-11-06T00%3A00%3A00Z&page=4>; rel="last""#;
 
         assert_eq!(
-            pages_from_link(link).unwrap(),
-            vec![
-                "https://api.github.com/notifications?all=true&since=2023-11-06T00%3A00%3A00Z&page=2",
-                "https://api.github.com/notifications?all=true&since=2023-11-06T00%3A00%3A00Z&page=3",
-                "https://api.github.com/notifications?all=true&since=2023-11-06T00%3A00%3A00Z&page=4"
-            ]
+            pagination(link).unwrap(),
+            Pagination::Pages(vec![
+                "https://api.github.com/notifications?all=true&since=2023-11-06T00%3A00%3A00Z&page=2".into(),
+                "https://api.github.com/notifications?all=true&since=2023-11-06T00%3A00%3A00Z&page=3".into(),
+                "https://api.github.com/notifications?all=true&since=2023-11-06T00%3A00%3A00Z&page=4".into()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_link_any_order() {
+        let link = r#"<https://api.github.com/notifications?page=4>; rel="last", <https://api.github.com/notifications?page=1>; rel="first", <https://api.github.com/notifications?page=3>; rel="next", <https://api.github.com/notifications?page=1>; rel="prev""#;
+
+        assert_eq!(
+            pagination(link).unwrap(),
+            Pagination::Pages(vec![
+                "https://api.github.com/notifications?page=3".into(),
+                "https://api.github.com/notifications?page=4".into()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_link_last_page() {
+        let link = r#"<https://api.github.com/notifications?page=3>; rel="prev", <https://api.github.com/notifications?page=1>; rel="first""#;
+
+        assert_eq!(pagination(link).unwrap(), Pagination::Pages(vec![]));
+    }
+
+    #[test]
+    fn test_link_cursor() {
+        let link = r#"<https://api.github.com/notifications?after=Y3Vyc29y>; rel="next""#;
+
+        assert_eq!(
+            pagination(link).unwrap(),
+            Pagination::Next("https://api.github.com/notifications?after=Y3Vyc29y".into())
         );
     }
 
@@ -519,8 +1481,174 @@ mod tests {
 
         let expected = NaiveDateTime::new(d, t);
 
-        assert_eq!(notifications.get(0).unwrap().updated_at, expected);
+        assert_eq!(notifications.first().unwrap().updated_at, expected);
 
         Ok(())
     }
+
+    #[test]
+    fn test_parser_subject_types() -> Result<()> {
+        use serde_json;
+
+        let notification = |type_: &str| {
+            format!(
+                r#"{{"id": "1", "unread": true, "reason": "subscribed",
+                "updated_at": "2025-04-05T09:23:12Z",
+                "subject": {{"title": "Ideas & roadmap", "url": null, "type": "{type_}"}},
+                "repository": {{"full_name": "cogip/project1",
+                "html_url": "https://github.com/cogip/project1"}}}}"#
+            )
+        };
+
+        let expected = [
+            ("Discussion", NotificationType::Discussion),
+            ("CheckSuite", NotificationType::CheckSuite),
+            ("Commit", NotificationType::Commit),
+            (
+                "RepositoryVulnerabilityAlert",
+                NotificationType::SecurityAlert,
+            ),
+            (
+                "RepositoryDependabotAlertsThread",
+                NotificationType::SecurityAlert,
+            ),
+            ("SecurityAdvisory", NotificationType::SecurityAlert),
+            ("TeamDiscussion", NotificationType::Unknown),
+        ];
+        for (type_, expected) in expected {
+            let parsed: Notification = serde_json::from_str(&notification(type_))?;
+            assert_eq!(parsed.subject.r#type, expected);
+        }
+
+        let discussion: Notification = serde_json::from_str(&notification("Discussion"))?;
+        assert_eq!(
+            discussion.fallback_html_url(),
+            "https://github.com/cogip/project1/discussions?discussions_q=Ideas+%26+roadmap"
+        );
+        let check_suite: Notification = serde_json::from_str(&notification("CheckSuite"))?;
+        assert_eq!(
+            check_suite.fallback_html_url(),
+            "https://github.com/cogip/project1/actions"
+        );
+        let alert: Notification =
+            serde_json::from_str(&notification("RepositoryDependabotAlertsThread"))?;
+        assert_eq!(
+            alert.fallback_html_url(),
+            "https://github.com/cogip/project1/security/dependabot"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_listing_info() {
+        let mut headers = HeaderMap::new();
+        headers.insert("date", "Sat, 04 Oct 2025 09:10:11 GMT".parse().unwrap());
+        headers.insert(
+            "last-modified",
+            "Sat, 04 Oct 2025 09:00:00 GMT".parse().unwrap(),
+        );
+        headers.insert("x-poll-interval", "60".parse().unwrap());
+        headers.insert("x-ratelimit-remaining", "4990".parse().unwrap());
+        headers.insert("x-ratelimit-used", "10".parse().unwrap());
+        let info = ListingInfo::from_headers(&headers);
+        assert_eq!(
+            info.date.unwrap().to_string(),
+            "2025-10-04 09:10:11".to_string()
+        );
+        assert_eq!(
+            info.last_modified.as_deref(),
+            Some("Sat, 04 Oct 2025 09:00:00 GMT")
+        );
+        assert_eq!(info.poll_interval, Some(60));
+        assert_eq!(info.ratelimit_remaining, Some(4990));
+        assert_eq!(info.ratelimit_used, Some(10));
+
+        let info = ListingInfo::from_headers(&HeaderMap::new());
+        assert!(info.date.is_none() && info.poll_interval.is_none());
+    }
+
+    #[test]
+    fn test_http_client() {
+        assert!(http_client(&HttpConfig::default()).is_ok());
+        let config = HttpConfig {
+            proxy: Some("http://proxy.corp:3128".into()),
+            no_proxy: Some("localhost,.corp".into()),
+            connect_timeout_secs: Some(5),
+            timeout_secs: Some(30),
+            ..HttpConfig::default()
+        };
+        assert!(http_client(&config).is_ok());
+        let settings = describe_http(&config);
+        assert_eq!(
+            settings[0],
+            ("proxy", "http://proxy.corp:3128 (config)".to_string())
+        );
+        assert_eq!(
+            settings[1],
+            ("no proxy", "localhost,.corp (config)".to_string())
+        );
+        assert_eq!(settings[2], ("ca", "system roots".to_string()));
+        assert_eq!(settings[5], ("timeout", "30s".to_string()));
+
+        let config = HttpConfig {
+            client_cert: Some("/tmp/cert.pem".into()),
+            ..HttpConfig::default()
+        };
+        let err = http_client(&config).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "client_cert and client_key must be set together"
+        );
+
+        let config = HttpConfig {
+            ca_cert: Some("/nonexistent/ca.pem".into()),
+            ..HttpConfig::default()
+        };
+        assert!(http_client(&config).is_err());
+        assert!(
+            describe_http(&config)[2]
+                .1
+                .starts_with("error: cannot read")
+        );
+    }
+
+    fn comment(body: &str) -> Comment {
+        Comment {
+            url: String::new(),
+            html_url: String::new(),
+            user: None,
+            body: Some(body.into()),
+            created_at: None,
+            submitted_at: None,
+        }
+    }
+
+    #[test]
+    fn test_comment_excerpt() {
+        assert_eq!(
+            comment("> you said\n\n**LGTM**, thanks!\n<!-- bot marker -->\n## Next\nfixes #12")
+                .excerpt(),
+            "LGTM, thanks! Next fixes #12"
+        );
+        assert_eq!(comment("").excerpt(), "");
+        assert_eq!(comment("<!-- unterminated").excerpt(), "");
+
+        let excerpt = comment(&"é".repeat(200)).excerpt();
+        assert_eq!(excerpt.chars().count(), EXCERPT_LEN);
+        assert!(excerpt.ends_with('…'));
+    }
+
+    #[test]
+    fn test_review_date() {
+        let review: Comment = serde_json::from_str(
+            r#"{"url": "u", "html_url": "h", "user": {"login": "octocat"}, "body": "ok",
+                "submitted_at": "2025-10-25T09:00:00Z"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            review.date().unwrap().to_string(),
+            "2025-10-25 09:00:00 UTC"
+        );
+    }
 }
@@ -0,0 +1,850 @@
+//! Batched enrichment through the GraphQL API
+//!
+//! Each notification subject becomes an aliased `repository` node (`n0`, `n1`, ...), so one query
+//! resolves up to `BATCH_SIZE` subjects. Results are converted into the structs returned by the
+//! REST API, `service::sync` doesn't know which backend was used.
+
+use super::{
+    Client, Discussion, Issue, Label, Milestone, NB_TASK, Notification, NotificationType,
+    PullRequest, Release, SecurityAlert, Team, User,
+};
+use anyhow::Result;
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use futures::stream::iter;
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use url::Url;
+
+const BATCH_SIZE: usize = 50;
+// a release notification is about a recent release, no need to look further
+const NB_RELEASES: usize = 20;
+// same for a discussion, recently updated
+const NB_DISCUSSIONS: usize = 20;
+const NB_ALERTS: usize = 50;
+const TRIAGE: &str = "labels(first: 20) { nodes { name } } \
+    assignees(first: 10) { nodes { login } } milestone { title }";
+const PR_REVIEW: &str = "reviewDecision \
+    reviewRequests(first: 50) { nodes { requestedReviewer { \
+    ... on User { login } ... on Team { slug organization { login } } } } } \
+    commits(last: 1) { nodes { commit { statusCheckRollup { state } } } }";
+// the canonical issue is only found in the timeline
+const ISSUE_STATE: &str = "stateReason \
+    timelineItems(itemTypes: [MARKED_AS_DUPLICATE_EVENT], last: 1) { nodes { \
+    ... on MarkedAsDuplicateEvent { canonical { ... on Issue { url } ... on PullRequest { url } } } } }";
+
+#[derive(Debug, PartialEq)]
+enum SubjectKind {
+    PullRequest(i64),
+    Issue(i64),
+    Release(i64),
+    /// found by title
+    Discussion(String),
+    /// found by the package named in the title
+    SecurityAlert(String),
+}
+
+#[derive(Debug, PartialEq)]
+struct SubjectRef {
+    /// API url of the subject, or the thread id of the subjects without one
+    url: String,
+    owner: String,
+    name: String,
+    kind: SubjectKind,
+}
+
+impl SubjectRef {
+    /// Parse an API url like `https://api.github.com/repos/{owner}/{name}/pulls/{number}`
+    fn parse(url: &str) -> Option<SubjectRef> {
+        let parsed = Url::parse(url).ok()?;
+        let segments: Vec<&str> = parsed.path_segments()?.collect();
+        let start = segments.iter().position(|segment| *segment == "repos")?;
+        let [owner, name, kind, id] = segments.get(start + 1..start + 5)? else {
+            return None;
+        };
+        let id = id.parse().ok()?;
+        let kind = match *kind {
+            "pulls" => SubjectKind::PullRequest(id),
+            "issues" => SubjectKind::Issue(id),
+            "releases" => SubjectKind::Release(id),
+            _ => return None,
+        };
+        Some(SubjectRef {
+            url: url.to_string(),
+            owner: owner.to_string(),
+            name: name.to_string(),
+            kind,
+        })
+    }
+
+    /// Discussions and security alerts come without url, they are looked for by title
+    fn titled(notification: &Notification) -> Option<SubjectRef> {
+        let (owner, name) = notification.repository.full_name.split_once('/')?;
+        let title = notification.subject.title.trim().to_string();
+        let kind = match notification.subject.r#type {
+            NotificationType::Discussion => SubjectKind::Discussion(title),
+            NotificationType::SecurityAlert => SubjectKind::SecurityAlert(title),
+            _ => return None,
+        };
+        Some(SubjectRef {
+            url: notification.id.clone(),
+            owner: owner.to_string(),
+            name: name.to_string(),
+            kind,
+        })
+    }
+
+    fn query(&self, alias: &str) -> String {
+        let node = match self.kind {
+            SubjectKind::PullRequest(number) => format!(
+                "pullRequest(number: {number}) {{ url state number isDraft merged author {{ login }} {TRIAGE} {PR_REVIEW} }}"
+            ),
+            SubjectKind::Issue(number) => {
+                format!(
+                    "issue(number: {number}) {{ url state author {{ login }} {TRIAGE} {ISSUE_STATE} }}"
+                )
+            }
+            SubjectKind::Release(_) => format!(
+                "releases(first: {NB_RELEASES}, orderBy: {{field: CREATED_AT, direction: DESC}}) {{ nodes {{ databaseId url author {{ login }} tagName isPrerelease isDraft publishedAt }} }}"
+            ),
+            SubjectKind::Discussion(_) => format!(
+                "discussions(first: {NB_DISCUSSIONS}, orderBy: {{field: UPDATED_AT, direction: DESC}}) {{ nodes {{ url title closed stateReason isAnswered author {{ login }} }} }}"
+            ),
+            SubjectKind::SecurityAlert(_) => format!(
+                "vulnerabilityAlerts(last: {NB_ALERTS}) {{ nodes {{ number state securityVulnerability {{ package {{ name }} }} }} }}"
+            ),
+        };
+        format!(
+            "{alias}: repository(owner: {}, name: {}) {{ {node} }}",
+            quote(&self.owner),
+            quote(&self.name)
+        )
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn build_query(subjects: &[SubjectRef]) -> String {
+    let nodes: Vec<String> = subjects
+        .iter()
+        .enumerate()
+        .map(|(idx, subject)| subject.query(&alias(idx)))
+        .collect();
+    format!("query {{ {} }}", nodes.join(" "))
+}
+
+fn alias(idx: usize) -> String {
+    format!("n{idx}")
+}
+
+/// GitHub serves GraphQL at `/graphql`, or `/api/graphql` on an enterprise server
+fn graphql_url(base_url: &str) -> String {
+    match base_url.strip_suffix("/api/v3") {
+        Some(host) => format!("{host}/api/graphql"),
+        None => format!("{base_url}/graphql"),
+    }
+}
+
+#[derive(Serialize)]
+struct Query {
+    query: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct Response {
+    data: Option<HashMap<String, Option<RepositoryNode>>>,
+    errors: Option<Vec<ResponseError>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ResponseError {
+    message: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct RepositoryNode {
+    pull_request: Option<PullRequestNode>,
+    issue: Option<IssueNode>,
+    releases: Option<ReleaseConnection>,
+    discussions: Option<Nodes<DiscussionNode>>,
+    vulnerability_alerts: Option<Nodes<AlertNode>>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PullRequestNode {
+    url: String,
+    state: String,
+    number: i32,
+    is_draft: bool,
+    merged: bool,
+    author: Option<User>,
+    labels: Option<Nodes<Label>>,
+    assignees: Option<Nodes<User>>,
+    milestone: Option<Milestone>,
+    review_decision: Option<String>,
+    review_requests: Option<Nodes<ReviewRequestNode>>,
+    commits: Option<Nodes<CommitNode>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Nodes<T> {
+    nodes: Vec<T>,
+}
+
+fn nodes<T>(connection: Option<Nodes<T>>) -> Vec<T> {
+    connection
+        .map(|connection| connection.nodes)
+        .unwrap_or_default()
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ReviewRequestNode {
+    requested_reviewer: Option<ReviewerNode>,
+}
+
+/// a user or a team, bots and mannequins have no field
+#[derive(Deserialize, Debug)]
+struct ReviewerNode {
+    login: Option<String>,
+    slug: Option<String>,
+    organization: Option<User>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CommitNode {
+    commit: CommitStatusNode,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct CommitStatusNode {
+    status_check_rollup: Option<StatusCheckRollup>,
+}
+
+#[derive(Deserialize, Debug)]
+struct DuplicateEventNode {
+    canonical: Option<CanonicalNode>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CanonicalNode {
+    url: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct StatusCheckRollup {
+    state: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct IssueNode {
+    url: String,
+    state: String,
+    state_reason: Option<String>,
+    timeline_items: Option<Nodes<DuplicateEventNode>>,
+    author: Option<User>,
+    labels: Option<Nodes<Label>>,
+    assignees: Option<Nodes<User>>,
+    milestone: Option<Milestone>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ReleaseConnection {
+    nodes: Vec<ReleaseNode>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ReleaseNode {
+    database_id: Option<i64>,
+    url: String,
+    author: Option<User>,
+    #[serde(default)]
+    tag_name: String,
+    #[serde(default)]
+    is_prerelease: bool,
+    #[serde(default)]
+    is_draft: bool,
+    published_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct DiscussionNode {
+    url: String,
+    title: String,
+    closed: bool,
+    state_reason: Option<String>,
+    is_answered: Option<bool>,
+    author: Option<User>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct AlertNode {
+    number: i64,
+    state: String,
+    security_vulnerability: Option<VulnerabilityNode>,
+}
+
+#[derive(Deserialize, Debug)]
+struct VulnerabilityNode {
+    package: PackageNode,
+}
+
+#[derive(Deserialize, Debug)]
+struct PackageNode {
+    name: String,
+}
+
+/// deleted accounts are returned as a null author
+fn login(author: Option<User>) -> User {
+    author.unwrap_or(User {
+        login: "ghost".into(),
+    })
+}
+
+impl Response {
+    /// Match every subject with its aliased node, subjects not found are skipped
+    fn into_objects<T>(
+        self,
+        subjects: Vec<SubjectRef>,
+        convert: fn(SubjectRef, RepositoryNode) -> Option<T>,
+    ) -> Vec<T> {
+        if let Some(errors) = &self.errors {
+            for err in errors {
+                error!("graphql error: {}", err.message);
+            }
+        }
+        let mut data = self.data.unwrap_or_default();
+        subjects
+            .into_iter()
+            .enumerate()
+            .filter_map(|(idx, subject)| {
+                let node = data.remove(&alias(idx)).flatten()?;
+                convert(subject, node)
+            })
+            .collect()
+    }
+}
+
+fn to_pr(subject: SubjectRef, node: RepositoryNode) -> Option<PullRequest> {
+    let pr = node.pull_request?;
+    let mut reviewers: Vec<ReviewerNode> = pr
+        .review_requests
+        .into_iter()
+        .flat_map(|requests| requests.nodes)
+        .filter_map(|request| request.requested_reviewer)
+        .collect();
+    Some(PullRequest {
+        url: subject.url,
+        html_url: pr.url,
+        // MERGED is a closed PR for the REST API
+        state: match pr.state.as_str() {
+            "OPEN" => "open".into(),
+            _ => "closed".into(),
+        },
+        number: pr.number,
+        draft: pr.is_draft,
+        merged: pr.merged,
+        user: login(pr.author),
+        requested_reviewers: reviewers
+            .iter_mut()
+            .filter_map(|reviewer| {
+                Some(User {
+                    login: reviewer.login.take()?,
+                })
+            })
+            .collect(),
+        requested_teams: reviewers
+            .into_iter()
+            .filter_map(|reviewer| {
+                Some(Team {
+                    slug: reviewer.slug?,
+                    organization: reviewer.organization,
+                })
+            })
+            .collect(),
+        labels: nodes(pr.labels),
+        assignees: nodes(pr.assignees),
+        milestone: pr.milestone,
+        review_decision: pr.review_decision,
+        ci_state: pr
+            .commits
+            .and_then(|commits| commits.nodes.into_iter().next())
+            .and_then(|commit| commit.commit.status_check_rollup)
+            .map(|rollup| rollup.state),
+    })
+}
+
+fn to_issue(subject: SubjectRef, node: RepositoryNode) -> Option<Issue> {
+    let issue = node.issue?;
+    let state_reason = issue.state_reason.map(|reason| reason.to_lowercase());
+    // an issue unmarked as duplicate keeps the event in its timeline
+    let duplicate_of = if state_reason.as_deref() == Some("duplicate") {
+        nodes(issue.timeline_items)
+            .into_iter()
+            .filter_map(|event| event.canonical)
+            .map(|canonical| canonical.url)
+            .next_back()
+    } else {
+        None
+    };
+    Some(Issue {
+        url: subject.url,
+        html_url: issue.url,
+        user: login(issue.author),
+        state: issue.state.to_lowercase(),
+        state_reason,
+        duplicate_of,
+        labels: nodes(issue.labels),
+        assignees: nodes(issue.assignees),
+        milestone: issue.milestone,
+    })
+}
+
+fn to_release(subject: SubjectRef, node: RepositoryNode) -> Option<Release> {
+    let SubjectKind::Release(id) = subject.kind else {
+        return None;
+    };
+    let release = node
+        .releases?
+        .nodes
+        .into_iter()
+        .find(|release| release.database_id == Some(id))?;
+    Some(Release {
+        url: subject.url,
+        html_url: release.url,
+        author: login(release.author),
+        tag_name: release.tag_name,
+        prerelease: release.is_prerelease,
+        draft: release.is_draft,
+        published_at: release.published_at,
+    })
+}
+
+fn to_discussion(subject: SubjectRef, node: RepositoryNode) -> Option<Discussion> {
+    let SubjectKind::Discussion(title) = subject.kind else {
+        return None;
+    };
+    let discussion = nodes(node.discussions)
+        .into_iter()
+        .find(|discussion| discussion.title.trim() == title)?;
+    Some(Discussion {
+        thread_id: subject.url,
+        html_url: discussion.url,
+        author: login(discussion.author),
+        closed: discussion.closed,
+        state_reason: discussion.state_reason.map(|reason| reason.to_lowercase()),
+        answered: discussion.is_answered,
+    })
+}
+
+fn to_security_alert(subject: SubjectRef, node: RepositoryNode) -> Option<SecurityAlert> {
+    let SubjectKind::SecurityAlert(title) = subject.kind else {
+        return None;
+    };
+    let title = title.to_lowercase();
+    // the longest package name of the title, `serde_json` rather than `serde`, then the latest
+    // alert of that package
+    let (_, number, state) = nodes(node.vulnerability_alerts)
+        .into_iter()
+        .filter_map(|alert| {
+            let package = alert.security_vulnerability?.package.name.to_lowercase();
+            title
+                .contains(&package)
+                .then_some((package.len(), alert.number, alert.state))
+        })
+        .max()?;
+    Some(SecurityAlert {
+        thread_id: subject.url,
+        number,
+        state: state.to_lowercase(),
+    })
+}
+
+async fn fetch_batch<T>(
+    client: &Client,
+    subjects: Vec<SubjectRef>,
+    convert: fn(SubjectRef, RepositoryNode) -> Option<T>,
+) -> Result<Vec<T>> {
+    let query = Query {
+        query: build_query(&subjects),
+    };
+    let resp = client
+        .post(graphql_url(&client.base_url), &query)
+        .await?
+        .json::<Response>()
+        .await?;
+    if resp.data.is_none() {
+        return Err(anyhow!("graphql query without data"));
+    }
+    Ok(resp.into_objects(subjects, convert))
+}
+
+async fn fetch<T>(
+    client: &Client,
+    urls: Vec<String>,
+    convert: fn(SubjectRef, RepositoryNode) -> Option<T>,
+) -> Result<Vec<T>> {
+    let subjects = urls
+        .iter()
+        .filter_map(|url| SubjectRef::parse(url))
+        .collect();
+    fetch_subjects(client, subjects, convert).await
+}
+
+async fn fetch_subjects<T>(
+    client: &Client,
+    mut subjects: Vec<SubjectRef>,
+    convert: fn(SubjectRef, RepositoryNode) -> Option<T>,
+) -> Result<Vec<T>> {
+    debug!("{} subjects to fetch with graphql", subjects.len());
+
+    let mut batches = vec![];
+    while !subjects.is_empty() {
+        let rest = subjects.split_off(BATCH_SIZE.min(subjects.len()));
+        batches.push(subjects);
+        subjects = rest;
+    }
+
+    let results: Vec<Result<Vec<T>>> = iter(batches)
+        .map(|batch| fetch_batch(client, batch, convert))
+        .buffer_unordered(NB_TASK)
+        .collect()
+        .await;
+
+    let mut objects = vec![];
+    for result in results {
+        objects.extend(result?);
+    }
+    Ok(objects)
+}
+
+pub async fn fetch_prs(client: &Client, urls: Vec<String>) -> Result<Vec<PullRequest>> {
+    fetch(client, urls, to_pr).await
+}
+
+pub async fn fetch_issues(client: &Client, urls: Vec<String>) -> Result<Vec<Issue>> {
+    fetch(client, urls, to_issue).await
+}
+
+pub async fn fetch_releases(client: &Client, urls: Vec<String>) -> Result<Vec<Release>> {
+    fetch(client, urls, to_release).await
+}
+
+fn titled_subjects(
+    notifications: &[Notification],
+    notification_type: NotificationType,
+) -> Vec<SubjectRef> {
+    notifications
+        .iter()
+        .filter(|notif| notif.subject.r#type == notification_type)
+        .filter_map(SubjectRef::titled)
+        .collect()
+}
+
+pub async fn fetch_discussions(
+    client: &Client,
+    notifications: &[Notification],
+) -> Result<Vec<Discussion>> {
+    let subjects = titled_subjects(notifications, NotificationType::Discussion);
+    fetch_subjects(client, subjects, to_discussion).await
+}
+
+pub async fn fetch_security_alerts(
+    client: &Client,
+    notifications: &[Notification],
+) -> Result<Vec<SecurityAlert>> {
+    let subjects = titled_subjects(notifications, NotificationType::SecurityAlert);
+    fetch_subjects(client, subjects, to_security_alert).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Identity;
+    use super::*;
+
+    #[test]
+    fn test_subject_parse() {
+        assert_eq!(
+            SubjectRef::parse("https://api.github.com/repos/cogip/project1/pulls/2049"),
+            Some(SubjectRef {
+                url: "https://api.github.com/repos/cogip/project1/pulls/2049".into(),
+                owner: "cogip".into(),
+                name: "project1".into(),
+                kind: SubjectKind::PullRequest(2049),
+            })
+        );
+        assert_eq!(
+            SubjectRef::parse("https://ghe.corp/api/v3/repos/astral-sh/uv/issues/12584")
+                .unwrap()
+                .kind,
+            SubjectKind::Issue(12584)
+        );
+        assert_eq!(
+            SubjectRef::parse("https://api.github.com/repos/cogip/project/releases/210493144")
+                .unwrap()
+                .kind,
+            SubjectKind::Release(210493144)
+        );
+        assert!(
+            SubjectRef::parse("https://api.github.com/repos/cogip/project/commits/abc").is_none()
+        );
+        assert!(SubjectRef::parse("https://api.github.com/repos/cogip").is_none());
+        assert!(SubjectRef::parse("not an url").is_none());
+    }
+
+    #[test]
+    fn test_build_query() {
+        let subjects = vec![
+            SubjectRef::parse("https://api.github.com/repos/cogip/project1/pulls/2049").unwrap(),
+            SubjectRef::parse("https://api.github.com/repos/astral-sh/uv/issues/12584").unwrap(),
+        ];
+        assert_eq!(
+            build_query(&subjects),
+            "query { \
+            n0: repository(owner: \"cogip\", name: \"project1\") { pullRequest(number: 2049) { url state number isDraft merged author { login } \
+            labels(first: 20) { nodes { name } } assignees(first: 10) { nodes { login } } milestone { title } \
+            reviewDecision reviewRequests(first: 50) { nodes { requestedReviewer { ... on User { login } ... on Team { slug organization { login } } } } } \
+            commits(last: 1) { nodes { commit { statusCheckRollup { state } } } } } } \
+            n1: repository(owner: \"astral-sh\", name: \"uv\") { issue(number: 12584) { url state author { login } \
+            labels(first: 20) { nodes { name } } assignees(first: 10) { nodes { login } } milestone { title } \
+            stateReason timelineItems(itemTypes: [MARKED_AS_DUPLICATE_EVENT], last: 1) { nodes { \
+            ... on MarkedAsDuplicateEvent { canonical { ... on Issue { url } ... on PullRequest { url } } } } } } } \
+            }"
+        );
+    }
+
+    #[test]
+    fn test_graphql_url() {
+        assert_eq!(
+            graphql_url("https://api.github.com"),
+            "https://api.github.com/graphql"
+        );
+        assert_eq!(
+            graphql_url("https://ghe.corp/api/v3"),
+            "https://ghe.corp/api/graphql"
+        );
+    }
+
+    #[test]
+    fn test_response() -> Result<()> {
+        let urls = [
+            "https://api.github.com/repos/cogip/project1/pulls/1",
+            "https://api.github.com/repos/cogip/project1/pulls/2",
+            "https://api.github.com/repos/cogip/project1/pulls/3",
+        ];
+        let subjects = urls
+            .iter()
+            .filter_map(|url| SubjectRef::parse(url))
+            .collect();
+        let resp: Response = serde_json::from_str(
+            r#"{
+              "data": {
+                "n0": {"pullRequest": {"url": "https://github.com/cogip/project1/pull/1",
+                  "state": "MERGED", "number": 1, "isDraft": false, "merged": true,
+                  "author": {"login": "bob"}}},
+                "n1": null,
+                "n2": {"pullRequest": {"url": "https://github.com/cogip/project1/pull/3",
+                  "state": "OPEN", "number": 3, "isDraft": true, "merged": false,
+                  "author": null, "reviewDecision": "CHANGES_REQUESTED",
+                  "reviewRequests": {"nodes": [
+                    {"requestedReviewer": {"login": "alice"}},
+                    {"requestedReviewer": {"slug": "core", "organization": {"login": "cogip"}}},
+                    {"requestedReviewer": {}}
+                  ]},
+                  "commits": {"nodes": [{"commit": {"statusCheckRollup": {"state": "FAILURE"}}}]}}}
+              },
+              "errors": [{"message": "Could not resolve to a Repository"}]
+            }"#,
+        )?;
+
+        let prs = resp.into_objects(subjects, to_pr);
+        assert_eq!(prs.len(), 2);
+        assert_eq!(prs[0].url, urls[0]);
+        assert_eq!(prs[0].html_url, "https://github.com/cogip/project1/pull/1");
+        assert_eq!(prs[0].state, "closed");
+        assert!(prs[0].merged);
+        assert_eq!(prs[0].user.login, "bob");
+        assert_eq!(prs[1].url, urls[2]);
+        assert_eq!(prs[1].state, "open");
+        assert!(prs[1].draft);
+        assert_eq!(prs[1].user.login, "ghost");
+        assert!(prs[0].requested_reviewers.is_empty());
+        assert!(prs[0].review_decision.is_none() && prs[0].ci_state.is_none());
+        assert_eq!(prs[1].review_decision.as_deref(), Some("CHANGES_REQUESTED"));
+        assert_eq!(prs[1].ci_state.as_deref(), Some("FAILURE"));
+        assert_eq!(prs[1].requested_reviewers.len(), 1);
+        assert_eq!(prs[1].requested_teams.len(), 1);
+
+        let alice = Identity {
+            login: "Alice".into(),
+            teams: vec![],
+        };
+        let core = Identity {
+            login: "bob".into(),
+            teams: vec!["cogip/core".into()],
+        };
+        let other = Identity {
+            login: "bob".into(),
+            teams: vec!["other/core".into()],
+        };
+        assert!(prs[1].review_requested(&alice, "cogip"));
+        assert!(prs[1].review_requested(&core, "cogip"));
+        assert!(!prs[1].review_requested(&other, "cogip"));
+        assert!(!prs[0].review_requested(&alice, "cogip"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_response_issue() -> Result<()> {
+        let urls = [
+            "https://api.github.com/repos/cogip/project1/issues/1",
+            "https://api.github.com/repos/cogip/project1/issues/2",
+            "https://api.github.com/repos/cogip/project1/issues/3",
+        ];
+        let subjects = urls
+            .iter()
+            .filter_map(|url| SubjectRef::parse(url))
+            .collect();
+        let resp: Response = serde_json::from_str(
+            r#"{"data": {
+              "n0": {"issue": {"url": "https://github.com/cogip/project1/issues/1", "state": "CLOSED",
+                "author": {"login": "bob"}, "stateReason": "DUPLICATE",
+                "timelineItems": {"nodes": [{"canonical": {"url": "https://github.com/cogip/project1/issues/7"}}]}}},
+              "n1": {"issue": {"url": "https://github.com/cogip/project1/issues/2", "state": "OPEN",
+                "author": {"login": "bob"}, "stateReason": "REOPENED",
+                "timelineItems": {"nodes": [{"canonical": {"url": "https://github.com/cogip/project1/issues/7"}}]}}},
+              "n2": {"issue": {"url": "https://github.com/cogip/project1/issues/3", "state": "CLOSED",
+                "author": {"login": "bob"}, "stateReason": "NOT_PLANNED", "timelineItems": {"nodes": []}}}
+            }}"#,
+        )?;
+
+        let issues = resp.into_objects(subjects, to_issue);
+        assert_eq!(issues.len(), 3);
+        assert_eq!(issues[0].state_reason.as_deref(), Some("duplicate"));
+        assert_eq!(
+            issues[0].duplicate_of.as_deref(),
+            Some("https://github.com/cogip/project1/issues/7")
+        );
+        assert_eq!(issues[1].state, "open");
+        assert_eq!(issues[1].state_reason.as_deref(), Some("reopened"));
+        assert!(issues[1].duplicate_of.is_none());
+        assert_eq!(issues[2].state_reason.as_deref(), Some("not_planned"));
+        assert!(issues[2].duplicate_of.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_response_release() -> Result<()> {
+        let url = "https://api.github.com/repos/cogip/project/releases/210493144";
+        let subjects = vec![SubjectRef::parse(url).unwrap()];
+        let resp: Response = serde_json::from_str(
+            r#"{"data": {"n0": {"releases": {"nodes": [
+              {"databaseId": 210493145, "url": "https://github.com/cogip/project/releases/tag/0.0.15",
+               "author": {"login": "cgp-github-ci"}},
+              {"databaseId": 210493144, "url": "https://github.com/cogip/project/releases/tag/0.0.14",
+               "author": {"login": "cgp-github-ci"}, "tagName": "0.0.14", "isPrerelease": true,
+               "isDraft": false, "publishedAt": "2025-04-05T00:37:46Z"}
+            ]}}}}"#,
+        )?;
+
+        let releases = resp.into_objects(subjects, to_release);
+        assert_eq!(releases.len(), 1);
+        assert_eq!(releases[0].url, url);
+        assert_eq!(
+            releases[0].html_url,
+            "https://github.com/cogip/project/releases/tag/0.0.14"
+        );
+        assert_eq!(releases[0].tag_name, "0.0.14");
+        assert!(releases[0].prerelease && !releases[0].draft);
+        assert_eq!(
+            releases[0].published_at.unwrap().to_string(),
+            "2025-04-05 00:37:46 UTC"
+        );
+
+        Ok(())
+    }
+
+    fn titled(kind: SubjectKind) -> SubjectRef {
+        SubjectRef {
+            url: "42".into(),
+            owner: "rust-lang".into(),
+            name: "cargo".into(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_response_discussion() -> Result<()> {
+        let subjects = vec![
+            titled(SubjectKind::Discussion("How to vendor?".into())),
+            titled(SubjectKind::Discussion("Gone".into())),
+        ];
+        let resp: Response = serde_json::from_str(
+            r#"{"data": {
+              "n0": {"discussions": {"nodes": [
+                {"url": "https://github.com/rust-lang/cargo/discussions/2", "title": "Other",
+                 "closed": false, "stateReason": null, "isAnswered": null, "author": {"login": "bob"}},
+                {"url": "https://github.com/rust-lang/cargo/discussions/1", "title": " How to vendor? ",
+                 "closed": true, "stateReason": "OUTDATED", "isAnswered": false, "author": null}
+              ]}},
+              "n1": {"discussions": {"nodes": []}}
+            }}"#,
+        )?;
+
+        let discussions = resp.into_objects(subjects, to_discussion);
+        assert_eq!(discussions.len(), 1);
+        assert_eq!(discussions[0].thread_id, "42");
+        assert_eq!(
+            discussions[0].html_url,
+            "https://github.com/rust-lang/cargo/discussions/1"
+        );
+        assert_eq!(discussions[0].author.login, "ghost");
+        assert!(discussions[0].closed);
+        assert_eq!(discussions[0].state_reason.as_deref(), Some("outdated"));
+        assert_eq!(discussions[0].answered, Some(false));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_response_security_alert() -> Result<()> {
+        let subjects = vec![
+            titled(SubjectKind::SecurityAlert(
+                "Potential security vulnerability found in the serde_json dependency".into(),
+            )),
+            titled(SubjectKind::SecurityAlert(
+                "Dependabot alert: openssl".into(),
+            )),
+        ];
+        let alerts = r#"{"vulnerabilityAlerts": {"nodes": [
+            {"number": 3, "state": "FIXED", "securityVulnerability": {"package": {"name": "serde_json"}}},
+            {"number": 4, "state": "OPEN", "securityVulnerability": {"package": {"name": "serde"}}},
+            {"number": 5, "state": "DISMISSED", "securityVulnerability": {"package": {"name": "serde_json"}}},
+            {"number": 6, "state": "OPEN", "securityVulnerability": null}
+        ]}}"#;
+        let resp: Response = serde_json::from_str(&format!(
+            r#"{{"data": {{"n0": {alerts}, "n1": {alerts}}}}}"#
+        ))?;
+
+        let found = resp.into_objects(subjects, to_security_alert);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].thread_id, "42");
+        assert_eq!(found[0].number, 5);
+        assert_eq!(found[0].state, "dismissed");
+
+        Ok(())
+    }
+}
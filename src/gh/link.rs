@@ -0,0 +1,198 @@
+//! `Link` header parser, see https://www.rfc-editor.org/rfc/rfc8288#section-3
+//!
+//! Only the target and its `rel` are kept, other parameters are skipped.
+
+use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+/// Target url of each relation type, rel are lowercased
+pub fn parse(header: &str) -> Result<HashMap<String, String>> {
+    let mut parser = Parser {
+        input: header,
+        chars: header.char_indices().peekable(),
+    };
+    let mut links = HashMap::new();
+    loop {
+        parser.skip(|c| c.is_whitespace() || c == ',');
+        if parser.chars.peek().is_none() {
+            return Ok(links);
+        }
+        let target = parser.target()?;
+        for rel in parser.rels()? {
+            // the first occurrence of a rel wins
+            links.entry(rel).or_insert_with(|| target.clone());
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip(&mut self, pred: impl Fn(char) -> bool) {
+        while self.chars.next_if(|&(_, c)| pred(c)).is_some() {}
+    }
+
+    /// consume until `pred` matches (not consumed), return the consumed part
+    fn take_until(&mut self, pred: impl Fn(char) -> bool) -> &str {
+        let start = self.position();
+        while self.chars.next_if(|&(_, c)| !pred(c)).is_some() {}
+        &self.input[start..self.position()]
+    }
+
+    fn position(&mut self) -> usize {
+        self.chars
+            .peek()
+            .map(|&(idx, _)| idx)
+            .unwrap_or(self.input.len())
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((idx, c)) => Err(anyhow!(
+                "invalid link: '{c}' at {idx}, expected '{expected}'"
+            )),
+            None => Err(anyhow!(
+                "invalid link: unexpected end, expected '{expected}'"
+            )),
+        }
+    }
+
+    /// `<url>`
+    fn target(&mut self) -> Result<String> {
+        self.expect('<')?;
+        let target = self.take_until(|c| c == '>').trim().to_string();
+        self.expect('>')?;
+        Ok(target)
+    }
+
+    /// `; name=value; name="quoted value"` up to the next link
+    fn rels(&mut self) -> Result<Vec<String>> {
+        let mut rels = vec![];
+        loop {
+            self.skip(char::is_whitespace);
+            match self.chars.peek() {
+                None | Some((_, ',')) => return Ok(rels),
+                Some((_, ';')) => {
+                    self.chars.next();
+                }
+                Some(&(idx, c)) => return Err(anyhow!("invalid link: '{c}' at {idx}")),
+            }
+            self.skip(char::is_whitespace);
+            let name = self
+                .take_until(|c| c == '=' || c == ';' || c == ',')
+                .trim()
+                .to_lowercase();
+            if self.chars.next_if(|&(_, c)| c == '=').is_none() {
+                // parameter without value
+                continue;
+            }
+            self.skip(char::is_whitespace);
+            let value = if self.chars.next_if(|&(_, c)| c == '"').is_some() {
+                self.quoted()?
+            } else {
+                self.take_until(|c| c == ';' || c == ',').trim().to_string()
+            };
+            if name == "rel" {
+                rels.extend(value.split_whitespace().map(str::to_lowercase));
+            }
+        }
+    }
+
+    /// rest of a quoted string, the opening quote is consumed
+    fn quoted(&mut self) -> Result<String> {
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(value),
+                Some((_, '\\')) => match self.chars.next() {
+                    Some((_, c)) => value.push(c),
+                    None => break,
+                },
+                Some((_, c)) => value.push(c),
+                None => break,
+            }
+        }
+        Err(anyhow!("invalid link: unterminated quoted string"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(header: &str) -> Vec<(String, String)> {
+        let mut links: Vec<_> = parse(header).unwrap().into_iter().collect();
+        links.sort();
+        links
+    }
+
+    fn link(rel: &str, page: u32) -> (String, String) {
+        (
+            rel.to_string(),
+            format!("https://api.github.com/notifications?page={page}"),
+        )
+    }
+
+    #[test]
+    fn test_first_page() {
+        let header = r#"<https://api.github.com/notifications?page=2>; rel="next", <https://api.github.com/notifications?page=4>; rel="last""#;
+        assert_eq!(links(header), vec![link("last", 4), link("next", 2)]);
+    }
+
+    #[test]
+    fn test_middle_page() {
+        let header = r#"<https://api.github.com/notifications?page=1>; rel="prev", <https://api.github.com/notifications?page=3>; rel="next", <https://api.github.com/notifications?page=4>; rel="last", <https://api.github.com/notifications?page=1>; rel="first""#;
+        assert_eq!(
+            links(header),
+            vec![
+                link("first", 1),
+                link("last", 4),
+                link("next", 3),
+                link("prev", 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_last_page() {
+        let header = r#"<https://api.github.com/notifications?page=3>; rel="prev", <https://api.github.com/notifications?page=1>; rel="first""#;
+        assert_eq!(links(header), vec![link("first", 1), link("prev", 3)]);
+    }
+
+    #[test]
+    fn test_cursor() {
+        let header = r#"<https://api.github.com/repositories/1/events?per_page=50&after=Y3Vyc29yOnYyOpK5>; rel="next""#;
+        assert_eq!(
+            links(header),
+            vec![(
+                "next".to_string(),
+                "https://api.github.com/repositories/1/events?per_page=50&after=Y3Vyc29yOnYyOpK5"
+                    .to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_syntax_variants() {
+        // unquoted and uppercase rel, extra parameters, multiple rels, comma in a quoted value
+        let header = "<https://api.github.com/notifications?page=4> ; title=\"a, \\\"b\\\"\" ;REL=last,\n <https://api.github.com/notifications?page=2>;rel=\"next prefetch\"; anchor";
+        assert_eq!(
+            links(header),
+            vec![link("last", 4), link("next", 2), link("prefetch", 2)]
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(parse("not a link").is_err());
+        assert!(parse("<https://api.github.com").is_err());
+        assert!(parse(r#"<https://api.github.com>; rel="next"#).is_err());
+        assert!(parse("").unwrap().is_empty());
+    }
+}
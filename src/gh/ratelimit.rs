@@ -0,0 +1,246 @@
+//! Shared request scheduler, it keeps track of the rate limit announced by GitHub in response
+//! headers and slows down (or stops) requests before the budget is exhausted
+//!
+//! See https://docs.github.com/en/rest/using-the-rest-api/rate-limits-for-the-rest-api
+
+use super::Error;
+use chrono::{DateTime, TimeDelta, Utc};
+use log::{debug, info};
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// max number of requests in flight
+const MAX_PERMITS: u32 = 30;
+/// wait for the limit to be lifted if it's shorter than this, else give up
+const MAX_WAIT_SEC: i64 = 60;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct State {
+    remaining: Option<u64>,
+    reset: Option<DateTime<Utc>>,
+    blocked_until: Option<DateTime<Utc>>,
+}
+
+pub struct Scheduler {
+    state: Mutex<State>,
+    semaphore: Semaphore,
+}
+
+/// one scheduler per profile, each token has its own budget
+static SCHEDULERS: OnceLock<Mutex<HashMap<String, &'static Scheduler>>> = OnceLock::new();
+
+impl Scheduler {
+    pub fn get(profile: &str) -> &'static Scheduler {
+        SCHEDULERS
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .unwrap()
+            .entry(profile.to_string())
+            .or_insert_with(|| {
+                Box::leak(Box::new(Scheduler {
+                    state: Mutex::new(State::default()),
+                    semaphore: Semaphore::new(MAX_PERMITS as usize),
+                }))
+            })
+    }
+
+    /// Wait for the right to send a request. The permit must be kept until the response is
+    /// received.
+    pub async fn acquire(&self) -> Result<SemaphorePermit<'_>, Error> {
+        let state = *self.state.lock().unwrap();
+        if let Some(until) = state.blocked_until {
+            let wait = until - Utc::now();
+            if wait > TimeDelta::seconds(MAX_WAIT_SEC) {
+                return Err(Error::RateLimited(until));
+            }
+            if wait > TimeDelta::zero() {
+                info!("rate limited, waiting {} sec", wait.num_seconds());
+                tokio::time::sleep(wait.to_std().unwrap_or_default()).await;
+            }
+        }
+        let permits = weight(state.remaining);
+        Ok(self
+            .semaphore
+            .acquire_many(permits)
+            .await
+            .expect("semaphore closed"))
+    }
+
+    /// Record the rate limit headers of a response, return when the request can be retried if
+    /// the response is a rate limit error
+    pub fn update(&self, status: StatusCode, headers: &HeaderMap) -> Option<DateTime<Utc>> {
+        let mut state = self.state.lock().unwrap();
+        let new_state = next_state(*state, status, headers, Utc::now());
+        debug!("rate limit {:?}", new_state);
+        *state = new_state;
+        if is_rate_limited(status) {
+            new_state.blocked_until
+        } else {
+            None
+        }
+    }
+}
+
+fn is_rate_limited(status: StatusCode) -> bool {
+    status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Budget is shared between all the requests in flight, the lower it is, the more permits a
+/// request takes
+fn weight(remaining: Option<u64>) -> u32 {
+    match remaining {
+        None => 1,
+        Some(remaining) if remaining >= 500 => 1,
+        Some(remaining) if remaining >= 100 => 3,
+        Some(remaining) if remaining >= 20 => 10,
+        Some(_) => MAX_PERMITS,
+    }
+}
+
+fn header<T: std::str::FromStr>(headers: &HeaderMap, name: &str) -> Option<T> {
+    headers.get(name)?.to_str().ok()?.parse().ok()
+}
+
+fn next_state(state: State, status: StatusCode, headers: &HeaderMap, now: DateTime<Utc>) -> State {
+    let remaining = header::<u64>(headers, "x-ratelimit-remaining").or(state.remaining);
+    let reset = header::<i64>(headers, "x-ratelimit-reset")
+        .and_then(|reset| DateTime::from_timestamp(reset, 0))
+        .or(state.reset);
+    let retry_after =
+        header::<i64>(headers, "retry-after").map(|sec| now + TimeDelta::seconds(sec));
+
+    let blocked_until = if is_rate_limited(status) {
+        match (retry_after, remaining, reset) {
+            // secondary rate limit
+            (Some(retry_after), _, _) => Some(retry_after),
+            // primary rate limit
+            (None, Some(0), Some(reset)) => Some(reset),
+            // secondary rate limit without retry-after, docs say wait at least one minute
+            (None, _, _) if status == StatusCode::TOO_MANY_REQUESTS => {
+                Some(now + TimeDelta::seconds(60))
+            }
+            // not a rate limit, a plain 403
+            _ => None,
+        }
+    } else if remaining == Some(0) {
+        reset
+    } else {
+        None
+    };
+
+    State {
+        remaining,
+        reset,
+        blocked_until,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(values: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in values {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_weight() {
+        assert_eq!(weight(None), 1);
+        assert_eq!(weight(Some(4000)), 1);
+        assert_eq!(weight(Some(200)), 3);
+        assert_eq!(weight(Some(50)), 10);
+        assert_eq!(weight(Some(3)), MAX_PERMITS);
+    }
+
+    #[test]
+    fn test_next_state_ok() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let state = next_state(
+            State::default(),
+            StatusCode::OK,
+            &headers(&[
+                ("x-ratelimit-remaining", "4990"),
+                ("x-ratelimit-reset", "1700003600"),
+            ]),
+            now,
+        );
+        assert_eq!(state.remaining, Some(4990));
+        assert_eq!(state.reset, DateTime::from_timestamp(1_700_003_600, 0));
+        assert_eq!(state.blocked_until, None);
+    }
+
+    #[test]
+    fn test_next_state_primary() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let state = next_state(
+            State::default(),
+            StatusCode::FORBIDDEN,
+            &headers(&[
+                ("x-ratelimit-remaining", "0"),
+                ("x-ratelimit-reset", "1700003600"),
+            ]),
+            now,
+        );
+        assert_eq!(
+            state.blocked_until,
+            DateTime::from_timestamp(1_700_003_600, 0)
+        );
+    }
+
+    #[test]
+    fn test_next_state_secondary() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let state = next_state(
+            State::default(),
+            StatusCode::TOO_MANY_REQUESTS,
+            &headers(&[("x-ratelimit-remaining", "4000"), ("retry-after", "30")]),
+            now,
+        );
+        assert_eq!(state.blocked_until, Some(now + TimeDelta::seconds(30)));
+
+        let state = next_state(
+            State::default(),
+            StatusCode::TOO_MANY_REQUESTS,
+            &HeaderMap::new(),
+            now,
+        );
+        assert_eq!(state.blocked_until, Some(now + TimeDelta::seconds(60)));
+    }
+
+    #[test]
+    fn test_next_state_forbidden() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let state = next_state(
+            State::default(),
+            StatusCode::FORBIDDEN,
+            &headers(&[("x-ratelimit-remaining", "4000")]),
+            now,
+        );
+        assert_eq!(state.blocked_until, None);
+    }
+
+    #[test]
+    fn test_next_state_unblock() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let blocked = State {
+            remaining: Some(0),
+            reset: Some(now),
+            blocked_until: Some(now),
+        };
+        let state = next_state(
+            blocked,
+            StatusCode::OK,
+            &headers(&[("x-ratelimit-remaining", "5000")]),
+            now,
+        );
+        assert_eq!(state.blocked_until, None);
+        assert_eq!(state.remaining, Some(5000));
+    }
+}
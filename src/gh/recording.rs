@@ -0,0 +1,297 @@
+//! Record and replay of the HTTP exchanges, to reproduce a sync without a token or a network
+//!
+//! With `--record <dir>` every response is written with its request in `<dir>/<n>.json`, the
+//! token redacted. With `--replay <dir>` the requests are not sent, the recorded responses are
+//! served instead: they are matched by profile, method, url and body, in the order they were
+//! recorded, the last one being served again once the others are used.
+
+use crate::config::Recording;
+use anyhow::{Context, Result, anyhow};
+use log::{debug, info};
+use reqwest::header::{AUTHORIZATION, CONTENT_ENCODING, CONTENT_LENGTH, TRANSFER_ENCODING};
+use reqwest::{Request, Response, ResponseBuilderExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use url::Url;
+
+const REDACTED: &str = "<redacted>";
+
+/// A request and its response, as stored on disk
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Exchange {
+    pub profile: String,
+    pub method: String,
+    pub url: String,
+    /// `Authorization` redacted
+    pub request_headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<String>,
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+}
+
+impl Exchange {
+    fn new(profile: &str, request: &Request) -> Exchange {
+        let request_headers = request
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = if name == AUTHORIZATION {
+                    REDACTED.to_string()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes()).into()
+                };
+                (name.to_string(), value)
+            })
+            .collect();
+        Exchange {
+            profile: profile.to_string(),
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            request_headers,
+            request_body: request_body(request),
+            status: 0,
+            headers: BTreeMap::new(),
+            body: String::new(),
+        }
+    }
+
+    fn response(&self) -> Result<Response> {
+        let mut builder = http::Response::builder()
+            .status(self.status)
+            .url(Url::parse(&self.url)?);
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        Ok(builder.body(self.body.clone())?.into())
+    }
+
+    fn key(&self) -> Key {
+        (
+            self.profile.clone(),
+            self.method.clone(),
+            key_url(&self.url),
+            self.request_body.clone(),
+        )
+    }
+}
+
+/// profile, method, url and body
+type Key = (String, String, String, Option<String>);
+
+fn request_key(profile: &str, request: &Request) -> Key {
+    (
+        profile.to_string(),
+        request.method().to_string(),
+        key_url(request.url().as_str()),
+        request_body(request),
+    )
+}
+
+/// `since` depends on the last sync found in the database, not on what is replayed
+fn key_url(url: &str) -> String {
+    let Ok(mut url) = Url::parse(url) else {
+        return url.to_string();
+    };
+    let query: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(name, _)| name != "since")
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    if query.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(query);
+    }
+    url.to_string()
+}
+
+fn request_body(request: &Request) -> Option<String> {
+    request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map(|bytes| String::from_utf8_lossy(bytes).into())
+}
+
+/// The exchanges of a directory, being recorded or replayed
+pub struct Tape {
+    dir: PathBuf,
+    next: AtomicUsize,
+    exchanges: Mutex<HashMap<Key, VecDeque<Exchange>>>,
+}
+
+/// one tape per directory and mode, loaded on first use
+static TAPES: OnceLock<Mutex<HashMap<Recording, &'static Tape>>> = OnceLock::new();
+
+impl Tape {
+    pub fn get(recording: &Recording) -> Result<&'static Tape> {
+        let mut tapes = TAPES
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .unwrap();
+        if let Some(tape) = tapes.get(recording) {
+            return Ok(tape);
+        }
+        let tape = match recording {
+            Recording::Record(dir) => {
+                fs::create_dir_all(dir)
+                    .with_context(|| format!("cannot create {}", dir.display()))?;
+                // appended to the exchanges already there
+                let recorded = load(dir)?.len();
+                Tape {
+                    dir: dir.clone(),
+                    next: AtomicUsize::new(recorded + 1),
+                    exchanges: Mutex::default(),
+                }
+            }
+            Recording::Replay(dir) => {
+                let loaded = load(dir)?;
+                info!(
+                    "{} exchanges to replay from {}",
+                    loaded.len(),
+                    dir.display()
+                );
+                let mut exchanges: HashMap<Key, VecDeque<Exchange>> = HashMap::new();
+                for exchange in loaded {
+                    exchanges
+                        .entry(exchange.key())
+                        .or_default()
+                        .push_back(exchange);
+                }
+                Tape {
+                    dir: dir.clone(),
+                    next: AtomicUsize::new(0),
+                    exchanges: Mutex::new(exchanges),
+                }
+            }
+        };
+        let tape = Box::leak(Box::new(tape));
+        tapes.insert(recording.clone(), tape);
+        Ok(tape)
+    }
+
+    /// Write the exchange, the body is read so a new response is returned
+    pub async fn record(
+        &self,
+        profile: &str,
+        request: &Request,
+        resp: Response,
+    ) -> Result<Response> {
+        let mut exchange = Exchange::new(profile, request);
+        exchange.status = resp.status().as_u16();
+        exchange.headers = resp
+            .headers()
+            .iter()
+            // the body is stored decoded
+            .filter(|(name, _)| {
+                ![CONTENT_ENCODING, CONTENT_LENGTH, TRANSFER_ENCODING].contains(name)
+            })
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).into(),
+                )
+            })
+            .collect();
+        exchange.body = String::from_utf8_lossy(&resp.bytes().await?).into();
+
+        let path = self.dir.join(format!(
+            "{:04}.json",
+            self.next.fetch_add(1, Ordering::SeqCst)
+        ));
+        debug!(
+            "record {} {} in {}",
+            exchange.method,
+            exchange.url,
+            path.display()
+        );
+        fs::write(&path, serde_json::to_string_pretty(&exchange)?)
+            .with_context(|| format!("cannot write {}", path.display()))?;
+        exchange.response()
+    }
+
+    pub fn replay(&self, profile: &str, request: &Request) -> Result<Response> {
+        let mut exchanges = self.exchanges.lock().unwrap();
+        let queue = exchanges
+            .get_mut(&request_key(profile, request))
+            .ok_or(anyhow!(
+                "no recorded response for {} {} in {}",
+                request.method(),
+                request.url(),
+                self.dir.display()
+            ))?;
+        let exchange = if queue.len() > 1 {
+            queue.pop_front().unwrap()
+        } else {
+            queue[0].clone()
+        };
+        debug!("replay {} {}", exchange.method, exchange.url);
+        exchange.response()
+    }
+}
+
+/// Exchanges of `dir` in recording order
+fn load(dir: &Path) -> Result<Vec<Exchange>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("cannot read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| {
+            let content = fs::read_to_string(path)?;
+            serde_json::from_str(&content).with_context(|| format!("invalid {}", path.display()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_url() {
+        assert_eq!(
+            key_url("https://api.github.com/notifications?all=true&since=2025-01-01T00:00:00Z"),
+            "https://api.github.com/notifications?all=true"
+        );
+        assert_eq!(
+            key_url("https://api.github.com/notifications?since=2025-01-01T00:00:00Z"),
+            "https://api.github.com/notifications"
+        );
+        assert_eq!(
+            key_url("https://api.github.com/notifications?page=2"),
+            "https://api.github.com/notifications?page=2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exchange() -> Result<()> {
+        let request = reqwest::Client::new()
+            .post("https://api.github.com/graphql")
+            .header(AUTHORIZATION, "Bearer ghp_secret")
+            .body("{}")
+            .build()?;
+        let mut exchange = Exchange::new("work", &request);
+        assert_eq!(exchange.request_headers["authorization"], REDACTED);
+        assert_eq!(exchange.request_body.as_deref(), Some("{}"));
+        assert_eq!(exchange.key(), request_key("work", &request));
+
+        exchange.status = 404;
+        exchange.headers.insert("etag".into(), "\"abc\"".into());
+        exchange.body = r#"{"message": "Not Found"}"#.into();
+        let resp = exchange.response()?;
+        assert_eq!(resp.status(), 404);
+        assert_eq!(resp.url().as_str(), "https://api.github.com/graphql");
+        assert_eq!(resp.headers()["etag"], "\"abc\"");
+        assert_eq!(resp.text().await?, r#"{"message": "Not Found"}"#);
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+//! Jittered exponential backoff for transient HTTP failures
+
+use crate::config::RetryPolicy;
+use reqwest::{Method, StatusCode};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Connection errors and server errors are worth a retry, a 4xx will fail again
+pub fn is_transient_error(err: &reqwest::Error) -> bool {
+    err.is_connect() || err.is_timeout()
+}
+
+/// Only the requests that can be repeated are retried: a POST may have been applied before the
+/// failure. The PATCH requests of the forges set the state of a thread, sending one twice is fine.
+pub fn is_retryable(method: &Method) -> bool {
+    method.is_idempotent() || method == Method::PATCH
+}
+
+pub fn is_transient_status(status: StatusCode) -> bool {
+    status.is_server_error()
+}
+
+/// Delay before the retry number `attempt` (starting at 1): exponential, capped to
+/// `max_delay_ms`, and randomized in its upper half so concurrent requests don't retry together
+pub fn backoff(policy: &RetryPolicy, attempt: u32) -> Duration {
+    let delay = policy
+        .base_delay_ms
+        .saturating_mul(2u64.saturating_pow(attempt.saturating_sub(1)))
+        .min(policy.max_delay_ms);
+    let half = delay / 2;
+    Duration::from_millis(half + random() % (half + 1))
+}
+
+fn random() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            base_delay_ms: 100,
+            max_delay_ms: 1000,
+        };
+        for _ in 0..20 {
+            let delay = backoff(&policy, 1).as_millis();
+            assert!((50..=100).contains(&delay), "{delay}");
+            let delay = backoff(&policy, 3).as_millis();
+            assert!((200..=400).contains(&delay), "{delay}");
+            let delay = backoff(&policy, 10).as_millis();
+            assert!((500..=1000).contains(&delay), "{delay}");
+        }
+    }
+
+    #[test]
+    fn test_transient_status() {
+        assert!(is_transient_status(StatusCode::BAD_GATEWAY));
+        assert!(is_transient_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_transient_status(StatusCode::NOT_FOUND));
+        assert!(!is_transient_status(StatusCode::UNAUTHORIZED));
+        assert!(!is_transient_status(StatusCode::NOT_MODIFIED));
+    }
+
+    #[test]
+    fn test_retryable() {
+        assert!(is_retryable(&Method::GET));
+        assert!(is_retryable(&Method::PUT));
+        assert!(is_retryable(&Method::PATCH));
+        assert!(is_retryable(&Method::DELETE));
+        assert!(!is_retryable(&Method::POST));
+    }
+}
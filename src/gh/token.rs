@@ -0,0 +1,241 @@
+//! Token discovery: the profile token, then environment, `token_command`, gh CLI config and git
+//! credential helpers, the first one found is used. GitLab and Gitea profiles read
+//! `GITLAB_TOKEN` or `GITEA_TOKEN` and skip the gh CLI config.
+
+use super::Error;
+use crate::config::Profile;
+use crate::models::ForgeKind;
+use directories::BaseDirs;
+use log::{debug, info};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use url::Url;
+
+pub fn resolve(profile: &Profile) -> Result<String, Error> {
+    if let Some(token) = &profile.token {
+        return Ok(token.clone());
+    }
+    let mut tried = vec![];
+    discover(profile, &mut tried).ok_or(Error::MissingToken(tried))
+}
+
+fn discover(profile: &Profile, tried: &mut Vec<String>) -> Option<String> {
+    let vars: &[&str] = match profile.forge {
+        ForgeKind::GitHub => &["GH_TOKEN", "GITHUB_TOKEN"],
+        ForgeKind::GitLab => &["GITLAB_TOKEN"],
+        ForgeKind::Gitea => &["GITEA_TOKEN", "FORGEJO_TOKEN"],
+    };
+    for var in vars {
+        tried.push(var.to_string());
+        if let Ok(token) = dotenvy::var(var)
+            && !token.is_empty()
+        {
+            return Some(token);
+        }
+    }
+
+    if let Some(command) = &profile.token_command {
+        tried.push(format!("token_command `{command}`"));
+        if let Some(token) = command_token(command) {
+            info!("token of {} from token_command", profile.name);
+            return Some(token);
+        }
+    }
+
+    // the gh CLI config and the credential helpers are looked up by host
+    let host = host(&profile.web_url);
+    if host.is_none() {
+        tried.push(format!("host of web_url `{}`", profile.web_url));
+    }
+
+    if profile.forge == ForgeKind::GitHub
+        && let Some(host) = &host
+        && let Some(path) = gh_hosts_path()
+    {
+        tried.push(path.display().to_string());
+        if let Some(token) = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| parse_gh_hosts(&content, host))
+        {
+            info!("token of {} from {}", profile.name, path.display());
+            return Some(token);
+        }
+    }
+
+    if let Some(host) = &host {
+        tried.push("git credential fill".to_string());
+        if let Some(token) = git_credential_token(host) {
+            info!("token of {} from git credential", profile.name);
+            return Some(token);
+        }
+    }
+    None
+}
+
+fn host(web_url: &str) -> Option<String> {
+    Some(Url::parse(web_url).ok()?.host_str()?.to_string())
+}
+
+/// stdout of a shell command, trimmed
+fn command_token(command: &str) -> Option<String> {
+    let output = Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        debug!("token_command failed with {}", output.status);
+        return None;
+    }
+    let token = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!token.is_empty()).then_some(token)
+}
+
+/// `hosts.yml` of the gh CLI, see `gh help environment`
+fn gh_hosts_path() -> Option<PathBuf> {
+    let dir = match env::var_os("GH_CONFIG_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) => PathBuf::from(dir).join("gh"),
+            None => BaseDirs::new()?.home_dir().join(".config").join("gh"),
+        },
+    };
+    Some(dir.join("hosts.yml"))
+}
+
+/// First `oauth_token` under the `host` key. Tokens stored in the system keyring by recent gh
+/// versions are not in this file.
+fn parse_gh_hosts(content: &str, host: &str) -> Option<String> {
+    let mut in_host = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with([' ', '\t']) {
+            in_host = unquote(trimmed.trim_end_matches(':')) == host;
+        } else if in_host && let Some(token) = trimmed.strip_prefix("oauth_token:") {
+            let token = unquote(token.trim());
+            if !token.is_empty() {
+                return Some(token.to_string());
+            }
+        }
+    }
+    None
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim_matches(['"', '\''])
+}
+
+/// Password of the credential helpers for `https://<host>`, without prompting
+fn git_credential_token(host: &str) -> Option<String> {
+    let mut child = Command::new("git")
+        .args(["credential", "fill"])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    child
+        .stdin
+        .take()?
+        .write_all(format!("protocol=https\nhost={host}\n\n").as_bytes())
+        .ok()?;
+    let output = child.wait_with_output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_credential(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_credential(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix("password="))
+        .filter(|password| !password.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gh_hosts() {
+        let content = r#"
+github.com:
+    user: octocat
+    oauth_token: gho_public
+    git_protocol: https
+"ghe.corp":
+    users:
+        octocat:
+            oauth_token: "gho_corp"
+    user: octocat
+"#;
+        assert_eq!(
+            parse_gh_hosts(content, "github.com").as_deref(),
+            Some("gho_public")
+        );
+        assert_eq!(
+            parse_gh_hosts(content, "ghe.corp").as_deref(),
+            Some("gho_corp")
+        );
+        assert_eq!(parse_gh_hosts(content, "other.corp"), None);
+        assert_eq!(
+            parse_gh_hosts("github.com:\n    user: octocat\n", "github.com"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_credential() {
+        assert_eq!(
+            parse_credential("protocol=https\nhost=github.com\nusername=me\npassword=gho_x\n")
+                .as_deref(),
+            Some("gho_x")
+        );
+        assert_eq!(parse_credential("protocol=https\nhost=github.com\n"), None);
+    }
+
+    #[test]
+    fn test_command_token() {
+        assert_eq!(
+            command_token("echo ' gho_cmd '").as_deref(),
+            Some("gho_cmd")
+        );
+        assert_eq!(command_token("echo"), None);
+        assert_eq!(command_token("exit 1"), None);
+    }
+
+    #[test]
+    fn test_host() {
+        assert_eq!(host("https://github.com").as_deref(), Some("github.com"));
+        assert_eq!(host("https://ghe.corp:8443").as_deref(), Some("ghe.corp"));
+    }
+
+    #[test]
+    fn test_discover_without_host() {
+        let mut profile = Profile::new("corp".into(), "not a url".into(), PathBuf::new());
+        profile.forge = ForgeKind::Gitea;
+        profile.token_command = Some("exit 1".into());
+        let mut tried = vec![];
+
+        assert_eq!(discover(&profile, &mut tried), None);
+        assert_eq!(
+            tried,
+            [
+                "GITEA_TOKEN",
+                "FORGEJO_TOKEN",
+                "token_command `exit 1`",
+                "host of web_url `not a url`",
+            ]
+        );
+    }
+}
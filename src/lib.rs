@@ -1,6 +1,8 @@
 pub mod config;
 pub mod dirs;
+pub mod feed;
 pub mod filter;
+pub mod forge;
 pub mod gh;
 pub mod models;
 pub mod schema;
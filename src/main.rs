@@ -1,27 +1,82 @@
 use std::str::FromStr;
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{TimeDelta, Utc};
 use log::LevelFilter;
 use log::{debug, info};
 use log4rs::append::file::FileAppender;
 use log4rs::config::{Appender, Config, Root};
-use riirview::{dirs, get_connection_pool, run_db_migrations, tui};
+use riirview::config::{self, Recording};
+use riirview::gh::Clients;
+use riirview::{dirs, get_connection_pool, run_db_migrations, service, tui};
+
+const DEFAULT_RESYNC_DAYS: i64 = 30;
+
+enum Mode {
+    Tui,
+    /// refetch the notifications of the last days
+    Resync(i64),
+    /// check the config and the connection to every profile
+    Doctor,
+}
 
 fn main() -> Result<()> {
-    if std::env::args().nth(1) == Some("--version".into()) {
-        println!("riirview {}", env!("VERGEN_GIT_DESCRIBE"));
-    } else {
-        let runtime = tokio::runtime::Builder::new_multi_thread()
-            .enable_all()
-            .build()?;
-        runtime.block_on(tokio_main())?;
-        runtime.shutdown_background();
-    }
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let recording = recording(&mut args)?;
+    let mode = match args.first().map(String::as_str) {
+        Some("--version") => {
+            println!("riirview {}", env!("VERGEN_GIT_DESCRIBE"));
+            return Ok(());
+        }
+        Some("resync") => Mode::Resync(match args.get(1).map(String::as_str) {
+            None => DEFAULT_RESYNC_DAYS,
+            Some("--days") => args
+                .get(2)
+                .ok_or(anyhow!("--days expects a number of days"))?
+                .parse()
+                .context("invalid number of days")?,
+            Some(arg) => bail!("unknown argument {arg}, usage: riirview resync [--days N]"),
+        }),
+        Some("doctor") => Mode::Doctor,
+        Some(arg) => bail!("unknown argument {arg}"),
+        None => Mode::Tui,
+    };
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    runtime.block_on(tokio_main(mode, recording))?;
+    runtime.shutdown_background();
     Ok(())
 }
 
-async fn tokio_main() -> Result<()> {
+/// `--record <dir>` or `--replay <dir>`, accepted in every mode
+fn recording(args: &mut Vec<String>) -> Result<Option<Recording>> {
+    let Some(idx) = args
+        .iter()
+        .position(|arg| arg == "--record" || arg == "--replay")
+    else {
+        return Ok(None);
+    };
+    let flag = args.remove(idx);
+    if idx >= args.len() {
+        bail!("{flag} expects a directory");
+    }
+    let dir = args.remove(idx).into();
+    Ok(Some(if flag == "--record" {
+        Recording::Record(dir)
+    } else {
+        Recording::Replay(dir)
+    }))
+}
+
+async fn tokio_main(mode: Mode, recording: Option<Recording>) -> Result<()> {
     _ = dotenvy::dotenv();
+    // an invalid config.toml stops every command, doctor included
+    let config = config::Config::load(&config::config_path())?;
+    config::Config::set(config::Config {
+        recording,
+        ..config
+    });
 
     let directories = dirs::Directories::new();
     directories.create().context("Error creating directories")?;
@@ -58,6 +113,24 @@ async fn tokio_main() -> Result<()> {
 
     let mut connection = get_connection_pool().get().unwrap();
     run_db_migrations(&mut connection);
+    service::adopt_default_account(&mut connection)?;
 
-    tui::run().await
+    match mode {
+        Mode::Tui => tui::run().await,
+        Mode::Resync(days) => {
+            let since = (Utc::now() - TimeDelta::days(days)).naive_utc();
+            println!("resyncing the notifications updated since {since}");
+            let report = service::resync(&mut connection, &Clients::from_config()?, since).await?;
+            print!("{report}");
+            Ok(())
+        }
+        Mode::Doctor => {
+            let report = service::doctor().await;
+            print!("{report}");
+            if !report.is_ok() {
+                bail!("some checks failed");
+            }
+            Ok(())
+        }
+    }
 }
@@ -3,6 +3,7 @@ use diesel::prelude::*;
 use diesel::{
     AsExpression, FromSqlRow, backend::Backend, deserialize, serialize, sql_types::VarChar,
 };
+use serde::Deserialize;
 
 #[derive(AsExpression, FromSqlRow, Debug, Clone)]
 #[diesel(sql_type = VarChar)]
@@ -10,6 +11,12 @@ pub enum NotificationType {
     PullRequest,
     Issue,
     Release,
+    Discussion,
+    CheckSuite,
+    Commit,
+    SecurityAlert,
+    /// entry of an RSS or Atom feed
+    FeedEntry,
 }
 
 impl<B: Backend> serialize::ToSql<VarChar, B> for NotificationType
@@ -21,6 +28,11 @@ where
             NotificationType::PullRequest => "PullRequest",
             NotificationType::Issue => "Issue",
             NotificationType::Release => "Release",
+            NotificationType::Discussion => "Discussion",
+            NotificationType::CheckSuite => "CheckSuite",
+            NotificationType::Commit => "Commit",
+            NotificationType::SecurityAlert => "SecurityAlert",
+            NotificationType::FeedEntry => "FeedEntry",
         };
         <str as serialize::ToSql<VarChar, B>>::to_sql(type_, out)
     }
@@ -36,6 +48,11 @@ where
                 "PullRequest" => NotificationType::PullRequest,
                 "Issue" => NotificationType::Issue,
                 "Release" => NotificationType::Release,
+                "Discussion" => NotificationType::Discussion,
+                "CheckSuite" => NotificationType::CheckSuite,
+                "Commit" => NotificationType::Commit,
+                "SecurityAlert" => NotificationType::SecurityAlert,
+                "FeedEntry" => NotificationType::FeedEntry,
                 _ => panic!("invalid type {sql}"),
             }
         })
@@ -83,10 +100,189 @@ where
     }
 }
 
+#[derive(AsExpression, FromSqlRow, Debug, Clone, Copy, PartialEq)]
+#[diesel(sql_type = VarChar)]
+pub enum ReviewDecision {
+    Approved,
+    ChangesRequested,
+    ReviewRequired,
+}
+
+impl<B: Backend> serialize::ToSql<VarChar, B> for ReviewDecision
+where
+    str: serialize::ToSql<VarChar, B>,
+{
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, B>) -> serialize::Result {
+        let decision = match self {
+            ReviewDecision::Approved => "Approved",
+            ReviewDecision::ChangesRequested => "ChangesRequested",
+            ReviewDecision::ReviewRequired => "ReviewRequired",
+        };
+        <str as serialize::ToSql<VarChar, B>>::to_sql(decision, out)
+    }
+}
+
+impl<B: Backend> deserialize::FromSql<VarChar, B> for ReviewDecision
+where
+    String: deserialize::FromSql<VarChar, B>,
+{
+    fn from_sql(bytes: B::RawValue<'_>) -> deserialize::Result<Self> {
+        <String as deserialize::FromSql<VarChar, B>>::from_sql(bytes).map(|sql| {
+            match sql.as_str() {
+                "Approved" => ReviewDecision::Approved,
+                "ChangesRequested" => ReviewDecision::ChangesRequested,
+                "ReviewRequired" => ReviewDecision::ReviewRequired,
+                _ => panic!("invalid review decision {sql}"),
+            }
+        })
+    }
+}
+
+#[derive(AsExpression, FromSqlRow, Debug, Clone, Copy, PartialEq)]
+#[diesel(sql_type = VarChar)]
+pub enum CiState {
+    Success,
+    Failure,
+    Pending,
+}
+
+impl<B: Backend> serialize::ToSql<VarChar, B> for CiState
+where
+    str: serialize::ToSql<VarChar, B>,
+{
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, B>) -> serialize::Result {
+        let ci_state = match self {
+            CiState::Success => "Success",
+            CiState::Failure => "Failure",
+            CiState::Pending => "Pending",
+        };
+        <str as serialize::ToSql<VarChar, B>>::to_sql(ci_state, out)
+    }
+}
+
+impl<B: Backend> deserialize::FromSql<VarChar, B> for CiState
+where
+    String: deserialize::FromSql<VarChar, B>,
+{
+    fn from_sql(bytes: B::RawValue<'_>) -> deserialize::Result<Self> {
+        <String as deserialize::FromSql<VarChar, B>>::from_sql(bytes).map(|sql| {
+            match sql.as_str() {
+                "Success" => CiState::Success,
+                "Failure" => CiState::Failure,
+                "Pending" => CiState::Pending,
+                _ => panic!("invalid ci state {sql}"),
+            }
+        })
+    }
+}
+
+/// Why an issue was last closed or reopened
+#[derive(AsExpression, FromSqlRow, Debug, Clone, Copy, PartialEq)]
+#[diesel(sql_type = VarChar)]
+pub enum StateReason {
+    Completed,
+    NotPlanned,
+    Duplicate,
+    Reopened,
+}
+
+impl<B: Backend> serialize::ToSql<VarChar, B> for StateReason
+where
+    str: serialize::ToSql<VarChar, B>,
+{
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, B>) -> serialize::Result {
+        let reason = match self {
+            StateReason::Completed => "Completed",
+            StateReason::NotPlanned => "NotPlanned",
+            StateReason::Duplicate => "Duplicate",
+            StateReason::Reopened => "Reopened",
+        };
+        <str as serialize::ToSql<VarChar, B>>::to_sql(reason, out)
+    }
+}
+
+impl<B: Backend> deserialize::FromSql<VarChar, B> for StateReason
+where
+    String: deserialize::FromSql<VarChar, B>,
+{
+    fn from_sql(bytes: B::RawValue<'_>) -> deserialize::Result<Self> {
+        <String as deserialize::FromSql<VarChar, B>>::from_sql(bytes).map(|sql| {
+            match sql.as_str() {
+                "Completed" => StateReason::Completed,
+                "NotPlanned" => StateReason::NotPlanned,
+                "Duplicate" => StateReason::Duplicate,
+                "Reopened" => StateReason::Reopened,
+                _ => panic!("invalid state reason {sql}"),
+            }
+        })
+    }
+}
+
+/// Kind of server of a profile: GitHub notifications, GitLab To-Do items or Gitea and Forgejo
+/// notifications
+#[derive(
+    AsExpression, FromSqlRow, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash,
+)]
+#[diesel(sql_type = VarChar)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    #[default]
+    GitHub,
+    GitLab,
+    /// Forgejo too, eg Codeberg
+    #[serde(alias = "forgejo")]
+    Gitea,
+}
+
+impl ForgeKind {
+    /// GitLab to-do items are only pending or done, reading one is local
+    pub fn has_read_state(self) -> bool {
+        self != ForgeKind::GitLab
+    }
+
+    /// Gitea threads are only unread or read, done is marked as read
+    pub fn has_done_state(self) -> bool {
+        self != ForgeKind::Gitea
+    }
+}
+
+impl<B: Backend> serialize::ToSql<VarChar, B> for ForgeKind
+where
+    str: serialize::ToSql<VarChar, B>,
+{
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, B>) -> serialize::Result {
+        let kind = match self {
+            ForgeKind::GitHub => "GitHub",
+            ForgeKind::GitLab => "GitLab",
+            ForgeKind::Gitea => "Gitea",
+        };
+        <str as serialize::ToSql<VarChar, B>>::to_sql(kind, out)
+    }
+}
+
+impl<B: Backend> deserialize::FromSql<VarChar, B> for ForgeKind
+where
+    String: deserialize::FromSql<VarChar, B>,
+{
+    fn from_sql(bytes: B::RawValue<'_>) -> deserialize::Result<Self> {
+        <String as deserialize::FromSql<VarChar, B>>::from_sql(bytes).map(|sql| {
+            match sql.as_str() {
+                "GitHub" => ForgeKind::GitHub,
+                "GitLab" => ForgeKind::GitLab,
+                "Gitea" => ForgeKind::Gitea,
+                _ => panic!("invalid forge {sql}"),
+            }
+        })
+    }
+}
+
 #[derive(Queryable, Selectable, Insertable, Identifiable, AsChangeset, Debug, Clone)]
 #[diesel(table_name = crate::schema::notifications)]
+#[diesel(primary_key(id, account))]
 pub struct Notification {
     pub id: String,
+    /// name of the profile this notification was fetched with
+    pub account: String,
     pub title: String,
     pub repo: String,
     pub url: String,
@@ -99,6 +295,64 @@ pub struct Notification {
     pub state: NotificationState,
     pub author: String,
     pub reason: String,
+    /// thread subscription set to ignored
+    pub muted: bool,
+    /// pull request still waiting for a review of mine or of one of my teams
+    pub review_requested: bool,
+    pub review_decision: Option<ReviewDecision>,
+    /// combined status of the checks of the last commit of a pull request
+    pub ci_state: Option<CiState>,
+    /// title of the milestone of an issue or a pull request
+    pub milestone: Option<String>,
+    /// latest comment or review of the thread
+    pub comment_author: Option<String>,
+    pub comment_at: Option<NaiveDateTime>,
+    /// beginning of the comment as plain text
+    pub comment_excerpt: Option<String>,
+    /// html url of the comment, with its anchor
+    pub comment_url: Option<String>,
+    pub state_reason: Option<StateReason>,
+    /// html url of the canonical issue of an issue closed as duplicate
+    pub duplicate_of: Option<String>,
+    pub release_tag: Option<String>,
+    pub prerelease: bool,
+    pub published_at: Option<NaiveDateTime>,
+    /// server the notification was fetched from
+    pub forge: ForgeKind,
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::schema::notification_labels)]
+pub struct NotificationLabel {
+    pub notification_id: String,
+    pub account: String,
+    pub name: String,
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::schema::notification_assignees)]
+pub struct NotificationAssignee {
+    pub notification_id: String,
+    pub account: String,
+    pub login: String,
+}
+
+/// Labels and assignees of an issue or a pull request, stored in their own tables
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Details {
+    pub labels: Vec<String>,
+    pub assignees: Vec<String>,
+}
+
+impl Details {
+    /// Sorted and deduplicated, as stored
+    pub fn new(mut labels: Vec<String>, mut assignees: Vec<String>) -> Details {
+        labels.sort();
+        labels.dedup();
+        assignees.sort();
+        assignees.dedup();
+        Details { labels, assignees }
+    }
 }
 
 impl Notification {
@@ -106,3 +360,101 @@ impl Notification {
         self.repo.split('/').next().unwrap().to_string()
     }
 }
+
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::schema::http_cache)]
+pub struct HttpCache {
+    pub url: String,
+    pub etag: String,
+    pub body: String,
+    pub updated_at: NaiveDateTime,
+}
+
+/// GitHub call applied locally but not yet sent
+#[derive(AsExpression, FromSqlRow, Debug, Clone, Copy, PartialEq)]
+#[diesel(sql_type = VarChar)]
+pub enum Action {
+    Done,
+    Read,
+    Mute,
+    Unmute,
+}
+
+impl<B: Backend> serialize::ToSql<VarChar, B> for Action
+where
+    str: serialize::ToSql<VarChar, B>,
+{
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, B>) -> serialize::Result {
+        let action = match self {
+            Action::Done => "Done",
+            Action::Read => "Read",
+            Action::Mute => "Mute",
+            Action::Unmute => "Unmute",
+        };
+        <str as serialize::ToSql<VarChar, B>>::to_sql(action, out)
+    }
+}
+
+impl<B: Backend> deserialize::FromSql<VarChar, B> for Action
+where
+    String: deserialize::FromSql<VarChar, B>,
+{
+    fn from_sql(bytes: B::RawValue<'_>) -> deserialize::Result<Self> {
+        <String as deserialize::FromSql<VarChar, B>>::from_sql(bytes).map(|sql| {
+            match sql.as_str() {
+                "Done" => Action::Done,
+                "Read" => Action::Read,
+                "Mute" => Action::Mute,
+                "Unmute" => Action::Unmute,
+                _ => panic!("invalid action {sql}"),
+            }
+        })
+    }
+}
+
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[diesel(table_name = crate::schema::pending_actions)]
+pub struct PendingAction {
+    pub id: i32,
+    pub notification_id: String,
+    pub account: String,
+    pub action: Action,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_attempt_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::pending_actions)]
+pub struct NewPendingAction {
+    pub notification_id: String,
+    pub account: String,
+    pub action: Action,
+    pub created_at: NaiveDateTime,
+}
+
+/// Outcome of the last sync of a profile, the server values are those of the last successful one
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Debug, Clone)]
+#[diesel(table_name = crate::schema::sync_state)]
+#[diesel(primary_key(account))]
+#[diesel(treat_none_as_null = true)]
+pub struct SyncState {
+    pub account: String,
+    /// end of the last sync, local clock
+    pub synced_at: NaiveDateTime,
+    pub succeeded_at: Option<NaiveDateTime>,
+    /// server `Date` when the notifications were listed, `since` of the next sync
+    pub since: Option<NaiveDateTime>,
+    /// server `Last-Modified`, sent back as `If-Modified-Since`
+    pub last_modified: Option<String>,
+    pub poll_interval: Option<i32>,
+    pub ratelimit_remaining: Option<i32>,
+    pub ratelimit_used: Option<i32>,
+    pub duration_ms: i32,
+    /// notifications fetched
+    pub fetched: i32,
+    pub error: Option<String>,
+    /// `Last-Modified` of the inbox at the last reconcile
+    pub inbox_last_modified: Option<String>,
+}
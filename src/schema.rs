@@ -1,8 +1,18 @@
 // @generated automatically by Diesel CLI.
 
 diesel::table! {
-    notifications (id) {
+    http_cache (url) {
+        url -> Text,
+        etag -> Text,
+        body -> Text,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    notifications (id, account) {
         id -> Text,
+        account -> Text,
         title -> Text,
         repo -> Text,
         url -> Text,
@@ -16,5 +26,75 @@ diesel::table! {
         score -> Integer,
         score_boost -> Integer,
         reason -> Text,
+        muted -> Bool,
+        review_requested -> Bool,
+        review_decision -> Nullable<Text>,
+        ci_state -> Nullable<Text>,
+        milestone -> Nullable<Text>,
+        comment_author -> Nullable<Text>,
+        comment_at -> Nullable<Timestamp>,
+        comment_excerpt -> Nullable<Text>,
+        comment_url -> Nullable<Text>,
+        state_reason -> Nullable<Text>,
+        duplicate_of -> Nullable<Text>,
+        release_tag -> Nullable<Text>,
+        prerelease -> Bool,
+        published_at -> Nullable<Timestamp>,
+        forge -> Text,
     }
 }
+
+diesel::table! {
+    notification_labels (notification_id, account, name) {
+        notification_id -> Text,
+        account -> Text,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    notification_assignees (notification_id, account, login) {
+        notification_id -> Text,
+        account -> Text,
+        login -> Text,
+    }
+}
+
+diesel::table! {
+    pending_actions (id) {
+        id -> Integer,
+        notification_id -> Text,
+        account -> Text,
+        action -> Text,
+        attempts -> Integer,
+        last_error -> Nullable<Text>,
+        created_at -> Timestamp,
+        last_attempt_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    sync_state (account) {
+        account -> Text,
+        synced_at -> Timestamp,
+        succeeded_at -> Nullable<Timestamp>,
+        since -> Nullable<Timestamp>,
+        last_modified -> Nullable<Text>,
+        poll_interval -> Nullable<Integer>,
+        ratelimit_remaining -> Nullable<Integer>,
+        ratelimit_used -> Nullable<Integer>,
+        duration_ms -> Integer,
+        fetched -> Integer,
+        error -> Nullable<Text>,
+        inbox_last_modified -> Nullable<Text>,
+    }
+}
+
+diesel::allow_tables_to_appear_in_same_query!(
+    http_cache,
+    notification_assignees,
+    notification_labels,
+    notifications,
+    pending_actions,
+    sync_state,
+);
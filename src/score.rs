@@ -1,4 +1,4 @@
-use crate::models::Notification;
+use crate::models::{CiState, Details, Notification, ReviewDecision};
 use core::fmt;
 use log::{debug, error, info};
 use serde::Deserialize;
@@ -14,6 +14,10 @@ enum RuleType {
     Title,
     Org,
     Reason,
+    Review,
+    Ci,
+    Label,
+    Release,
 }
 
 #[derive(Deserialize, Debug)]
@@ -32,15 +36,19 @@ pub struct Rule {
 }
 
 impl Rule {
-    pub fn matcher(&self, notification: &Notification) -> i32 {
-        let fct = match self.rule {
-            RuleType::Author => rule_author,
-            RuleType::Repo => rule_repo,
-            RuleType::Title => rule_title,
-            RuleType::Org => rule_org,
-            RuleType::Reason => rule_reason,
+    pub fn matcher(&self, notification: &Notification, details: &Details) -> i32 {
+        let matched = match self.rule {
+            RuleType::Author => rule_author(notification, &self.params),
+            RuleType::Repo => rule_repo(notification, &self.params),
+            RuleType::Title => rule_title(notification, &self.params),
+            RuleType::Org => rule_org(notification, &self.params),
+            RuleType::Reason => rule_reason(notification, &self.params),
+            RuleType::Review => rule_review(notification, &self.params),
+            RuleType::Ci => rule_ci(notification, &self.params),
+            RuleType::Label => rule_label(details, &self.params),
+            RuleType::Release => rule_release(notification, &self.params),
         };
-        if fct(notification, &self.params) {
+        if matched {
             info!(
                 "{} match {} score:{}",
                 notification.title, self.name, self.score
@@ -86,16 +94,16 @@ impl Scorer {
         Ok(Scorer { rules: rules? })
     }
 
-    pub fn score(&self, notification: &Notification) -> i32 {
+    pub fn score(&self, notification: &Notification, details: &Details) -> i32 {
         self.rules
             .iter()
-            .fold(0, |acc, rule| acc + rule.matcher(notification))
+            .fold(0, |acc, rule| acc + rule.matcher(notification, details))
     }
 
-    pub fn explain(&self, notification: &Notification) -> Vec<Rule> {
+    pub fn explain(&self, notification: &Notification, details: &Details) -> Vec<Rule> {
         self.rules
             .iter()
-            .filter(|rule| rule.matcher(notification) != 0)
+            .filter(|rule| rule.matcher(notification, details) != 0)
             .cloned()
             .collect()
     }
@@ -108,6 +116,10 @@ fn rule_from_str(rule_name: &str) -> Result<RuleType, String> {
         "title" => Ok(RuleType::Title),
         "org" => Ok(RuleType::Org),
         "reason" => Ok(RuleType::Reason),
+        "review" => Ok(RuleType::Review),
+        "ci" => Ok(RuleType::Ci),
+        "label" => Ok(RuleType::Label),
+        "release" => Ok(RuleType::Release),
         _ => Err(rule_name.into()),
     }
 }
@@ -144,6 +156,100 @@ fn rule_reason(notification: &Notification, params: &[String]) -> bool {
     params.iter().any(|p| notification.reason.contains(p))
 }
 
+fn rule_review(notification: &Notification, params: &[String]) -> bool {
+    params.iter().any(|p| match p.as_str() {
+        "requested" => notification.review_requested,
+        "approved" => notification.review_decision == Some(ReviewDecision::Approved),
+        "changes_requested" => {
+            notification.review_decision == Some(ReviewDecision::ChangesRequested)
+        }
+        "review_required" => notification.review_decision == Some(ReviewDecision::ReviewRequired),
+        _ => false,
+    })
+}
+
+fn rule_ci(notification: &Notification, params: &[String]) -> bool {
+    params.iter().any(|p| match p.as_str() {
+        "success" => notification.ci_state == Some(CiState::Success),
+        "failure" => notification.ci_state == Some(CiState::Failure),
+        "pending" => notification.ci_state == Some(CiState::Pending),
+        _ => false,
+    })
+}
+
+fn rule_label(details: &Details, params: &[String]) -> bool {
+    params.iter().any(|p| {
+        details
+            .labels
+            .iter()
+            .any(|label| label.eq_ignore_ascii_case(p))
+    })
+}
+
+fn rule_release(notification: &Notification, params: &[String]) -> bool {
+    let Some(tag) = &notification.release_tag else {
+        return false;
+    };
+    let version = Version::parse(tag);
+    params.iter().any(|p| match p.as_str() {
+        "prerelease" => notification.prerelease || version.as_ref().is_some_and(|v| v.prerelease),
+        "major" => version.as_ref().map(Version::level) == Some(ReleaseLevel::Major),
+        "minor" => version.as_ref().map(Version::level) == Some(ReleaseLevel::Minor),
+        "patch" => version.as_ref().map(Version::level) == Some(ReleaseLevel::Patch),
+        _ => false,
+    })
+}
+
+#[derive(PartialEq, Debug)]
+enum ReleaseLevel {
+    Major,
+    Minor,
+    Patch,
+}
+
+/// Version found in a release tag, laxer than semver: `v1.2`, `release-1.2.3` or `pkg@1.2.3-rc.1`
+#[derive(PartialEq, Debug)]
+struct Version {
+    numbers: Vec<u64>,
+    prerelease: bool,
+}
+
+impl Version {
+    fn parse(tag: &str) -> Option<Version> {
+        // monorepos prefix the tag with the package name, which may contain digits
+        let tag = tag.rsplit('@').next()?;
+        let tag = &tag[tag.find(|c: char| c.is_ascii_digit())?..];
+        let (core, suffix) = match tag.find(['-', '+']) {
+            Some(idx) => (&tag[..idx], &tag[idx..]),
+            None => (tag, ""),
+        };
+        let numbers = core
+            .split('.')
+            .map(str::parse)
+            .collect::<Result<Vec<u64>, _>>()
+            .ok()?;
+        Some(Version {
+            numbers,
+            prerelease: suffix.starts_with('-'),
+        })
+    }
+
+    /// Which number was bumped, assuming the previous ones were: 2.0.0 is a major release, 1.3.0
+    /// a minor one. Like cargo, `0.y.0` counts as major and `0.y.z` as patch.
+    fn level(&self) -> ReleaseLevel {
+        let rest_is_zero = |from: usize| self.numbers.iter().skip(from).all(|n| *n == 0);
+        match self.numbers.first() {
+            Some(0) if self.numbers.get(1).is_some_and(|minor| *minor > 0) && rest_is_zero(2) => {
+                ReleaseLevel::Major
+            }
+            Some(0) => ReleaseLevel::Patch,
+            _ if rest_is_zero(1) => ReleaseLevel::Major,
+            _ if rest_is_zero(2) => ReleaseLevel::Minor,
+            _ => ReleaseLevel::Patch,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
     RuleFileNotFound,
@@ -189,8 +295,8 @@ impl From<String> for Error {
 mod tests {
     use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 
-    use crate::models::NotificationState;
     use crate::models::NotificationType;
+    use crate::models::{ForgeKind, NotificationState};
 
     use super::*;
     use std::collections::HashSet;
@@ -198,6 +304,7 @@ mod tests {
     fn create_notification() -> Notification {
         Notification {
             id: "1".to_string(),
+            account: "default".to_string(),
             reason: "participating".to_string(),
             title: "title".into(),
             url: "http://exemple.com".into(),
@@ -213,6 +320,21 @@ mod tests {
             state: NotificationState::Open,
             author: "JohnDoe".into(),
             score_boost: 0,
+            muted: false,
+            review_requested: false,
+            review_decision: None,
+            ci_state: None,
+            milestone: None,
+            comment_author: None,
+            comment_at: None,
+            comment_excerpt: None,
+            comment_url: None,
+            state_reason: None,
+            duplicate_of: None,
+            release_tag: None,
+            prerelease: false,
+            published_at: None,
+            forge: ForgeKind::GitHub,
         }
     }
 
@@ -246,53 +368,131 @@ mod tests {
 
         let db_notification = create_notification();
 
-        assert_eq!(scorer.score(&db_notification), 105);
+        assert_eq!(scorer.score(&db_notification, &Details::default()), 105);
     }
 
     #[test]
     fn test_scorer_title() {
         let notification = create_notification();
 
-        assert_eq!(
-            rule_title(
-                &notification,
-                &vec!["bad title".into(), "title".into(), "another title".into()]
-            ),
-            true
-        );
-        assert_eq!(
-            rule_title(
-                &notification,
-                &vec!["bad title".into(), "another title".into()]
-            ),
-            false
-        );
+        assert!(rule_title(
+            &notification,
+            &["bad title".into(), "title".into(), "another title".into()]
+        ));
+        assert!(!rule_title(
+            &notification,
+            &["bad title".into(), "another title".into()]
+        ));
     }
 
     #[test]
     fn test_scorer_org() {
         let notification = create_notification();
 
-        assert_eq!(rule_org(&notification, &vec!["torvalds".into()]), true);
-        assert_eq!(rule_org(&notification, &vec!["!torvalds".into()]), false);
-        assert_eq!(rule_org(&notification, &vec!["!rms".into()]), true);
-        assert_eq!(rule_org(&notification, &vec!["deraadt".into()]), false)
+        assert!(rule_org(&notification, &["torvalds".into()]));
+        assert!(!rule_org(&notification, &["!torvalds".into()]));
+        assert!(rule_org(&notification, &["!rms".into()]));
+        assert!(!rule_org(&notification, &["deraadt".into()]))
     }
 
     #[test]
     fn test_scorer_reason() {
         let notification = create_notification();
 
+        assert!(rule_reason(
+            &notification,
+            &["comment".into(), "participating".into(), "mention".into()]
+        ));
+        assert!(!rule_reason(
+            &notification,
+            &["comment".into(), "mention".into()]
+        ));
+    }
+
+    #[test]
+    fn test_scorer_review() {
+        let mut notification = create_notification();
+        assert!(!rule_review(&notification, &["requested".into()]));
+
+        notification.review_requested = true;
+        notification.review_decision = Some(ReviewDecision::ChangesRequested);
+        assert!(rule_review(&notification, &["requested".into()]));
+        assert!(rule_review(
+            &notification,
+            &["approved".into(), "changes_requested".into()]
+        ));
+        assert!(!rule_review(&notification, &["approved".into()]));
+    }
+
+    #[test]
+    fn test_scorer_ci() {
+        let mut notification = create_notification();
+        assert!(!rule_ci(&notification, &["success".into()]));
+
+        notification.ci_state = Some(CiState::Failure);
+        assert!(rule_ci(
+            &notification,
+            &["failure".into(), "pending".into()]
+        ));
+        assert!(!rule_ci(&notification, &["success".into()]));
+    }
+
+    #[test]
+    fn test_scorer_label() {
+        let details = Details {
+            labels: vec!["P0".into(), "security".into()],
+            assignees: vec![],
+        };
+
+        assert!(rule_label(&details, &["p0".into(), "P1".into()]));
+        assert!(rule_label(&details, &["security".into()]));
+        assert!(!rule_label(&details, &["P1".into()]));
+        assert!(!rule_label(&Details::default(), &["P0".into()]));
+    }
+
+    #[test]
+    fn test_version() {
+        let level = |tag: &str| Version::parse(tag).map(|v| v.level());
+        assert_eq!(level("v2.0.0"), Some(ReleaseLevel::Major));
+        assert_eq!(level("v2"), Some(ReleaseLevel::Major));
+        assert_eq!(level("release-1.3"), Some(ReleaseLevel::Minor));
+        assert_eq!(level("1.3.0"), Some(ReleaseLevel::Minor));
+        assert_eq!(level("1.3.1"), Some(ReleaseLevel::Patch));
+        assert_eq!(level("2025.4.0.1"), Some(ReleaseLevel::Patch));
+        assert_eq!(level("0.5.0"), Some(ReleaseLevel::Major));
+        assert_eq!(level("0.0.14"), Some(ReleaseLevel::Patch));
+        assert_eq!(level("0.5.2"), Some(ReleaseLevel::Patch));
+        assert_eq!(level("@scope/pkg2@3.0.0"), Some(ReleaseLevel::Major));
+        assert_eq!(level("nightly"), None);
+        assert_eq!(level("1.x"), None);
+
+        assert!(Version::parse("v2.0.0-rc.1").unwrap().prerelease);
+        assert!(!Version::parse("v2.0.0+build.5").unwrap().prerelease);
         assert_eq!(
-            rule_reason(
-                &notification,
-                &vec!["comment".into(), "participating".into(), "mention".into()]
-            ),
-            true
-        );
-        assert_eq!(
-            rule_reason(&notification, &vec!["comment".into(), "mention".into()]),
-            false
+            Version::parse("v2.0.0-rc.1").unwrap().numbers,
+            vec![2, 0, 0]
         );
     }
+
+    #[test]
+    fn test_scorer_release() {
+        let mut notification = create_notification();
+        notification.type_ = NotificationType::Release;
+        assert!(!rule_release(&notification, &["major".into()]));
+
+        notification.release_tag = Some("v3.0.0".into());
+        assert!(rule_release(&notification, &["major".into()]));
+        assert!(!rule_release(
+            &notification,
+            &["minor".into(), "patch".into(), "prerelease".into()]
+        ));
+
+        notification.release_tag = Some("v3.0.0-beta.2".into());
+        assert!(rule_release(&notification, &["prerelease".into()]));
+
+        notification.release_tag = Some("v3.0.1".into());
+        notification.prerelease = true;
+        assert!(rule_release(&notification, &["patch".into()]));
+        assert!(rule_release(&notification, &["prerelease".into()]));
+    }
 }
@@ -1,127 +1,262 @@
-use crate::dirs;
+use crate::config::{DEFAULT_PROFILE, Profile, Recording, Subscription};
+use crate::feed::Fetched;
+use crate::forge::ProfileForge;
 use crate::models::Notification as DBNotification;
+use crate::models::{
+    Action, Details, ForgeKind, HttpCache, NewPendingAction, NotificationAssignee,
+    NotificationLabel, NotificationType, PendingAction, SyncState,
+};
 use crate::score::{Rule, Scorer};
 use crate::*;
 use anyhow::Result;
 use anyhow::anyhow;
-use chrono::NaiveDateTime;
-use diesel::dsl::insert_into;
+use chrono::{NaiveDateTime, Utc};
+use diesel::dsl::{exists, insert_into};
 use diesel::prelude::*;
 use diesel::update;
 use filter::Filter;
-use gh::UpdateStatus;
+use futures::StreamExt;
+use futures::stream::iter;
+use gh::{Clients, ListingInfo, UpdateStatus};
 use log::{debug, error, info};
-use models::NotificationState;
 use schema::notifications::dsl::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
 
-pub async fn check_update_and_limit(mut connection: DbConnection) -> Result<UpdateStatus> {
-    let last_update = get_recent_update(&mut connection).ok_or(anyhow!("no recent update"))?;
-    gh::check_update_and_limit(last_update).await
-}
-
-pub async fn sync(connection: &mut DbConnection) -> Result<()> {
-    let last_update = get_recent_update(connection);
+/// Feeds have no polling hint, they are fetched again after half an hour
+const FEED_REFRESH_SEC: i64 = 1800;
 
-    let gh_notifications = gh::fetch_notifications(last_update).await?;
+/// A pending action failing that many times is parked, it's only replayed again once redone
+pub const MAX_PENDING_ATTEMPTS: i32 = 10;
+const PENDING_BASE_DELAY_SEC: i64 = 5;
+const PENDING_MAX_DELAY_SEC: i64 = 3600;
 
-    let (gh_prs, gh_releases, gh_issues) = tokio::join!(
-        gh::fetch_prs(&gh_notifications),
-        gh::fetch_releases(&gh_notifications),
-        gh::fetch_issues(&gh_notifications)
-    );
-    let (gh_prs, gh_releases, gh_issues) = (gh_prs?, gh_releases?, gh_issues?);
+/// Status of all the profiles merged, an update is also needed when a feed is due
+pub async fn check_update_and_limit(
+    mut connection: DbConnection,
+    clients: &Clients,
+) -> Result<UpdateStatus> {
+    let config = Config::get();
+    let mut status: Option<UpdateStatus> = None;
+    for profile in &config.profiles {
+        let last_sync = get_sync_state(&mut connection, &profile.name);
+        let last_update = last_sync
+            .as_ref()
+            .and_then(|last_sync| last_sync.since)
+            .or_else(|| get_recent_update(&mut connection, &profile.name))
+            .ok_or(anyhow!("no recent update for {}", profile.name))?;
+        let last_modified = last_sync.and_then(|last_sync| last_sync.last_modified);
+        let profile_status = ProfileForge::new(clients, profile)?
+            .check_update(last_update, last_modified.as_deref())
+            .await?;
+        status = Some(match status {
+            Some(status) => status.merge(profile_status),
+            None => profile_status,
+        });
+    }
+    let mut status = status.ok_or(anyhow!("no profile"))?;
+    status.need_update |= config
+        .feeds
+        .iter()
+        .any(|subscription| feed_due(&mut connection, subscription));
+    Ok(status)
+}
 
+/// Sync every profile then the feeds that are due, a failing profile or feed doesn't prevent the
+/// others to be synced
+pub async fn sync(connection: &mut DbConnection, clients: &Clients) -> Result<()> {
     let config = Config::get();
-    let scorer = Scorer::new(config.rules_path)?;
-
-    info!("inserting {} notifications", gh_notifications.len());
-    for gh_notification in gh_notifications {
-        let notif_url = &gh_notification.subject.url.unwrap_or(String::default());
-        let (_url, _type, _author, _state) = match gh_notification.subject.r#type {
-            gh::NotificationType::PullRequest => {
-                let pr = gh_prs
-                    .iter()
-                    .find(|pr| pr.url == *notif_url)
-                    .ok_or(anyhow!("no pr found"))?;
-
-                (
-                    pr.html_url.clone(),
-                    models::NotificationType::PullRequest,
-                    pr.user.login.clone(),
-                    if pr.state.as_ref() as &str == "closed" {
-                        if pr.merged {
-                            NotificationState::Resolved
-                        } else {
-                            NotificationState::Canceled
-                        }
-                    } else if pr.draft {
-                        NotificationState::Draft
-                    } else {
-                        NotificationState::Open
-                    },
-                )
-            }
-            gh::NotificationType::Release => {
-                let release = gh_releases
-                    .iter()
-                    .find(|release| release.url == *notif_url)
-                    .ok_or(anyhow!("no release found"))?;
-                (
-                    release.html_url.clone(),
-                    models::NotificationType::Release,
-                    release.author.login.clone(),
-                    NotificationState::Open,
-                )
+    let mut res = Ok(());
+    for profile in &config.profiles {
+        let start = Instant::now();
+        let profile_res = sync_profile(connection, clients, profile).await;
+        if let Err(err) = record_sync(connection, &profile.name, start.elapsed(), &profile_res) {
+            error!("cannot record sync of {}: {:?}", profile.name, err);
+        }
+        if let Err(err) = profile_res {
+            error!("cannot sync profile {}: {:?}", profile.name, err);
+            if res.is_ok() {
+                res = Err(err);
             }
-            gh::NotificationType::Issue => {
-                let issue = gh_issues
-                    .iter()
-                    .find(|issue| issue.url == *notif_url)
-                    .ok_or(anyhow!("no issue found"))?;
-                (
-                    issue.html_url.clone(),
-                    models::NotificationType::Issue,
-                    issue.user.login.clone(),
-                    if issue.state == "open" {
-                        models::NotificationState::Open
-                    } else {
-                        models::NotificationState::Resolved
-                    },
-                )
+        }
+    }
+    if let Err(err) = prune_http_cache(connection) {
+        error!("cannot prune the http cache: {:?}", err);
+    }
+    // feeds are not recorded, nothing is sent when replaying
+    if matches!(config.recording, Some(Recording::Replay(_))) {
+        return res;
+    }
+    for subscription in &config.feeds {
+        if !feed_due(connection, subscription) {
+            continue;
+        }
+        let start = Instant::now();
+        let feed_res = sync_feed(connection, clients, subscription).await;
+        if let Err(err) = record_sync(connection, &subscription.name, start.elapsed(), &feed_res) {
+            error!("cannot record sync of {}: {:?}", subscription.name, err);
+        }
+        if let Err(err) = feed_res {
+            error!("cannot sync feed {}: {:?}", subscription.name, err);
+            if res.is_ok() {
+                res = Err(err);
             }
-            gh::NotificationType::Unknown => (
-                "".into(),
-                models::NotificationType::PullRequest,
-                "".into(),
-                models::NotificationState::Canceled,
-            ),
-        };
-        let mut db_notification = DBNotification {
-            id: gh_notification.id,
-            reason: gh_notification.reason,
-            title: gh_notification.subject.title.trim().into(),
-            repo: gh_notification.repository.full_name,
-            unread: gh_notification.unread,
-            updated_at: gh_notification.updated_at,
-            done: false,
-            score: -1, // mutable
-            score_boost: 0,
-            url: _url,
-            type_: _type,
-            author: _author,
-            state: _state,
+        }
+    }
+    res
+}
+
+/// Objects are cached when fetched with the REST API, and touched every time they are used. An
+/// entry unused since the oldest notification listed was updated belongs to a thread marked as
+/// done, it is deleted. The ETags of the feeds are kept.
+fn prune_http_cache(connection: &mut DbConnection) -> Result<usize> {
+    let oldest: Option<NaiveDateTime> = notifications
+        .select(diesel::dsl::min(updated_at))
+        .filter(done.eq(false))
+        .filter(type_.ne(NotificationType::FeedEntry))
+        .first(connection)?;
+    let feed_urls: Vec<String> = Config::get()
+        .feeds
+        .into_iter()
+        .map(|subscription| subscription.url)
+        .collect();
+    let pruned = diesel::delete(schema::http_cache::table)
+        .filter(schema::http_cache::updated_at.lt(oldest.unwrap_or(NaiveDateTime::MAX)))
+        .filter(schema::http_cache::url.ne_all(feed_urls))
+        .execute(connection)?;
+    debug!("{} entries pruned from the http cache", pruned);
+    Ok(pruned)
+}
+
+/// Save the outcome of a sync, a failed one keeps the server values of the last success
+fn record_sync(
+    connection: &mut DbConnection,
+    profile_name: &str,
+    duration: Duration,
+    res: &Result<(usize, ListingInfo)>,
+) -> Result<()> {
+    let now = Utc::now().naive_utc();
+    let duration_ms = i32::try_from(duration.as_millis()).unwrap_or(i32::MAX);
+    let previous = get_sync_state(connection, profile_name).unwrap_or(SyncState {
+        account: profile_name.to_string(),
+        synced_at: now,
+        succeeded_at: None,
+        since: None,
+        last_modified: None,
+        poll_interval: None,
+        ratelimit_remaining: None,
+        ratelimit_used: None,
+        duration_ms,
+        fetched: 0,
+        error: None,
+        inbox_last_modified: None,
+    });
+    let to_i32 = |value: Option<u64>| value.and_then(|value| i32::try_from(value).ok());
+    let sync_state = match res {
+        Ok((fetched, info)) => SyncState {
+            synced_at: now,
+            succeeded_at: Some(now),
+            // without a server date, fall back on the newest notification
+            since: info
+                .date
+                .or_else(|| get_recent_update(connection, profile_name)),
+            last_modified: info.last_modified.clone(),
+            poll_interval: to_i32(info.poll_interval),
+            ratelimit_remaining: to_i32(info.ratelimit_remaining),
+            ratelimit_used: to_i32(info.ratelimit_used),
+            duration_ms,
+            fetched: i32::try_from(*fetched).unwrap_or(i32::MAX),
+            error: None,
+            ..previous
+        },
+        Err(err) => SyncState {
+            synced_at: now,
+            duration_ms,
+            fetched: 0,
+            error: Some(format!("{err:#}")),
+            ..previous
+        },
+    };
+    insert_into(schema::sync_state::table)
+        .values(&sync_state)
+        .on_conflict(schema::sync_state::account)
+        .do_update()
+        .set(&sync_state)
+        .execute(connection)?;
+    Ok(())
+}
+
+/// Sync state of every profile that was synced at least once
+pub fn get_sync_states(connection: &mut DbConnection) -> Result<Vec<SyncState>> {
+    Ok(schema::sync_state::table
+        .select(SyncState::as_select())
+        .order_by(schema::sync_state::account)
+        .load(connection)?)
+}
+
+/// Oldest successful sync of the profiles, none if a profile was never synced
+pub fn last_synced(connection: &mut DbConnection) -> Option<NaiveDateTime> {
+    Config::get()
+        .profiles
+        .iter()
+        .map(|profile| get_sync_state(connection, &profile.name)?.succeeded_at)
+        .collect::<Option<Vec<_>>>()?
+        .into_iter()
+        .min()
+}
+
+fn get_sync_state(connection: &mut DbConnection, profile_name: &str) -> Option<SyncState> {
+    schema::sync_state::table
+        .select(SyncState::as_select())
+        .find(profile_name)
+        .first(connection)
+        .ok()
+}
+
+async fn sync_profile(
+    connection: &mut DbConnection,
+    clients: &Clients,
+    profile: &Profile,
+) -> Result<(usize, ListingInfo)> {
+    let profile_forge = ProfileForge::new(clients, profile)?;
+    let last_update = get_sync_state(connection, &profile.name)
+        .and_then(|last_sync| last_sync.since)
+        .or_else(|| get_recent_update(connection, &profile.name));
+
+    let (db_notifications, info) =
+        fetch_profile_notifications(connection, &profile_forge, profile, last_update).await?;
+    let fetched = db_notifications.len();
+
+    info!(
+        "inserting {} notifications for {}",
+        db_notifications.len(),
+        profile.name
+    );
+    // what the forge can't tell is kept
+    let local_state: HashMap<String, (bool, bool)> =
+        if profile.forge.has_read_state() && profile.forge.has_done_state() {
+            HashMap::new()
+        } else {
+            notifications
+                .filter(account.eq(&profile.name))
+                .select((id, (unread, done)))
+                .load(connection)?
+                .into_iter()
+                .collect()
         };
-        let computed_score = scorer.score(&db_notification);
-        db_notification.score = computed_score;
-        debug!(
-            "score {} for {} {}",
-            computed_score,
-            db_notification.title,
-            db_notification.url // TODO: display trait
-        );
+    for (mut db_notification, details) in db_notifications {
+        // a done thread comes back when updated, without a done state only once unread again
+        let mut keep_done = false;
+        if let Some(&(local_unread, local_done)) = local_state.get(&db_notification.id) {
+            if !profile.forge.has_read_state() {
+                db_notification.unread = local_unread;
+            }
+            keep_done = !profile.forge.has_done_state() && local_done && !db_notification.unread;
+        }
         let res = insert_into(notifications)
             .values(&db_notification)
-            .on_conflict(id)
+            .on_conflict((id, account))
             .do_update()
             .set((
                 reason.eq(&db_notification.reason),
@@ -129,14 +264,29 @@ pub async fn sync(connection: &mut DbConnection) -> Result<()> {
                 unread.eq(db_notification.unread),
                 repo.eq(&db_notification.repo),
                 updated_at.eq(db_notification.updated_at),
-                done.eq(false),
+                done.eq(keep_done),
                 score.eq(db_notification.score),
                 url.eq(&db_notification.url),
                 type_.eq(&db_notification.type_),
                 author.eq(&db_notification.author),
                 state.eq(&db_notification.state),
+                review_requested.eq(db_notification.review_requested),
+                review_decision.eq(db_notification.review_decision),
+                ci_state.eq(db_notification.ci_state),
+                milestone.eq(&db_notification.milestone),
+                comment_author.eq(&db_notification.comment_author),
+                comment_at.eq(db_notification.comment_at),
+                comment_excerpt.eq(&db_notification.comment_excerpt),
+                comment_url.eq(&db_notification.comment_url),
+                state_reason.eq(db_notification.state_reason),
+                duplicate_of.eq(&db_notification.duplicate_of),
+                release_tag.eq(&db_notification.release_tag),
+                prerelease.eq(db_notification.prerelease),
+                published_at.eq(db_notification.published_at),
+                forge.eq(db_notification.forge),
             ))
-            .execute(connection);
+            .execute(connection)
+            .and_then(|_| save_details(connection, &db_notification, &details));
         if res.is_err() {
             error!(
                 "insert err {} {:?}",
@@ -145,7 +295,529 @@ pub async fn sync(connection: &mut DbConnection) -> Result<()> {
             )
         }
     }
-    Ok(())
+    apply_pending_actions(connection, &profile.name)?;
+    Ok((fetched, info))
+}
+
+/// Never synced, or not for `FEED_REFRESH_SEC`
+fn feed_due(connection: &mut DbConnection, subscription: &Subscription) -> bool {
+    let now = Utc::now().naive_utc();
+    get_sync_state(connection, &subscription.name)
+        .is_none_or(|last_sync| (now - last_sync.synced_at).num_seconds() >= FEED_REFRESH_SEC)
+}
+
+/// Entries of the feed, fetched with the ETag of the last fetch. Entries marked as done stay
+/// done, a feed keeps its entries long after they are read.
+async fn sync_feed(
+    connection: &mut DbConnection,
+    clients: &Clients,
+    subscription: &Subscription,
+) -> Result<(usize, ListingInfo)> {
+    let cached_etag = schema::http_cache::table
+        .select(HttpCache::as_select())
+        .find(&subscription.url)
+        .first(connection)
+        .optional()?
+        .map(|entry| entry.etag);
+    let (body, new_etag) =
+        match feed::fetch(clients.http(), &subscription.url, cached_etag.as_deref()).await? {
+            Fetched::NotModified => {
+                debug!("feed {} not modified", subscription.name);
+                return Ok((0, ListingInfo::default()));
+            }
+            Fetched::Modified { body, etag } => (body, etag),
+        };
+    let parsed = feed::parse(&body)?;
+
+    let scorer = Scorer::new(subscription.rules_path.clone())?;
+    let no_details = Details::default();
+    let entries = feed::notifications(subscription, &parsed);
+    let fetched = entries.len();
+    info!("inserting {} entries for {}", fetched, subscription.name);
+    for mut entry in entries {
+        entry.score = scorer.score(&entry, &no_details);
+        let res = insert_into(notifications)
+            .values(&entry)
+            .on_conflict((id, account))
+            .do_update()
+            .set((
+                title.eq(&entry.title),
+                url.eq(&entry.url),
+                author.eq(&entry.author),
+                updated_at.eq(entry.updated_at),
+                score.eq(entry.score),
+            ))
+            .execute(connection);
+        // one bad entry doesn't hold back the others
+        if let Err(err) = res {
+            error!("insert err {} {:?}", err, entry);
+        }
+    }
+
+    // only the ETag is kept, the entries are in the database already
+    if let Some(etag) = new_etag {
+        let entry = HttpCache {
+            url: subscription.url.clone(),
+            etag,
+            body: String::new(),
+            updated_at: Utc::now().naive_utc(),
+        };
+        insert_into(schema::http_cache::table)
+            .values(&entry)
+            .on_conflict(schema::http_cache::url)
+            .do_update()
+            .set((
+                schema::http_cache::etag.eq(&entry.etag),
+                schema::http_cache::updated_at.eq(entry.updated_at),
+            ))
+            .execute(connection)?;
+    }
+    Ok((fetched, ListingInfo::default()))
+}
+
+/// Notifications of the profile updated since `since`, with their details and score
+async fn fetch_profile_notifications(
+    connection: &mut DbConnection,
+    profile_forge: &ProfileForge,
+    profile: &Profile,
+    since: Option<NaiveDateTime>,
+) -> Result<(Vec<(DBNotification, Details)>, ListingInfo)> {
+    let (mut db_notifications, info) = profile_forge.fetch_notifications(connection, since).await?;
+
+    let scorer = Scorer::new(profile.rules_path.clone())?;
+    for (db_notification, details) in &mut db_notifications {
+        let computed_score = scorer.score(db_notification, details);
+        db_notification.score = computed_score;
+        debug!(
+            "score {} for {} {}",
+            computed_score,
+            db_notification.title,
+            db_notification.url // TODO: display trait
+        );
+    }
+    Ok((db_notifications, info))
+}
+
+/// Catch up with the threads handled on github.com or another device: the ones gone from the
+/// inbox are marked as done, the ones read elsewhere as read. Return the number of notifications
+/// updated.
+pub async fn reconcile(connection: &mut DbConnection, clients: &Clients) -> Result<usize> {
+    let config = Config::get();
+    let mut res = Ok(0);
+    for profile in &config.profiles {
+        match reconcile_profile(connection, clients, profile).await {
+            Ok(updated) => {
+                if let Ok(total) = &mut res {
+                    *total += updated;
+                }
+            }
+            Err(err) => {
+                error!("cannot reconcile profile {}: {:?}", profile.name, err);
+                if res.is_ok() {
+                    res = Err(err);
+                }
+            }
+        }
+    }
+    res
+}
+
+async fn reconcile_profile(
+    connection: &mut DbConnection,
+    clients: &Clients,
+    profile: &Profile,
+) -> Result<usize> {
+    let local = notifications
+        .select(DBNotification::as_select())
+        .filter(account.eq(&profile.name))
+        .filter(done.eq(false))
+        .load(connection)?;
+    // older threads can't tell anything about the local ones
+    let Some(oldest) = local
+        .iter()
+        .map(|notification| notification.updated_at)
+        .min()
+    else {
+        return Ok(0);
+    };
+    let last_modified = get_sync_state(connection, &profile.name)
+        .and_then(|sync_state| sync_state.inbox_last_modified);
+    // `since` is exclusive on the server
+    let Some(inbox) = ProfileForge::new(clients, profile)?
+        .inbox(
+            oldest - chrono::Duration::seconds(1),
+            last_modified.as_deref(),
+        )
+        .await?
+    else {
+        info!("reconcile {}: inbox not modified", profile.name);
+        return Ok(0);
+    };
+    update(schema::sync_state::table.find(&profile.name))
+        .set(schema::sync_state::inbox_last_modified.eq(&inbox.last_modified))
+        .execute(connection)?;
+
+    let mut gone = vec![];
+    let mut read = vec![];
+    for notification in local {
+        match inbox.threads.get(&notification.id) {
+            None => gone.push(notification.id),
+            Some(false) if notification.unread => read.push(notification.id),
+            _ => {}
+        }
+    }
+    info!(
+        "reconcile {}: {} done and {} read elsewhere",
+        profile.name,
+        gone.len(),
+        read.len()
+    );
+
+    let updated = update(notifications)
+        .filter(account.eq(&profile.name))
+        .filter(id.eq_any(gone))
+        .set(done.eq(true))
+        .execute(connection)?
+        + update(notifications)
+            .filter(account.eq(&profile.name))
+            .filter(id.eq_any(read))
+            .set(unread.eq(false))
+            .execute(connection)?;
+    Ok(updated)
+}
+
+/// Settings in use and whether each profile can reach its server, see `riirview doctor`
+#[derive(Debug, Default)]
+pub struct DoctorReport {
+    pub config_path: String,
+    pub http: Vec<(&'static str, String)>,
+    /// name, API url, and the login the token authenticates as or the error
+    pub profiles: Vec<(String, String, Result<String, String>)>,
+}
+
+impl DoctorReport {
+    pub fn is_ok(&self) -> bool {
+        !self.profiles.is_empty() && self.profiles.iter().all(|(_, _, res)| res.is_ok())
+    }
+}
+
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "config: {}", self.config_path)?;
+        writeln!(f, "http:")?;
+        for (setting, value) in &self.http {
+            writeln!(f, "    {setting}: {value}")?;
+        }
+        writeln!(f, "profiles:")?;
+        for (name, api_url, res) in &self.profiles {
+            match res {
+                Ok(login) => writeln!(f, "    {name} {api_url}: ok, authenticated as {login}")?,
+                Err(err) => writeln!(f, "    {name} {api_url}: error: {err}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Build the HTTP client from the config and authenticate every profile with it
+pub async fn doctor() -> DoctorReport {
+    let config = Config::get();
+    let mut report = DoctorReport {
+        config_path: config::config_path().display().to_string(),
+        http: gh::describe_http(&config.http),
+        profiles: vec![],
+    };
+    let http = match gh::http_client(&config.http) {
+        Ok(http) => http,
+        Err(err) => {
+            report.http.push(("client", format!("error: {err:#}")));
+            return report;
+        }
+    };
+    let clients = Clients::new(http);
+    for profile in &config.profiles {
+        let res = match ProfileForge::new(&clients, profile) {
+            Ok(profile_forge) => profile_forge
+                .login()
+                .await
+                .map_err(|err| format!("{err:#}")),
+            Err(err) => Err(err.to_string()),
+        };
+        report
+            .profiles
+            .push((profile.name.clone(), profile.api_url.clone(), res));
+    }
+    report
+}
+
+/// What a resync changed, notifications are described as `account repo: title`
+#[derive(Debug, Default)]
+pub struct ResyncReport {
+    pub fetched: usize,
+    pub added: Vec<String>,
+    pub updated: Vec<(String, Vec<String>)>,
+    pub unchanged: usize,
+}
+
+impl fmt::Display for ResyncReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} fetched, {} added, {} updated, {} unchanged",
+            self.fetched,
+            self.added.len(),
+            self.updated.len(),
+            self.unchanged
+        )?;
+        for notification in &self.added {
+            writeln!(f, "+ {notification}")?;
+        }
+        for (notification, changes) in &self.updated {
+            writeln!(f, "~ {notification}")?;
+            for change in changes {
+                writeln!(f, "    {change}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Refetch the notifications updated since `since`, read or not, rebuild their details and
+/// rescore every local notification with the current rules. Boosts, done and muted flags are
+/// kept. A failing profile doesn't stop the others, its error is returned once all were tried.
+pub async fn resync(
+    connection: &mut DbConnection,
+    clients: &Clients,
+    since: NaiveDateTime,
+) -> Result<ResyncReport> {
+    let config = Config::get();
+    let mut report = ResyncReport::default();
+    let mut res = Ok(());
+    for profile in &config.profiles {
+        let start = Instant::now();
+        let profile_res = resync_profile(connection, clients, profile, since, &mut report).await;
+        if let Err(err) = record_sync(connection, &profile.name, start.elapsed(), &profile_res) {
+            error!("cannot record sync of {}: {:?}", profile.name, err);
+        }
+        if let Err(err) = profile_res {
+            error!("cannot resync profile {}: {:?}", profile.name, err);
+            if res.is_ok() {
+                res = Err(err);
+            }
+        }
+    }
+    res.map(|()| report)
+}
+
+async fn resync_profile(
+    connection: &mut DbConnection,
+    clients: &Clients,
+    profile: &Profile,
+    since: NaiveDateTime,
+    report: &mut ResyncReport,
+) -> Result<(usize, ListingInfo)> {
+    let profile_forge = ProfileForge::new(clients, profile)?;
+    let (fetched, info) =
+        fetch_profile_notifications(connection, &profile_forge, profile, Some(since)).await?;
+    let fetched_count = fetched.len();
+    report.fetched += fetched_count;
+
+    let mut local: HashMap<String, DBNotification> = notifications
+        .select(DBNotification::as_select())
+        .filter(account.eq(&profile.name))
+        .load(connection)?
+        .into_iter()
+        .map(|notification| (notification.id.clone(), notification))
+        .collect();
+    let mut local_details = load_account_details(connection, &profile.name)?;
+
+    for (mut db_notification, details) in fetched {
+        let Some(existing) = local.remove(&db_notification.id) else {
+            insert_into(notifications)
+                .values(&db_notification)
+                .execute(connection)?;
+            save_details(connection, &db_notification, &details)?;
+            report.added.push(describe(&db_notification));
+            continue;
+        };
+        if !profile.forge.has_read_state() {
+            db_notification.unread = existing.unread;
+        }
+        let existing_details = local_details
+            .remove(&db_notification.id)
+            .unwrap_or_default();
+        let mut notification_changes = changes(&existing, &db_notification);
+        notification_changes.extend(details_changes(&existing_details, &details));
+        if notification_changes.is_empty() {
+            report.unchanged += 1;
+            continue;
+        }
+        update(&existing)
+            .set((
+                reason.eq(&db_notification.reason),
+                title.eq(&db_notification.title),
+                unread.eq(db_notification.unread),
+                repo.eq(&db_notification.repo),
+                updated_at.eq(db_notification.updated_at),
+                score.eq(db_notification.score),
+                url.eq(&db_notification.url),
+                type_.eq(&db_notification.type_),
+                author.eq(&db_notification.author),
+                state.eq(&db_notification.state),
+                review_requested.eq(db_notification.review_requested),
+                review_decision.eq(db_notification.review_decision),
+                ci_state.eq(db_notification.ci_state),
+                milestone.eq(&db_notification.milestone),
+                comment_author.eq(&db_notification.comment_author),
+                comment_at.eq(db_notification.comment_at),
+                comment_excerpt.eq(&db_notification.comment_excerpt),
+                comment_url.eq(&db_notification.comment_url),
+                state_reason.eq(db_notification.state_reason),
+                duplicate_of.eq(&db_notification.duplicate_of),
+                release_tag.eq(&db_notification.release_tag),
+                prerelease.eq(db_notification.prerelease),
+                published_at.eq(db_notification.published_at),
+                forge.eq(db_notification.forge),
+            ))
+            .execute(connection)?;
+        save_details(connection, &db_notification, &details)?;
+        report
+            .updated
+            .push((describe(&db_notification), notification_changes));
+    }
+
+    // out of the window: only the rules may have changed
+    let scorer = Scorer::new(profile.rules_path.clone())?;
+    for notification in local.into_values() {
+        let details = local_details.remove(&notification.id).unwrap_or_default();
+        let computed_score = scorer.score(&notification, &details);
+        if computed_score == notification.score {
+            report.unchanged += 1;
+            continue;
+        }
+        update(&notification)
+            .set(score.eq(computed_score))
+            .execute(connection)?;
+        report.updated.push((
+            describe(&notification),
+            vec![format!(
+                "score {} -> {}",
+                notification.score, computed_score
+            )],
+        ));
+    }
+    info!(
+        "resync {}: {} added, {} updated",
+        profile.name,
+        report.added.len(),
+        report.updated.len()
+    );
+    apply_pending_actions(connection, &profile.name)?;
+    Ok((fetched_count, info))
+}
+
+fn describe(notification: &DBNotification) -> String {
+    format!(
+        "{} {}: {}",
+        notification.account, notification.repo, notification.title
+    )
+}
+
+/// Fields fetched from GitHub or computed that differ, as `field old -> new`
+fn changes(old: &DBNotification, new: &DBNotification) -> Vec<String> {
+    let mut changes = vec![];
+    let mut compare = |field: &str, old: String, new: String| {
+        if old != new {
+            changes.push(format!("{field} {old} -> {new}"));
+        }
+    };
+    compare("title", old.title.clone(), new.title.clone());
+    compare("repo", old.repo.clone(), new.repo.clone());
+    compare("url", old.url.clone(), new.url.clone());
+    compare(
+        "type",
+        format!("{:?}", old.type_),
+        format!("{:?}", new.type_),
+    );
+    compare(
+        "state",
+        format!("{:?}", old.state),
+        format!("{:?}", new.state),
+    );
+    compare("author", old.author.clone(), new.author.clone());
+    compare("reason", old.reason.clone(), new.reason.clone());
+    compare("unread", old.unread.to_string(), new.unread.to_string());
+    compare(
+        "review_requested",
+        old.review_requested.to_string(),
+        new.review_requested.to_string(),
+    );
+    compare(
+        "review",
+        format!("{:?}", old.review_decision),
+        format!("{:?}", new.review_decision),
+    );
+    compare(
+        "ci",
+        format!("{:?}", old.ci_state),
+        format!("{:?}", new.ci_state),
+    );
+    compare(
+        "state_reason",
+        format!("{:?}", old.state_reason),
+        format!("{:?}", new.state_reason),
+    );
+    compare(
+        "duplicate_of",
+        format!("{:?}", old.duplicate_of),
+        format!("{:?}", new.duplicate_of),
+    );
+    compare(
+        "release_tag",
+        format!("{:?}", old.release_tag),
+        format!("{:?}", new.release_tag),
+    );
+    compare(
+        "prerelease",
+        old.prerelease.to_string(),
+        new.prerelease.to_string(),
+    );
+    compare(
+        "milestone",
+        format!("{:?}", old.milestone),
+        format!("{:?}", new.milestone),
+    );
+    compare(
+        "comment",
+        format!("{:?}", old.comment_url),
+        format!("{:?}", new.comment_url),
+    );
+    compare(
+        "updated_at",
+        old.updated_at.to_string(),
+        new.updated_at.to_string(),
+    );
+    compare("score", old.score.to_string(), new.score.to_string());
+    changes
+}
+
+fn details_changes(old: &Details, new: &Details) -> Vec<String> {
+    let mut changes = vec![];
+    if old.labels != new.labels {
+        changes.push(format!(
+            "labels [{}] -> [{}]",
+            old.labels.join(", "),
+            new.labels.join(", ")
+        ));
+    }
+    if old.assignees != new.assignees {
+        changes.push(format!(
+            "assignees [{}] -> [{}]",
+            old.assignees.join(", "),
+            new.assignees.join(", ")
+        ));
+    }
+    changes
 }
 
 pub async fn get_notifications(
@@ -157,8 +829,14 @@ pub async fn get_notifications(
     debug!("filters: {:?}", filters);
     let mut query = notifications
         .into_boxed()
-        .select(DBNotification::as_select())
-        .filter(done.eq(false));
+        .select(DBNotification::as_select());
+
+    // muted threads are done, they are only shown in their own view
+    query = if filters.muted {
+        query.filter(muted.eq(true))
+    } else {
+        query.filter(done.eq(false))
+    };
 
     if !filters.title.is_empty() {
         query = filters.title.split(" ").fold(query, |query, title_term| {
@@ -178,43 +856,308 @@ pub async fn get_notifications(
         query = query.filter(state.like(format!("%{}%", filters.state)));
     }
 
+    if !filters.account.is_empty() {
+        query = query.filter(account.eq(filters.account));
+    }
+
+    match filters.review.as_str() {
+        "" => {}
+        "requested" => query = query.filter(review_requested.eq(true)),
+        decision => query = query.filter(review_decision.eq(decision.to_string())),
+    }
+
+    if !filters.ci.is_empty() {
+        query = query.filter(ci_state.eq(filters.ci));
+    }
+
+    // feed entries come from no forge
+    if !filters.forge.is_empty() {
+        query = query
+            .filter(forge.eq(filters.forge))
+            .filter(type_.ne(NotificationType::FeedEntry));
+    }
+
+    // like with the wildcards escaped: exact match, ignoring the case
+    if !filters.label.is_empty() {
+        query = query.filter(exists(
+            schema::notification_labels::table
+                .filter(schema::notification_labels::notification_id.eq(id))
+                .filter(schema::notification_labels::account.eq(account))
+                .filter(
+                    schema::notification_labels::name
+                        .like(escape_like(&filters.label))
+                        .escape('\\'),
+                ),
+        ));
+    }
+
+    if !filters.assignee.is_empty() {
+        query = query.filter(exists(
+            schema::notification_assignees::table
+                .filter(schema::notification_assignees::notification_id.eq(id))
+                .filter(schema::notification_assignees::account.eq(account))
+                .filter(
+                    schema::notification_assignees::login
+                        .like(escape_like(&filters.assignee))
+                        .escape('\\'),
+                ),
+        ));
+    }
+
     Ok(query
         .order_by(((score + score_boost).desc(), updated_at.desc()))
         .load(connection)?)
 }
 
+/// `%` and `_` match themselves, escaped with a backslash
+fn escape_like(term: &str) -> String {
+    term.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Applied locally, GitHub is updated by `flush_pending_actions`
 pub async fn mark_notification_as_done(
     connection: &mut DbConnection,
     notification: &DBNotification,
 ) -> Result<()> {
-    gh::mark_as_done(&notification.id).await?;
-    update(notification)
-        .set(done.eq(true))
-        .execute(connection)?;
-    Ok(())
+    connection.transaction(|connection| {
+        update(notification)
+            .set(done.eq(true))
+            .execute(connection)?;
+        enqueue_action(connection, notification, Action::Done)
+    })
 }
 
+/// Applied locally, GitHub is updated by `flush_pending_actions`
 pub async fn mark_notifications_as_done(
     connection: &mut DbConnection,
-    notifs: &Vec<&DBNotification>,
+    notifs: &[DBNotification],
 ) -> Result<()> {
-    let ids = notifs.iter().map(|n| n.id.clone()).collect();
-    gh::mark_as_done_multiple(&ids).await?;
-    update(notifications)
-        .filter(id.eq_any(ids))
-        .set(done.eq(true))
-        .execute(connection)?;
-    Ok(())
+    connection.transaction(|connection| {
+        for notification in notifs {
+            update(notification)
+                .set(done.eq(true))
+                .execute(connection)?;
+            enqueue_action(connection, notification, Action::Done)?;
+        }
+        Ok(())
+    })
 }
 
+/// Applied locally, GitHub is updated by `flush_pending_actions`
 pub async fn mark_notification_as_read(
     connection: &mut DbConnection,
     notification: &DBNotification,
 ) -> Result<()> {
-    gh::mark_as_read(&notification.id).await?;
-    update(notification)
-        .set(unread.eq(false))
+    connection.transaction(|connection| {
+        update(notification)
+            .set(unread.eq(false))
+            .execute(connection)?;
+        enqueue_action(connection, notification, Action::Read)
+    })
+}
+
+/// Stop receiving notifications for the thread, and mark it as done. Applied locally, GitHub is
+/// updated by `flush_pending_actions`
+pub async fn mute_notification(
+    connection: &mut DbConnection,
+    notification: &DBNotification,
+) -> Result<()> {
+    connection.transaction(|connection| {
+        update(notification)
+            .set((muted.eq(true), done.eq(true)))
+            .execute(connection)?;
+        enqueue_action(connection, notification, Action::Mute)?;
+        enqueue_action(connection, notification, Action::Done)
+    })
+}
+
+/// Applied locally, GitHub is updated by `flush_pending_actions`
+pub async fn unmute_notification(
+    connection: &mut DbConnection,
+    notification: &DBNotification,
+) -> Result<()> {
+    connection.transaction(|connection| {
+        update(notification)
+            .set(muted.eq(false))
+            .execute(connection)?;
+        enqueue_action(connection, notification, Action::Unmute)
+    })
+}
+
+fn enqueue_action(
+    connection: &mut DbConnection,
+    notification: &DBNotification,
+    pending_action: Action,
+) -> Result<()> {
+    use schema::pending_actions::dsl as pending;
+
+    // feed entries only live here
+    if matches!(notification.type_, NotificationType::FeedEntry) {
+        return Ok(());
+    }
+
+    // the last of mute and unmute wins
+    if matches!(pending_action, Action::Mute | Action::Unmute) {
+        diesel::delete(
+            pending::pending_actions
+                .filter(pending::notification_id.eq(&notification.id))
+                .filter(pending::account.eq(&notification.account))
+                .filter(pending::action.eq_any([Action::Mute, Action::Unmute])),
+        )
         .execute(connection)?;
+    }
+
+    // redoing a queued action retries it from scratch, parked or not
+    let already_queued = update(
+        pending::pending_actions
+            .filter(pending::notification_id.eq(&notification.id))
+            .filter(pending::account.eq(&notification.account))
+            .filter(pending::action.eq(pending_action)),
+    )
+    .set((
+        pending::attempts.eq(0),
+        pending::created_at.eq(Utc::now().naive_utc()),
+        pending::last_attempt_at.eq(None::<NaiveDateTime>),
+    ))
+    .execute(connection)?;
+    if already_queued == 0 {
+        insert_into(pending::pending_actions)
+            .values(NewPendingAction {
+                notification_id: notification.id.clone(),
+                account: notification.account.clone(),
+                action: pending_action,
+                created_at: Utc::now().naive_utc(),
+            })
+            .execute(connection)?;
+    }
+    Ok(())
+}
+
+pub fn count_pending_actions(connection: &mut DbConnection) -> Result<i64> {
+    Ok(schema::pending_actions::table
+        .count()
+        .get_result(connection)?)
+}
+
+/// Last error of the actions given up on, the most recent first
+pub fn parked_action_error(connection: &mut DbConnection) -> Result<Option<String>> {
+    Ok(schema::pending_actions::table
+        .filter(schema::pending_actions::attempts.ge(MAX_PENDING_ATTEMPTS))
+        .order_by(schema::pending_actions::id.desc())
+        .select(schema::pending_actions::last_error)
+        .first(connection)
+        .optional()?
+        .flatten())
+}
+
+/// An action failing again waits twice as long, from its last attempt, before the next replay.
+/// Offline, it only waits the base delay.
+fn pending_action_due(pending_action: &PendingAction, now: NaiveDateTime) -> bool {
+    let Some(last_attempt_at) = pending_action.last_attempt_at else {
+        return true;
+    };
+    let delay = PENDING_BASE_DELAY_SEC
+        .saturating_mul(1 << (pending_action.attempts.max(1) - 1).min(20))
+        .min(PENDING_MAX_DELAY_SEC);
+    last_attempt_at + chrono::Duration::seconds(delay) <= now
+}
+
+/// Replay the queued actions on GitHub, return how many are still pending. Failed actions are
+/// kept for a later run with a backoff, unless GitHub refused them for good, and parked after
+/// `MAX_PENDING_ATTEMPTS`. Failing to reach GitHub doesn't count as an attempt.
+pub async fn flush_pending_actions(
+    connection: &mut DbConnection,
+    clients: &Clients,
+) -> Result<i64> {
+    let now = Utc::now().naive_utc();
+    let actions: Vec<PendingAction> = schema::pending_actions::table
+        .filter(schema::pending_actions::attempts.lt(MAX_PENDING_ATTEMPTS))
+        .select(PendingAction::as_select())
+        .order_by(schema::pending_actions::id)
+        .load(connection)?
+        .into_iter()
+        .filter(|pending_action| pending_action_due(pending_action, now))
+        .collect();
+    if actions.is_empty() {
+        return count_pending_actions(connection);
+    }
+    info!("replaying {} pending actions", actions.len());
+
+    // futures are built upfront, a closure capturing the clients would make the stream !Send
+    let replays: Vec<_> = actions
+        .iter()
+        .map(|pending_action| replay_action(clients, pending_action))
+        .collect();
+    let results: Vec<Result<()>> = iter(replays).buffered(gh::NB_TASK).collect().await;
+
+    for (pending_action, result) in actions.iter().zip(results) {
+        match result {
+            Ok(()) => {
+                diesel::delete(pending_action).execute(connection)?;
+            }
+            Err(err) if gh::is_permanent_error(&err) => {
+                error!("dropping {:?}: {}", pending_action, err);
+                diesel::delete(pending_action).execute(connection)?;
+            }
+            Err(err) if gh::is_transient_error(&err) => {
+                debug!("cannot reach the forge for {:?}: {}", pending_action, err);
+                update(pending_action)
+                    .set((
+                        schema::pending_actions::last_error.eq(err.to_string()),
+                        schema::pending_actions::last_attempt_at.eq(now),
+                    ))
+                    .execute(connection)?;
+            }
+            Err(err) => {
+                if pending_action.attempts + 1 >= MAX_PENDING_ATTEMPTS {
+                    error!("parking {:?}: {}", pending_action, err);
+                } else {
+                    debug!("cannot replay {:?}: {}", pending_action, err);
+                }
+                update(pending_action)
+                    .set((
+                        schema::pending_actions::attempts.eq(pending_action.attempts + 1),
+                        schema::pending_actions::last_error.eq(err.to_string()),
+                        schema::pending_actions::last_attempt_at.eq(now),
+                    ))
+                    .execute(connection)?;
+            }
+        }
+    }
+    count_pending_actions(connection)
+}
+
+async fn replay_action(clients: &Clients, pending_action: &PendingAction) -> Result<()> {
+    let profile = Config::get()
+        .profile(&pending_action.account)
+        .cloned()
+        .ok_or(anyhow!("no profile for account {}", pending_action.account))?;
+    ProfileForge::new(clients, &profile)?
+        .apply(pending_action.action, &pending_action.notification_id)
+        .await
+}
+
+/// Queued actions win over the state fetched from GitHub until they are replayed
+fn apply_pending_actions(connection: &mut DbConnection, profile_name: &str) -> Result<()> {
+    let actions = schema::pending_actions::table
+        .select(PendingAction::as_select())
+        .filter(schema::pending_actions::account.eq(profile_name))
+        .load(connection)?;
+    for pending_action in actions {
+        let target = notifications
+            .filter(id.eq(&pending_action.notification_id))
+            .filter(account.eq(profile_name));
+        match pending_action.action {
+            Action::Done => update(target).set(done.eq(true)).execute(connection)?,
+            Action::Read => update(target).set(unread.eq(false)).execute(connection)?,
+            Action::Mute => update(target)
+                .set((muted.eq(true), done.eq(true)))
+                .execute(connection)?,
+            Action::Unmute => update(target).set(muted.eq(false)).execute(connection)?,
+        };
+    }
     Ok(())
 }
 
@@ -229,16 +1172,205 @@ pub async fn update_score(
     Ok(())
 }
 
-pub async fn explain(notification: &DBNotification) -> Result<Vec<Rule>> {
-    let directories = dirs::Directories::new();
-    let scorer = Scorer::new(directories.config.join("rules.toml"))?;
-    let rules = scorer.explain(notification);
+pub async fn explain(
+    connection: &mut DbConnection,
+    notification: &DBNotification,
+) -> Result<Vec<Rule>> {
+    let rules_path = match Config::get().feed(&notification.account) {
+        Some(subscription) => subscription.rules_path.clone(),
+        None => profile_of(notification)?.rules_path,
+    };
+    let scorer = Scorer::new(rules_path)?;
+    let details = get_details(connection, notification)?;
+    let rules = scorer.explain(notification, &details);
     Ok(rules)
 }
 
-fn get_recent_update(connection: &mut DbConnection) -> Option<NaiveDateTime> {
+/// Labels and assignees of a notification, sorted
+pub fn get_details(
+    connection: &mut DbConnection,
+    notification: &DBNotification,
+) -> Result<Details> {
+    let labels = schema::notification_labels::table
+        .select(schema::notification_labels::name)
+        .filter(schema::notification_labels::notification_id.eq(&notification.id))
+        .filter(schema::notification_labels::account.eq(&notification.account))
+        .order_by(schema::notification_labels::name)
+        .load(connection)?;
+    let assignees = schema::notification_assignees::table
+        .select(schema::notification_assignees::login)
+        .filter(schema::notification_assignees::notification_id.eq(&notification.id))
+        .filter(schema::notification_assignees::account.eq(&notification.account))
+        .order_by(schema::notification_assignees::login)
+        .load(connection)?;
+    Ok(Details { labels, assignees })
+}
+
+/// Details of every notification of an account, by notification id
+fn load_account_details(
+    connection: &mut DbConnection,
+    profile_name: &str,
+) -> Result<HashMap<String, Details>> {
+    let mut details: HashMap<String, Details> = HashMap::new();
+    for label in schema::notification_labels::table
+        .select(NotificationLabel::as_select())
+        .filter(schema::notification_labels::account.eq(profile_name))
+        .order_by(schema::notification_labels::name)
+        .load(connection)?
+    {
+        details
+            .entry(label.notification_id)
+            .or_default()
+            .labels
+            .push(label.name);
+    }
+    for assignee in schema::notification_assignees::table
+        .select(NotificationAssignee::as_select())
+        .filter(schema::notification_assignees::account.eq(profile_name))
+        .order_by(schema::notification_assignees::login)
+        .load(connection)?
+    {
+        details
+            .entry(assignee.notification_id)
+            .or_default()
+            .assignees
+            .push(assignee.login);
+    }
+    Ok(details)
+}
+
+/// Replace the labels and assignees of a notification
+fn save_details(
+    connection: &mut DbConnection,
+    notification: &DBNotification,
+    details: &Details,
+) -> QueryResult<()> {
+    connection.transaction(|connection| {
+        diesel::delete(
+            schema::notification_labels::table
+                .filter(schema::notification_labels::notification_id.eq(&notification.id))
+                .filter(schema::notification_labels::account.eq(&notification.account)),
+        )
+        .execute(connection)?;
+        diesel::delete(
+            schema::notification_assignees::table
+                .filter(schema::notification_assignees::notification_id.eq(&notification.id))
+                .filter(schema::notification_assignees::account.eq(&notification.account)),
+        )
+        .execute(connection)?;
+        let labels: Vec<NotificationLabel> = details
+            .labels
+            .iter()
+            .map(|label| NotificationLabel {
+                notification_id: notification.id.clone(),
+                account: notification.account.clone(),
+                name: label.clone(),
+            })
+            .collect();
+        insert_into(schema::notification_labels::table)
+            .values(&labels)
+            .execute(connection)?;
+        let assignees: Vec<NotificationAssignee> = details
+            .assignees
+            .iter()
+            .map(|assignee| NotificationAssignee {
+                notification_id: notification.id.clone(),
+                account: notification.account.clone(),
+                login: assignee.clone(),
+            })
+            .collect();
+        insert_into(schema::notification_assignees::table)
+            .values(&assignees)
+            .execute(connection)?;
+        Ok(())
+    })
+}
+
+/// Notifications synced before profiles were configured belong to the `default` account. Without
+/// a profile of that name, they are moved to the first GitHub profile, where the threads it has
+/// synced since are kept. Return the number of notifications moved.
+pub fn adopt_default_account(connection: &mut DbConnection) -> Result<usize> {
+    let config = Config::get();
+    if config.profile(DEFAULT_PROFILE).is_some() {
+        return Ok(0);
+    }
+    let Some(owner) = config
+        .profiles
+        .iter()
+        .find(|profile| profile.forge == ForgeKind::GitHub)
+    else {
+        return Ok(0);
+    };
+    let moved = connection.transaction(|connection| {
+        let owned: Vec<String> = notifications
+            .select(id)
+            .filter(account.eq(&owner.name))
+            .load(connection)?;
+        diesel::delete(notifications)
+            .filter(account.eq(DEFAULT_PROFILE))
+            .filter(id.eq_any(&owned))
+            .execute(connection)?;
+        diesel::delete(
+            schema::notification_labels::table
+                .filter(schema::notification_labels::account.eq(DEFAULT_PROFILE))
+                .filter(schema::notification_labels::notification_id.eq_any(&owned)),
+        )
+        .execute(connection)?;
+        diesel::delete(
+            schema::notification_assignees::table
+                .filter(schema::notification_assignees::account.eq(DEFAULT_PROFILE))
+                .filter(schema::notification_assignees::notification_id.eq_any(&owned)),
+        )
+        .execute(connection)?;
+
+        let moved = update(notifications)
+            .filter(account.eq(DEFAULT_PROFILE))
+            .filter(type_.ne(NotificationType::FeedEntry))
+            .set(account.eq(&owner.name))
+            .execute(connection)?;
+        update(schema::notification_labels::table)
+            .filter(schema::notification_labels::account.eq(DEFAULT_PROFILE))
+            .set(schema::notification_labels::account.eq(&owner.name))
+            .execute(connection)?;
+        update(schema::notification_assignees::table)
+            .filter(schema::notification_assignees::account.eq(DEFAULT_PROFILE))
+            .set(schema::notification_assignees::account.eq(&owner.name))
+            .execute(connection)?;
+        update(schema::pending_actions::table)
+            .filter(schema::pending_actions::account.eq(DEFAULT_PROFILE))
+            .set(schema::pending_actions::account.eq(&owner.name))
+            .execute(connection)?;
+        // the profile syncs from its own state if it has one
+        if get_sync_state(connection, &owner.name).is_some() {
+            diesel::delete(schema::sync_state::table.find(DEFAULT_PROFILE)).execute(connection)?;
+        } else {
+            update(schema::sync_state::table.find(DEFAULT_PROFILE))
+                .set(schema::sync_state::account.eq(&owner.name))
+                .execute(connection)?;
+        }
+        QueryResult::Ok(moved)
+    })?;
+    if moved > 0 {
+        info!(
+            "{} notifications of {} moved to {}",
+            moved, DEFAULT_PROFILE, owner.name
+        );
+    }
+    Ok(moved)
+}
+
+/// Profile the notification was fetched with, its actions must go through the same account
+fn profile_of(notification: &DBNotification) -> Result<Profile> {
+    Config::get()
+        .profile(&notification.account)
+        .cloned()
+        .ok_or(anyhow!("no profile for account {}", notification.account))
+}
+
+fn get_recent_update(connection: &mut DbConnection, profile_name: &str) -> Option<NaiveDateTime> {
     let recent_pr = notifications
         .select(DBNotification::as_select())
+        .filter(account.eq(profile_name))
         .order_by(updated_at.desc())
         .first(connection);
     let last_update = recent_pr.map(|notif| notif.updated_at).ok();
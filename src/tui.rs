@@ -1,8 +1,12 @@
+use crate::gh::Clients;
 use crate::gh::Error as GhError;
-use crate::models::{Notification, NotificationState, NotificationType};
+use crate::models::{
+    CiState, Notification, NotificationState, NotificationType, ReviewDecision, StateReason,
+};
 use crate::score::Error as ScoreError;
 use crate::{DbConnection, DbConnectionManager, Pool, get_connection_pool, service};
 use anyhow::Result;
+use chrono::NaiveDateTime;
 use chrono_humanize::{Accuracy, HumanTime, Tense};
 use log::{debug, error, info};
 use ratatui::crossterm::event::{self, Event, KeyCode};
@@ -35,6 +39,8 @@ enum MessageAction {
     Open,
     MarkAsDone,
     MarkBelowAsDone,
+    Mute,
+    Unmute,
     Sync,
     SyncBackground,
     Explain,
@@ -52,6 +58,9 @@ enum MessageUi {
     SearchInput(Event),
     SearchQuit,
     Redraw,
+    /// number of actions not yet sent to GitHub, and the last error of those given up on
+    Pending(i64, Option<String>),
+    LastSynced(Option<NaiveDateTime>),
 }
 
 #[derive(Clone, PartialEq, Debug, Default)]
@@ -102,12 +111,16 @@ struct Popup {
 
 const REFRESH_DELAY_SEC: u64 = 300;
 const REDRAW_DELAY_SEC: u64 = 60;
+const PENDING_DELAY_SEC: u64 = 5;
+const RECONCILE_DELAY_SEC: u64 = 900;
 
 // define KEYMAP str constant with key binding info
 include!(concat!(env!("OUT_DIR"), "/keymap.rs"));
 
 pub async fn run() -> Result<()> {
-    let res = App::default().run().await;
+    // before the terminal is taken over, so that the error is readable
+    let clients = Clients::from_config()?;
+    let res = App::default().run(clients).await;
     ratatui::restore();
     res
 }
@@ -125,10 +138,13 @@ struct App<'a> {
     popup: Option<Popup>,
     search_text: TextArea<'a>,
     input_mode: InputMode,
+    pending: i64,
+    pending_error: Option<String>,
+    last_synced: Option<NaiveDateTime>,
 }
 
 impl App<'_> {
-    async fn run(&mut self) -> Result<()> {
+    async fn run(&mut self, clients: Clients) -> Result<()> {
         let mut terminal = ratatui::init();
         let mut list_state = ListState::default();
         list_state.select_first();
@@ -137,6 +153,7 @@ impl App<'_> {
         let pool = get_connection_pool();
 
         let notifications = refresh(&mut pool.clone().get()?, "").await?;
+        self.last_synced = service::last_synced(&mut pool.get()?);
         self.reset_search();
         self.update_ui(
             MessageUi::UiUpdate(UiState::default()),
@@ -148,8 +165,22 @@ impl App<'_> {
         .await?;
 
         let tx_cloned = tx.clone();
-        let notif_handle = tokio::spawn(auto_sync_notifs_loop(tx.clone(), pool.clone()));
+        let notif_handle = tokio::spawn(auto_sync_notifs_loop(
+            tx.clone(),
+            pool.clone(),
+            clients.clone(),
+        ));
         let refresh_handle = tokio::spawn(auto_refresh_ui_loop(tx.clone()));
+        let pending_handle = tokio::spawn(pending_actions_loop(
+            tx.clone(),
+            pool.clone(),
+            clients.clone(),
+        ));
+        let reconcile_handle = tokio::spawn(auto_reconcile_loop(
+            tx.clone(),
+            pool.clone(),
+            clients.clone(),
+        ));
         std::thread::spawn(|| handle_input_loop(tx_cloned));
 
         loop {
@@ -183,6 +214,7 @@ impl App<'_> {
                         tokio::spawn(handle_action(
                             tx.clone(),
                             pool.clone().get()?,
+                            clients.clone(),
                             message_action,
                             list_state.selected(),
                             notifications,
@@ -206,6 +238,8 @@ impl App<'_> {
 
         notif_handle.abort();
         refresh_handle.abort();
+        pending_handle.abort();
+        reconcile_handle.abort();
 
         Ok(())
     }
@@ -299,6 +333,13 @@ impl App<'_> {
             MessageUi::UiUpdate(update) => {
                 self.state = update;
             }
+            MessageUi::LastSynced(last_synced) => {
+                self.last_synced = last_synced;
+            }
+            MessageUi::Pending(pending, pending_error) => {
+                self.pending = pending;
+                self.pending_error = pending_error;
+            }
             MessageUi::SearchActivate => {
                 self.state.reset();
                 self.input_mode = InputMode::Search;
@@ -321,7 +362,19 @@ impl App<'_> {
             Err(self.state.error.clone())
         } else {
             let info = if self.state.info.is_empty() {
-                format!("Riirview, {} notifs", notifications.len())
+                let mut info = format!("Riirview, {} notifs", notifications.len());
+                if self.pending > 0 {
+                    info.push_str(&format!(", {} unsynced", self.pending));
+                }
+                if let Some(err) = &self.pending_error {
+                    info.push_str(&format!(" (gave up: {err})"));
+                }
+                if let Some(last_synced) = self.last_synced {
+                    let time = HumanTime::from(last_synced.and_utc())
+                        .to_text_en(Accuracy::Rough, Tense::Past);
+                    info.push_str(&format!(", last synced {time}"));
+                }
+                info
             } else {
                 self.state.info.clone()
             };
@@ -358,6 +411,7 @@ impl App<'_> {
 async fn handle_action(
     tx: mpsc::Sender<Message>,
     mut connection: DbConnection,
+    clients: Clients,
     message: MessageAction,
     idx: Option<usize>,
     notifications: Vec<Notification>,
@@ -412,6 +466,20 @@ async fn handle_action(
 
             res
         }
+        MessageAction::Mute => {
+            let res = mute(&mut connection, idx, &notifications).await;
+            tx.send(Message::Ui(MessageUi::Redraw))
+                .await
+                .expect("cannot send");
+            res
+        }
+        MessageAction::Unmute => {
+            let res = unmute(&mut connection, idx, &notifications).await;
+            tx.send(Message::Ui(MessageUi::Redraw))
+                .await
+                .expect("cannot send");
+            res
+        }
         MessageAction::Open => {
             let res = open_gh(&mut connection, idx, &notifications).await;
             tx.send(Message::Ui(MessageUi::Redraw))
@@ -426,7 +494,12 @@ async fn handle_action(
             .await
             .expect("cannot send");
 
-            let res = sync(&mut connection).await;
+            let res = sync(&mut connection, &clients).await;
+            tx.send(Message::Ui(MessageUi::LastSynced(service::last_synced(
+                &mut connection,
+            ))))
+            .await
+            .expect("cannot send");
 
             tx.send(Message::Ui(MessageUi::UiUpdate(UiState::info_msg(
                 String::new(),
@@ -443,7 +516,12 @@ async fn handle_action(
             .await
             .expect("cannot send");
 
-            let res = sync(&mut connection).await;
+            let res = sync(&mut connection, &clients).await;
+            tx.send(Message::Ui(MessageUi::LastSynced(service::last_synced(
+                &mut connection,
+            ))))
+            .await
+            .expect("cannot send");
 
             tx.send(Message::Ui(MessageUi::UiUpdate(UiState::default())))
                 .await
@@ -454,7 +532,7 @@ async fn handle_action(
                 .expect("cannot send");
             res
         }
-        MessageAction::Explain => match explain(idx, &notifications).await {
+        MessageAction::Explain => match explain(&mut connection, idx, &notifications).await {
             Ok(explanation) => {
                 tx.send(Message::Ui(MessageUi::Popup(Popup {
                     title: "Explain".into(),
@@ -521,6 +599,8 @@ fn handle_input_loop(tx: mpsc::Sender<Message>) {
                         KeyCode::Enter => Message::Action(MessageAction::Open),
                         KeyCode::Char('r') => Message::Action(MessageAction::MarkAsDone),
                         KeyCode::Char('R') => Message::Action(MessageAction::MarkBelowAsDone),
+                        KeyCode::Char('m') => Message::Action(MessageAction::Mute),
+                        KeyCode::Char('u') => Message::Action(MessageAction::Unmute),
                         KeyCode::Char('g') => Message::Action(MessageAction::Sync),
                         KeyCode::Char('x') => Message::Action(MessageAction::Explain),
                         KeyCode::Char('?') => Message::Action(MessageAction::Help),
@@ -551,12 +631,27 @@ fn handle_input_loop(tx: mpsc::Sender<Message>) {
     }
 }
 
-async fn auto_sync_notifs_loop(tx: mpsc::Sender<Message>, pool: Pool<DbConnectionManager>) {
+async fn auto_sync_notifs_loop(
+    tx: mpsc::Sender<Message>,
+    pool: Pool<DbConnectionManager>,
+    clients: Clients,
+) {
     loop {
         debug!("refreshing notifications");
         let (refresh_delay, need_update) =
-            match service::check_update_and_limit(pool.get().unwrap()).await {
-                Err(_) => (REFRESH_DELAY_SEC, true),
+            match service::check_update_and_limit(pool.get().unwrap(), &clients).await {
+                Err(err) => match err.downcast_ref::<GhError>() {
+                    Some(GhError::RateLimited(until)) => {
+                        tx.send(Message::Ui(MessageUi::UiUpdate(UiState::error_msg(
+                            err.to_string(),
+                        ))))
+                        .await
+                        .expect("cannot send");
+                        let wait = (*until - chrono::Utc::now()).num_seconds().max(0);
+                        (std::cmp::max(REFRESH_DELAY_SEC, wait as u64), false)
+                    }
+                    _ => (REFRESH_DELAY_SEC, true),
+                },
                 Ok(update_status) => {
                     debug!("gh status {update_status:?}");
                     (
@@ -576,6 +671,58 @@ async fn auto_sync_notifs_loop(tx: mpsc::Sender<Message>, pool: Pool<DbConnectio
     }
 }
 
+/// Send the actions taken offline, and keep the unsynced count of the header up to date
+async fn pending_actions_loop(
+    tx: mpsc::Sender<Message>,
+    pool: Pool<DbConnectionManager>,
+    clients: Clients,
+) {
+    let mut last_pending = (0, None);
+    loop {
+        let pending = match service::flush_pending_actions(&mut pool.get().unwrap(), &clients).await
+        {
+            Ok(pending) => pending,
+            Err(err) => {
+                error!("cannot replay pending actions {:?}", err);
+                last_pending.0
+            }
+        };
+        let pending_error =
+            service::parked_action_error(&mut pool.get().unwrap()).unwrap_or_else(|err| {
+                error!("cannot read the parked actions {:?}", err);
+                last_pending.1.clone()
+            });
+        if (pending, &pending_error) != (last_pending.0, &last_pending.1) {
+            last_pending = (pending, pending_error.clone());
+            tx.send(Message::Ui(MessageUi::Pending(pending, pending_error)))
+                .await
+                .expect("cannot send");
+        }
+        tokio::time::sleep(tokio::time::Duration::from_secs(PENDING_DELAY_SEC)).await;
+    }
+}
+
+/// Catch up with what was done on github.com or another device
+async fn auto_reconcile_loop(
+    tx: mpsc::Sender<Message>,
+    pool: Pool<DbConnectionManager>,
+    clients: Clients,
+) {
+    loop {
+        tokio::time::sleep(tokio::time::Duration::from_secs(RECONCILE_DELAY_SEC)).await;
+        match service::reconcile(&mut pool.get().unwrap(), &clients).await {
+            Ok(0) => {}
+            Ok(updated) => {
+                info!("{updated} notifications reconciled");
+                tx.send(Message::Ui(MessageUi::Redraw))
+                    .await
+                    .expect("cannot send");
+            }
+            Err(err) => error!("cannot reconcile {:?}", err),
+        }
+    }
+}
+
 async fn auto_refresh_ui_loop(tx: mpsc::Sender<Message>) {
     loop {
         tokio::time::sleep(tokio::time::Duration::from_secs(REDRAW_DELAY_SEC)).await;
@@ -601,7 +748,12 @@ async fn open_gh(
     if let Some(idx) = idx
         && let Some(notification) = notifications.get(idx)
     {
-        return match open::that(notification.url.clone()) {
+        // straight to the latest comment when there is one
+        let url = notification
+            .comment_url
+            .clone()
+            .unwrap_or_else(|| notification.url.clone());
+        return match open::that(url) {
             Ok(_) => {
                 mark_as_read(connection, notification).await?;
                 Ok(())
@@ -634,15 +786,53 @@ async fn mark_as_done(
     Ok(())
 }
 
+async fn mute(
+    connection: &mut DbConnection,
+    idx: Option<usize>,
+    notifications: &[Notification],
+) -> Result<(), String> {
+    if let Some(idx) = idx
+        && let Some(notification) = notifications.get(idx)
+    {
+        return match service::mute_notification(connection, notification).await {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                error!("{e}");
+                Err(format!("Failed to mute {}", e))
+            }
+        };
+    }
+    Ok(())
+}
+
+async fn unmute(
+    connection: &mut DbConnection,
+    idx: Option<usize>,
+    notifications: &[Notification],
+) -> Result<(), String> {
+    if let Some(idx) = idx
+        && let Some(notification) = notifications.get(idx)
+        && notification.muted
+    {
+        return match service::unmute_notification(connection, notification).await {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                error!("{e}");
+                Err(format!("Failed to unmute {}", e))
+            }
+        };
+    }
+    Ok(())
+}
+
 async fn mark_all_below_as_done(
     connection: &mut DbConnection,
     idx: Option<usize>,
     notifications: &[Notification],
 ) -> Result<(), String> {
     if let Some(idx) = idx {
-        let selected_notifications = notifications.iter().skip(idx).collect::<Vec<_>>();
-        return match service::mark_notifications_as_done(connection, &selected_notifications).await
-        {
+        let selected_notifications = notifications.get(idx..).unwrap_or_default();
+        return match service::mark_notifications_as_done(connection, selected_notifications).await {
             Ok(_) => Ok(()),
             Err(e) => {
                 error!("{e}");
@@ -666,8 +856,8 @@ async fn mark_as_read(
     }
 }
 
-async fn sync(connection: &mut DbConnection) -> Result<(), String> {
-    service::sync(connection).await.map_err(|err| {
+async fn sync(connection: &mut DbConnection, clients: &Clients) -> Result<(), String> {
+    service::sync(connection, clients).await.map_err(|err| {
         let score_error_msg = match err.downcast_ref::<ScoreError>() {
             Some(ScoreError::RuleFileNotFound) => {
                 error!("rule file not found");
@@ -685,9 +875,9 @@ async fn sync(connection: &mut DbConnection) -> Result<(), String> {
         };
 
         let gh_error_msg = match err.downcast_ref::<GhError>() {
-            Some(GhError::MissingToken) => {
-                error!("env var GH_TOKEN is missing");
-                Some("env var GH_TOKEN is missing".to_string())
+            Some(err) => {
+                error!("{err}");
+                Some(err.to_string())
             }
             None => None,
         };
@@ -727,13 +917,19 @@ async fn update_score(
     Ok(None)
 }
 
-async fn explain(idx: Option<usize>, notifications: &[Notification]) -> Result<String, String> {
+async fn explain(
+    connection: &mut DbConnection,
+    idx: Option<usize>,
+    notifications: &[Notification],
+) -> Result<String, String> {
     if let Some(idx) = idx
         && let Some(notification) = notifications.get(idx)
     {
-        let res = service::explain(notification)
+        let res = service::explain(connection, notification)
             .await
             .or(Err(String::from("explain failed")))?;
+        let details = service::get_details(connection, notification)
+            .or(Err(String::from("explain failed")))?;
 
         let explanation = res.iter().fold(String::new(), |acc, rule| {
             let prefix = if acc.is_empty() {
@@ -754,6 +950,59 @@ async fn explain(idx: Option<usize>, notifications: &[Notification]) -> Result<S
             explanation
         };
 
+        let mut triage = String::new();
+        if !details.labels.is_empty() {
+            triage.push_str(&format!("\nlabels: {}", details.labels.join(", ")));
+        }
+        if !details.assignees.is_empty() {
+            triage.push_str(&format!("\nassignees: {}", details.assignees.join(", ")));
+        }
+        if let Some(milestone) = &notification.milestone {
+            triage.push_str(&format!("\nmilestone: {milestone}"));
+        }
+        if let Some(tag) = &notification.release_tag {
+            let published = notification
+                .published_at
+                .map(|date| {
+                    let time =
+                        HumanTime::from(date.and_utc()).to_text_en(Accuracy::Rough, Tense::Past);
+                    format!(", published {time}")
+                })
+                .unwrap_or_default();
+            let prerelease = if notification.prerelease {
+                " (prerelease)"
+            } else {
+                ""
+            };
+            triage.push_str(&format!("\ntag: {tag}{prerelease}{published}"));
+        }
+        match (notification.state_reason, &notification.duplicate_of) {
+            (Some(StateReason::Duplicate), Some(canonical)) => {
+                triage.push_str(&format!("\nclosed as duplicate of {canonical}"))
+            }
+            (Some(StateReason::Duplicate), None) => triage.push_str("\nclosed as duplicate"),
+            (Some(StateReason::NotPlanned), _) => triage.push_str("\nclosed as not planned"),
+            (Some(StateReason::Reopened), _) => triage.push_str("\nreopened"),
+            _ => {}
+        }
+        if let Some(comment_author) = &notification.comment_author {
+            let time = notification
+                .comment_at
+                .map(|date| {
+                    HumanTime::from(date.and_utc()).to_text_en(Accuracy::Rough, Tense::Past)
+                })
+                .unwrap_or_default();
+            triage.push_str(&format!(
+                "\n\nlast comment by {comment_author}, {time}:\n{}",
+                notification.comment_excerpt.as_deref().unwrap_or_default()
+            ));
+        }
+        let explanation = if triage.is_empty() {
+            explanation
+        } else {
+            format!("{triage}\n{explanation}")
+        };
+
         return Ok(explanation);
     };
     Ok(String::new())
@@ -775,17 +1024,61 @@ impl From<&Notification> for Text<'_> {
                 NotificationState::Canceled => "❌",
                 NotificationState::Draft => "📝",
             },
+            NotificationType::Discussion => "💬",
+            NotificationType::CheckSuite => match notification.state {
+                NotificationState::Resolved => "✅",
+                NotificationState::Canceled => "💥",
+                _ => "🚦",
+            },
+            NotificationType::Commit => "📌",
+            NotificationType::SecurityAlert => "🚨",
+            NotificationType::FeedEntry => "📰",
         };
+        let icon = if notification.muted { "🔇" } else { icon };
         let time = HumanTime::from(notification.updated_at.and_utc())
             .to_text_en(Accuracy::Rough, Tense::Past);
+        let badges: String = [
+            (notification.state_reason == Some(StateReason::Reopened)
+                && matches!(notification.state, NotificationState::Open))
+            .then_some("🔁"),
+            notification.prerelease.then_some("🧪"),
+            notification.review_requested.then_some("👀"),
+            match notification.review_decision {
+                Some(ReviewDecision::Approved) => Some("👍"),
+                Some(ReviewDecision::ChangesRequested) => Some("✋"),
+                _ => None,
+            },
+            match notification.ci_state {
+                Some(CiState::Success) => Some("🟢"),
+                Some(CiState::Failure) => Some("🔴"),
+                Some(CiState::Pending) => Some("🟡"),
+                None => None,
+            },
+        ]
+        .into_iter()
+        .flatten()
+        .map(|badge| format!("{badge} "))
+        .collect();
+        // release names often omit the version
+        let title = match &notification.release_tag {
+            Some(tag) if !notification.title.contains(tag.as_str()) => {
+                format!("{tag} {}", notification.title)
+            }
+            _ => notification.title.clone(),
+        };
+        let comment = notification
+            .comment_author
+            .as_ref()
+            .map(|comment_author| format!(" 💬 {}", ellipsis(comment_author, 15)))
+            .unwrap_or_default();
         let txt = format!(
-            "{score:>3} {icon} {time:<15} {author:15} {repo:<30} {title}",
+            "{score:>3} {icon} {time:<15} {author:15} {repo:<30} {badges}{title}{comment}",
             score = notification.score + notification.score_boost,
             icon = icon,
             time = ellipsis(&time, 15),
             author = ellipsis(&notification.author, 15),
             repo = ellipsis(&notification.repo, 30),
-            title = ellipsis(&notification.title, 80),
+            title = ellipsis(&title, 80),
         );
 
         let style = Style::default();
@@ -0,0 +1,69 @@
+mod common;
+
+use riirview::config::Profile;
+use riirview::service;
+
+#[tokio::test]
+async fn test_accounts() {
+    let mut personal_server = mockito::Server::new_async().await;
+    let mut work_server = mockito::Server::new_async().await;
+    // same fixtures on both servers, the notifications share their ids
+    common::mock_github(&mut personal_server);
+    common::mock_github(&mut work_server);
+
+    let env = common::setup_with(personal_server.url(), "", |config, rules_path| {
+        config.profiles.push(Profile::new(
+            "work".into(),
+            work_server.url(),
+            rules_path.to_path_buf(),
+        ));
+    });
+    let (pool, clients) = (&env.pool, &env.clients);
+
+    service::sync(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 100);
+
+    let work_notifications = service::get_notifications(&mut pool.get().unwrap(), "account:work")
+        .await
+        .unwrap();
+    assert_eq!(work_notifications.len(), 50);
+    for notification in &work_notifications {
+        assert_eq!(notification.account, "work");
+        assert!(notification.url.starts_with(&work_server.url()));
+    }
+
+    // actions go through the account of the notification
+    let notification = &work_notifications[0];
+    let path = format!("/notifications/threads/{}", notification.id);
+    let work_done = work_server
+        .mock("DELETE", path.as_str())
+        .with_status(205)
+        .expect(1)
+        .create();
+    let personal_done = personal_server
+        .mock("DELETE", path.as_str())
+        .with_status(205)
+        .expect(0)
+        .create();
+
+    service::mark_notification_as_done(&mut pool.get().unwrap(), notification)
+        .await
+        .unwrap();
+    let pending = service::flush_pending_actions(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    assert_eq!(pending, 0);
+    work_done.assert();
+    personal_done.assert();
+
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 99);
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use riirview::service;
+use std::fs;
+
+#[tokio::test]
+async fn test_latest_comment() {
+    let mut server = mockito::Server::new_async().await;
+    common::mock_github(&mut server);
+    let server_url = server.url();
+    let comments = server
+        .mock(
+            "GET",
+            mockito::Matcher::Regex(r"^/repos/[^/]+/[^/]+/issues/comments/\d+$".to_string()),
+        )
+        .with_header("content-type", "application/json")
+        .with_status(200)
+        .with_body_from_request(move |request| {
+            let id = request.path().rsplit('/').next().unwrap().to_string();
+            serde_json::json!({
+                "url": format!("{server_url}{}", request.path()),
+                "html_url": format!("https://github.com/cogip/project1/pull/1#issuecomment-{id}"),
+                "user": { "login": "renovate[bot]" },
+                "created_at": "2025-04-07T10:00:00Z",
+                "body": "<!-- renovate -->\n> quoted\n**Edited/Blocked Notification**\n\nRenovate will not update",
+            })
+            .to_string()
+            .into()
+        })
+        .expect_at_least(1)
+        .create();
+
+    let env = common::setup(
+        server.url(),
+        &fs::read_to_string("tests/rules.toml").unwrap(),
+    );
+    let pool = &env.pool;
+
+    service::sync(&mut pool.get().unwrap(), &env.clients)
+        .await
+        .unwrap();
+    comments.assert();
+
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    let commented = notifications
+        .iter()
+        .find(|notification| notification.title == "chore: update dependency typescript to v5.8.3")
+        .unwrap();
+    assert_eq!(commented.comment_author.as_deref(), Some("renovate[bot]"));
+    assert_eq!(
+        commented.comment_excerpt.as_deref(),
+        Some("Edited/Blocked Notification Renovate will not update")
+    );
+    // rewritten to the web url of the mock server, anchor included
+    assert!(
+        commented
+            .comment_url
+            .as_ref()
+            .unwrap()
+            .ends_with("/cogip/project1/pull/1#issuecomment-2780268633")
+    );
+    assert_eq!(
+        commented.comment_at.unwrap().to_string(),
+        "2025-04-07 10:00:00"
+    );
+
+    // no comment since the pull request was updated
+    let uncommented = notifications
+        .iter()
+        .find(|notification| notification.title == "chore: update expo monorepo")
+        .unwrap();
+    assert!(uncommented.comment_author.is_none() && uncommented.comment_url.is_none());
+}
@@ -0,0 +1,173 @@
+//! Shared by the integration tests: GitHub mocked with the fixtures, a migrated temporary database.
+//! Every test file is its own crate and only uses a part of it.
+
+#![allow(dead_code)]
+
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, Pool};
+use mockito::{Matcher, Mock, Server};
+use riirview::config::Config;
+use riirview::gh::Clients;
+use riirview::schema::pending_actions;
+use riirview::{get_connection_pool, run_db_migrations};
+use serde_json::Value;
+use std::env;
+use std::fs;
+use std::path::Path;
+use tempfile::NamedTempFile;
+
+pub const NOTIFICATIONS: &str = r"/notifications(\?.*)*$";
+pub const PULLS: &str = r"/repos/[^/]+/[^/]+/pulls/\d+$";
+pub const RELEASES: &str = r"/repos/[^/]+/[^/]+/releases/\d+$";
+pub const ISSUES: &str = r"/repos/[^/]+/[^/]+/issues/\d+$";
+
+/// Temporary database and rules of a test, deleted when dropped
+pub struct TestEnv {
+    pub pool: Pool<ConnectionManager<SqliteConnection>>,
+    pub clients: Clients,
+    pub rule_file: NamedTempFile,
+    db_file: NamedTempFile,
+}
+
+/// A `default` profile on `api_url` scored with `rules`
+pub fn setup(api_url: String, rules: &str) -> TestEnv {
+    setup_with(api_url, rules, |_, _| {})
+}
+
+/// Same as `setup`, `customize` completes the config, with the path of the rules, before the
+/// database is migrated
+pub fn setup_with(
+    api_url: String,
+    rules: &str,
+    customize: impl FnOnce(&mut Config, &Path),
+) -> TestEnv {
+    unsafe { env::set_var("GH_TOKEN", "faketoken") };
+
+    let db_file = NamedTempFile::new().unwrap();
+    let rule_file = NamedTempFile::new().unwrap();
+    fs::write(rule_file.path(), rules).unwrap();
+    let mut config = Config::init_for_test(
+        api_url,
+        db_file.path().to_str().unwrap().to_string(),
+        rule_file.path().to_str().unwrap().to_string(),
+    );
+    customize(&mut config, rule_file.path());
+    Config::set_for_test(config);
+
+    let pool = get_connection_pool();
+    run_db_migrations(&mut pool.get().unwrap());
+    TestEnv {
+        pool,
+        clients: Clients::from_config().unwrap(),
+        rule_file,
+        db_file,
+    }
+}
+
+/// A JSON fixture, to edit before serving it
+pub fn load_fixture(fixture: &str) -> Value {
+    serde_json::from_str(&fs::read_to_string(fixture).unwrap()).unwrap()
+}
+
+/// GET mock serving `data`, to complete and create. `REPLACE_URL` becomes the url of the request
+/// and the api.github.com urls point to `api_prefix` on the server, the web urls are kept.
+pub fn body_mock_at(server: &mut Server, api_prefix: &str, path_regex: &str, data: String) -> Mock {
+    let server_url = server.url();
+    let api_url = format!("{server_url}{api_prefix}");
+    server
+        .mock("GET", Matcher::Regex(format!("^{api_prefix}{path_regex}")))
+        .with_header("content-type", "application/json")
+        .with_status(200)
+        .with_body_from_request(move |request| {
+            let url = format!("{}{}", &server_url, request.path());
+            data.replace("REPLACE_URL", &url)
+                .replace("https://api.github.com", &api_url)
+                .into()
+        })
+}
+
+pub fn body_mock(server: &mut Server, path_regex: &str, data: String) -> Mock {
+    body_mock_at(server, "", path_regex, data)
+}
+
+pub fn fixture_mock(server: &mut Server, path_regex: &str, fixture: &str) -> Mock {
+    body_mock(server, path_regex, fs::read_to_string(fixture).unwrap())
+}
+
+pub fn mock_body(server: &mut Server, path_regex: &str, data: String) {
+    body_mock(server, path_regex, data).create();
+}
+
+pub fn mock_fixture(server: &mut Server, path_regex: &str, fixture: &str) {
+    fixture_mock(server, path_regex, fixture).create();
+}
+
+/// Subject fixtures served for every pull request, release and issue
+pub struct Subjects {
+    pub pull: Value,
+    pub release: Value,
+    pub issue: Value,
+}
+
+impl Default for Subjects {
+    fn default() -> Subjects {
+        Subjects {
+            pull: load_fixture("tests/pulls.json"),
+            release: load_fixture("tests/release.json"),
+            issue: load_fixture("tests/issues.json"),
+        }
+    }
+}
+
+/// The subjects under `api_prefix`, nothing resolved through GraphQL so that they are fetched
+/// with the REST API
+pub fn mock_subjects_at(server: &mut Server, api_prefix: &str, subjects: Subjects) {
+    body_mock_at(server, api_prefix, PULLS, subjects.pull.to_string()).create();
+    body_mock_at(server, api_prefix, RELEASES, subjects.release.to_string()).create();
+    body_mock_at(server, api_prefix, ISSUES, subjects.issue.to_string()).create();
+    let graphql = match api_prefix {
+        "" => "/graphql",
+        _ => "/api/graphql",
+    };
+    server
+        .mock("POST", graphql)
+        .with_header("content-type", "application/json")
+        .with_status(200)
+        .with_body(r#"{"data": {}}"#)
+        .create();
+}
+
+pub fn mock_subjects(server: &mut Server, subjects: Subjects) {
+    mock_subjects_at(server, "", subjects);
+}
+
+/// The 50 notifications of the fixtures and their subjects
+pub fn mock_github(server: &mut Server) {
+    mock_fixture(server, NOTIFICATIONS, "tests/notifications.json");
+    mock_subjects(server, Subjects::default());
+}
+
+/// The authenticated user, member of no team
+pub fn mock_user(server: &mut Server, login: &str) {
+    server
+        .mock("GET", "/user")
+        .with_header("content-type", "application/json")
+        .with_status(200)
+        .with_body(serde_json::json!({ "login": login }).to_string())
+        .create();
+    server
+        .mock("GET", "/user/teams?per_page=100")
+        .with_header("content-type", "application/json")
+        .with_status(200)
+        .with_body("[]")
+        .create();
+}
+
+/// Move the last attempt of the queued actions back in time, their backoff is over
+pub fn backdate_pending_actions(connection: &mut SqliteConnection) {
+    diesel::update(pending_actions::table)
+        .set(pending_actions::last_attempt_at.eq(Utc::now().naive_utc() - Duration::days(1)))
+        .execute(connection)
+        .unwrap();
+}
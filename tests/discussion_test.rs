@@ -0,0 +1,95 @@
+mod common;
+
+use riirview::models::{NotificationState, NotificationType};
+use riirview::service;
+
+fn thread(id: &str, title: &str, type_: &str) -> serde_json::Value {
+    serde_json::json!({
+        "id": id,
+        "unread": true,
+        "reason": "subscribed",
+        "updated_at": "2025-11-01T10:00:00Z",
+        "subject": { "title": title, "url": null, "latest_comment_url": null, "type": type_ },
+        "repository": {
+            "full_name": "rust-lang/cargo",
+            "html_url": "https://github.com/rust-lang/cargo",
+        },
+    })
+}
+
+#[tokio::test]
+async fn test_discussion_and_security_alert() {
+    let mut server = mockito::Server::new_async().await;
+    let threads = serde_json::json!([
+        thread("1", "How to vendor?", "Discussion"),
+        thread(
+            "2",
+            "Dependabot alert: openssl",
+            "RepositoryDependabotAlertsThread"
+        ),
+        thread("3", "Not among the latest", "Discussion"),
+    ]);
+    common::mock_body(&mut server, common::NOTIFICATIONS, threads.to_string());
+    let discussions = server
+        .mock("POST", "/graphql")
+        .match_body(mockito::Matcher::Regex("discussions".into()))
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"data": {
+              "n0": {"discussions": {"nodes": [
+                {"url": "https://github.com/rust-lang/cargo/discussions/7", "title": "How to vendor?",
+                 "closed": false, "stateReason": null, "isAnswered": true, "author": {"login": "epage"}}
+              ]}},
+              "n1": {"discussions": {"nodes": []}}
+            }}"#,
+        )
+        .expect(1)
+        .create();
+    let alerts = server
+        .mock("POST", "/graphql")
+        .match_body(mockito::Matcher::Regex("vulnerabilityAlerts".into()))
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"data": {"n0": {"vulnerabilityAlerts": {"nodes": [
+              {"number": 12, "state": "FIXED", "securityVulnerability": {"package": {"name": "openssl"}}}
+            ]}}}}"#,
+        )
+        .expect(1)
+        .create();
+
+    let env = common::setup(server.url(), "");
+    let pool = &env.pool;
+    service::sync(&mut pool.get().unwrap(), &env.clients)
+        .await
+        .unwrap();
+    discussions.assert();
+    alerts.assert();
+
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 3);
+
+    let discussion = notifications.iter().find(|n| n.id == "1").unwrap();
+    assert!(matches!(discussion.type_, NotificationType::Discussion));
+    assert_eq!(discussion.author, "epage");
+    assert!(matches!(discussion.state, NotificationState::Resolved));
+    // web urls are rewritten to the mock server
+    assert!(discussion.url.ends_with("/rust-lang/cargo/discussions/7"));
+
+    let alert = notifications.iter().find(|n| n.id == "2").unwrap();
+    assert!(matches!(alert.type_, NotificationType::SecurityAlert));
+    assert_eq!(alert.author, "dependabot");
+    assert!(matches!(alert.state, NotificationState::Resolved));
+    assert!(
+        alert
+            .url
+            .ends_with("/rust-lang/cargo/security/dependabot/12")
+    );
+
+    // not found: the search page of the discussions
+    let unknown = notifications.iter().find(|n| n.id == "3").unwrap();
+    assert!(unknown.author.is_empty());
+    assert!(matches!(unknown.state, NotificationState::Open));
+    assert!(unknown.url.contains("discussions_q="), "{}", unknown.url);
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use riirview::config::{Config, HttpConfig};
+use riirview::gh::Clients;
+use riirview::service;
+use std::env;
+use std::fs;
+use tempfile::NamedTempFile;
+
+#[tokio::test]
+async fn test_doctor() {
+    unsafe { env::set_var("GH_TOKEN", "faketoken") };
+
+    let mut server = mockito::Server::new_async().await;
+    common::mock_user(&mut server, "octocat");
+
+    let mut config = Config::init_for_test(
+        server.url(),
+        "/tmp/unused.db".into(),
+        "tests/rules.toml".into(),
+    );
+
+    let report = service::doctor().await;
+    assert!(report.is_ok());
+    assert_eq!(report.profiles.len(), 1);
+    assert_eq!(report.profiles[0].2, Ok("octocat".to_string()));
+    assert!(report.http.contains(&("ca", "system roots".to_string())));
+    assert!(report.to_string().contains("default"));
+    assert!(report.to_string().contains("ok, authenticated as octocat"));
+
+    // a CA file without certificate stops before any request
+    let ca_file = NamedTempFile::new().unwrap();
+    fs::write(ca_file.path(), "not a certificate").unwrap();
+    config.http = HttpConfig {
+        ca_cert: Some(ca_file.path().into()),
+        ..HttpConfig::default()
+    };
+    Config::set_for_test(config);
+
+    let report = service::doctor().await;
+    assert!(!report.is_ok());
+    assert!(report.profiles.is_empty());
+    let (_, client) = report.http.last().unwrap();
+    assert!(client.starts_with("error:"), "{client}");
+
+    // nor is it replaced by the defaults for the other commands
+    let err = Clients::from_config().err().unwrap();
+    assert!(err.to_string().contains("[http]"), "{err}");
+}
@@ -0,0 +1,167 @@
+mod common;
+
+use diesel::RunQueryDsl;
+use riirview::config::Subscription;
+use riirview::models::NotificationType;
+use riirview::schema;
+use riirview::service;
+
+const ATOM: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Release notes from rust</title>
+  <updated>2025-10-30T15:00:00Z</updated>
+  <entry>
+    <id>tag:github.com,2008:Repository/724712/1.91.0</id>
+    <updated>2025-10-30T15:00:00Z</updated>
+    <link rel="alternate" type="text/html" href="https://github.com/rust-lang/rust/releases/tag/1.91.0"/>
+    <title>Rust 1.91.0</title>
+    <author><name>cuviper</name></author>
+  </entry>
+  <entry>
+    <id>tag:github.com,2008:Repository/724712/1.90.0</id>
+    <link rel="alternate" type="text/html" href="https://github.com/rust-lang/rust/releases/tag/1.90.0"/>
+    <title>Rust 1.90.0</title>
+  </entry>
+</feed>"#;
+
+const RSS: &str = r#"<?xml version="1.0"?>
+<rss version="2.0">
+  <channel>
+    <title>Security advisories</title>
+    <item>
+      <title>CVE-2025-0001: heap overflow</title>
+      <link>https://example.com/advisories/1</link>
+      <guid>advisory-1</guid>
+      <pubDate>Thu, 30 Oct 2025 10:00:00 GMT</pubDate>
+    </item>
+    <item>
+      <title>Weekly news</title>
+      <link>https://example.com/news/2</link>
+      <author>news@example.com (Jane Doe)</author>
+    </item>
+  </channel>
+</rss>"#;
+
+#[tokio::test]
+async fn test_feeds() {
+    let mut server = mockito::Server::new_async().await;
+    common::mock_body(&mut server, common::NOTIFICATIONS, "[]".into());
+    let atom = server
+        .mock("GET", "/rust.atom")
+        .match_header("if-none-match", mockito::Matcher::Missing)
+        .with_header("content-type", "application/atom+xml")
+        .with_header("etag", "\"v1\"")
+        .with_status(200)
+        .with_body(ATOM)
+        .expect(1)
+        .create();
+    let atom_not_modified = server
+        .mock("GET", "/rust.atom")
+        .match_header("if-none-match", "\"v1\"")
+        .with_status(304)
+        .expect(1)
+        .create();
+    let rss = server
+        .mock("GET", "/advisories.rss")
+        .with_header("content-type", "application/rss+xml")
+        .with_status(200)
+        .with_body(RSS)
+        .expect(2)
+        .create();
+
+    let feed_url = |path: &str| format!("{}{path}", server.url());
+    let env = common::setup_with(
+        server.url(),
+        "[rust]\nrule=\"org\"\nparam=\"rust-lang\"\nscore=10\n\
+         [cuviper]\nrule=\"author\"\nparam=\"cuviper\"\nscore=5\n\
+         [cve]\nrule=\"title\"\nparam=\"CVE\"\nscore=30\n",
+        |config, rules_path| {
+            config.feeds = vec![
+                Subscription {
+                    name: "rust-lang/rust".into(),
+                    url: feed_url("/rust.atom"),
+                    rules_path: rules_path.to_path_buf(),
+                },
+                Subscription {
+                    name: "advisories".into(),
+                    url: feed_url("/advisories.rss"),
+                    rules_path: rules_path.to_path_buf(),
+                },
+            ];
+        },
+    );
+    let (pool, clients) = (&env.pool, &env.clients);
+
+    service::sync(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+
+    let entries = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 4);
+    for entry in &entries {
+        assert!(matches!(entry.type_, NotificationType::FeedEntry));
+        assert!(entry.unread);
+        assert_eq!(entry.reason, "feed");
+    }
+    let github = service::get_notifications(&mut pool.get().unwrap(), "forge:github")
+        .await
+        .unwrap();
+    assert!(github.is_empty());
+
+    // scored with the rules of the feed, best first
+    let cve = &entries[0];
+    assert_eq!(cve.id, "advisory-1");
+    assert_eq!(cve.account, "advisories");
+    assert_eq!(cve.repo, "advisories");
+    assert_eq!(cve.score, 30);
+    assert_eq!(cve.url, "https://example.com/advisories/1");
+    assert_eq!(cve.updated_at.to_string(), "2025-10-30 10:00:00");
+
+    let release = &entries[1];
+    assert_eq!(release.title, "Rust 1.91.0");
+    assert_eq!(release.repo, "rust-lang/rust");
+    assert_eq!(release.author, "cuviper");
+    assert_eq!(release.score, 15);
+    assert_eq!(entries[2].title, "Rust 1.90.0");
+    assert_eq!(entries[2].score, 10);
+    assert_eq!(entries[3].author, "Jane Doe");
+    assert_eq!(entries[3].score, 0);
+
+    let rules = service::explain(&mut pool.get().unwrap(), cve)
+        .await
+        .unwrap();
+    assert_eq!(rules.len(), 1);
+
+    // done stays local, nothing to replay
+    service::mark_notification_as_done(&mut pool.get().unwrap(), cve)
+        .await
+        .unwrap();
+    let pending = service::flush_pending_actions(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    assert_eq!(pending, 0);
+
+    // not due yet
+    service::sync(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    atom.assert();
+
+    // due again: the atom feed is not modified, the rss one is fetched again
+    diesel::delete(schema::sync_state::table)
+        .execute(&mut pool.get().unwrap())
+        .unwrap();
+    service::sync(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    atom_not_modified.assert();
+    rss.assert();
+
+    let entries = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 3);
+    assert!(entries.iter().all(|entry| entry.id != "advisory-1"));
+}
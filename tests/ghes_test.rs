@@ -0,0 +1,42 @@
+mod common;
+
+use common::Subjects;
+use riirview::config::Config;
+use riirview::service;
+
+#[tokio::test]
+async fn test_ghes() {
+    let mut server = mockito::Server::new_async().await;
+    let server_url = server.url();
+
+    // served the way an enterprise server would: api urls under `/api/v3`, while the web urls of
+    // the fixtures are left on github.com, to be rewritten by the profile
+    let threads = common::load_fixture("tests/notifications.json").to_string();
+    common::body_mock_at(&mut server, "/api/v3", common::NOTIFICATIONS, threads).create();
+    common::mock_subjects_at(&mut server, "/api/v3", Subjects::default());
+
+    let env = common::setup(format!("{}/api/v3", server_url), "");
+    let pool = &env.pool;
+    assert_eq!(Config::get().profiles[0].web_url, server_url);
+
+    service::sync(&mut pool.get().unwrap(), &env.clients)
+        .await
+        .unwrap();
+
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 50);
+    for notification in notifications {
+        assert!(
+            notification.url.starts_with(&server_url),
+            "{}",
+            notification.url
+        );
+        assert!(
+            !notification.url.contains("/api/v3"),
+            "{}",
+            notification.url
+        );
+    }
+}
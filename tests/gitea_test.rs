@@ -0,0 +1,309 @@
+mod common;
+
+use riirview::models::{ForgeKind, NotificationState, NotificationType};
+use riirview::service;
+use std::env;
+
+const AUTHORIZATION: &str = "token gitea-faketoken";
+
+fn mock_json(server: &mut mockito::Server, path_regex: &str, body: &str) -> mockito::Mock {
+    server
+        .mock("GET", mockito::Matcher::Regex(path_regex.to_string()))
+        .match_header("authorization", AUTHORIZATION)
+        .with_header("content-type", "application/json")
+        .with_status(200)
+        .with_body(body.replace("SERVER_URL", &server.url()))
+}
+
+/// Two pages counted by `X-Total-Count`: an issue, a draft pull request, a commit and an issue
+/// the server fails to return
+fn mock_gitea(server: &mut mockito::Server) {
+    mock_json(
+        server,
+        r"^/notifications\?limit=50&page=1$",
+        r#"[
+            {
+                "id": 1,
+                "repository": {"id": 1, "full_name": "forgejo/forgejo"},
+                "subject": {
+                    "title": "Crash on start",
+                    "url": "SERVER_URL/repos/forgejo/forgejo/issues/1",
+                    "html_url": "https://codeberg.org/forgejo/forgejo/issues/1",
+                    "type": "Issue",
+                    "state": "closed"
+                },
+                "unread": true,
+                "pinned": false,
+                "updated_at": "2025-11-01T10:00:00Z",
+                "url": "SERVER_URL/notifications/threads/1"
+            },
+            {
+                "id": 2,
+                "repository": {"id": 1, "full_name": "forgejo/forgejo"},
+                "subject": {
+                    "title": "WIP: Add a notification API",
+                    "url": "SERVER_URL/repos/forgejo/forgejo/pulls/2",
+                    "html_url": "https://codeberg.org/forgejo/forgejo/pulls/2",
+                    "type": "Pull",
+                    "state": "open"
+                },
+                "unread": true,
+                "pinned": false,
+                "updated_at": "2025-11-01T11:00:00Z",
+                "url": "SERVER_URL/notifications/threads/2"
+            }
+        ]"#,
+    )
+    .with_header("x-total-count", "4")
+    .create();
+    mock_json(
+        server,
+        r"^/notifications\?limit=50&page=2$",
+        r#"[
+            {
+                "id": 3,
+                "repository": {"id": 2, "full_name": "me/dotfiles"},
+                "subject": {
+                    "title": "Update the readme",
+                    "url": "",
+                    "html_url": "https://codeberg.org/me/dotfiles/commit/abc",
+                    "type": "Commit",
+                    "state": ""
+                },
+                "unread": false,
+                "pinned": false,
+                "updated_at": "2025-11-01T12:00:00Z",
+                "url": "SERVER_URL/notifications/threads/3"
+            },
+            {
+                "id": 4,
+                "repository": {"id": 2, "full_name": "me/dotfiles"},
+                "subject": {
+                    "title": "Broken link",
+                    "url": "SERVER_URL/repos/me/dotfiles/issues/4",
+                    "html_url": "https://codeberg.org/me/dotfiles/issues/4",
+                    "type": "Issue",
+                    "state": "open"
+                },
+                "unread": true,
+                "pinned": false,
+                "updated_at": "2025-11-01T09:00:00Z",
+                "url": "SERVER_URL/notifications/threads/4"
+            }
+        ]"#,
+    )
+    .with_header("x-total-count", "4")
+    .create();
+    server
+        .mock("GET", "/repos/me/dotfiles/issues/4")
+        .with_status(500)
+        .create();
+    mock_json(
+        server,
+        r"^/repos/forgejo/forgejo/issues/1$",
+        r#"{
+            "id": 11,
+            "number": 1,
+            "user": {"id": 5, "login": "dachary"},
+            "title": "Crash on start",
+            "labels": [{"id": 1, "name": "bug"}, {"id": 2, "name": "P0"}],
+            "milestone": {"id": 1, "title": "v13"},
+            "assignees": [{"id": 6, "login": "earl"}],
+            "state": "closed"
+        }"#,
+    )
+    .create();
+    mock_json(
+        server,
+        r"^/repos/forgejo/forgejo/pulls/2$",
+        r#"{
+            "id": 12,
+            "number": 2,
+            "user": {"id": 6, "login": "earl"},
+            "title": "WIP: Add a notification API",
+            "labels": [],
+            "milestone": null,
+            "assignees": null,
+            "state": "open",
+            "merged": false
+        }"#,
+    )
+    .create();
+}
+
+#[tokio::test]
+async fn test_gitea() {
+    unsafe { env::set_var("GITEA_TOKEN", "gitea-faketoken") };
+    unsafe { env::set_var("RIIRVIEW_HTTP_RETRIES", "0") };
+
+    let mut server = mockito::Server::new_async().await;
+    mock_gitea(&mut server);
+
+    let env = common::setup_with(server.url(), "", |config, _| {
+        config.profiles[0].forge = ForgeKind::Gitea;
+    });
+    let (pool, clients) = (&env.pool, &env.clients);
+
+    service::sync(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "forge:forgejo")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 4);
+
+    let issue = notifications.iter().find(|n| n.id == "1").unwrap();
+    assert!(matches!(issue.type_, NotificationType::Issue));
+    assert!(matches!(issue.state, NotificationState::Resolved));
+    assert_eq!(issue.author, "dachary");
+    assert_eq!(issue.repo, "forgejo/forgejo");
+    assert_eq!(issue.url, "https://codeberg.org/forgejo/forgejo/issues/1");
+    assert_eq!(issue.milestone.as_deref(), Some("v13"));
+    let details = service::get_details(&mut pool.get().unwrap(), issue).unwrap();
+    assert_eq!(details.labels, vec!["P0", "bug"]);
+    assert_eq!(details.assignees, vec!["earl"]);
+
+    let pull = notifications.iter().find(|n| n.id == "2").unwrap();
+    assert!(matches!(pull.type_, NotificationType::PullRequest));
+    assert!(matches!(pull.state, NotificationState::Draft));
+    assert_eq!(pull.author, "earl");
+    let details = service::get_details(&mut pool.get().unwrap(), pull).unwrap();
+    assert!(details.assignees.is_empty());
+
+    let commit = notifications.iter().find(|n| n.id == "3").unwrap();
+    assert!(matches!(commit.type_, NotificationType::Commit));
+    assert!(matches!(commit.state, NotificationState::Open));
+    assert!(!commit.unread);
+
+    // the issue couldn't be fetched, the thread is kept as is
+    let broken = notifications.iter().find(|n| n.id == "4").unwrap();
+    assert!(matches!(broken.type_, NotificationType::Issue));
+    assert!(matches!(broken.state, NotificationState::Open));
+    assert_eq!(broken.title, "Broken link");
+    assert!(broken.author.is_empty());
+
+    // unread threads left: a sync is needed
+    server
+        .mock("GET", "/notifications/new")
+        .match_header("authorization", AUTHORIZATION)
+        .with_header("content-type", "application/json")
+        .with_status(200)
+        .with_body(r#"{"new": 2}"#)
+        .create();
+    let status = service::check_update_and_limit(pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    assert!(status.need_update);
+    assert_eq!(status.poll_interval, 60);
+
+    // done marks the thread as read on the server
+    let read = server
+        .mock(
+            "PATCH",
+            mockito::Matcher::Regex(r"^/notifications/threads/1\?to-status=read$".into()),
+        )
+        .match_header("authorization", AUTHORIZATION)
+        .with_status(205)
+        .expect(1)
+        .create();
+    service::mark_notification_as_done(&mut pool.get().unwrap(), issue)
+        .await
+        .unwrap();
+    let pending = service::flush_pending_actions(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    assert_eq!(pending, 0);
+    read.assert();
+
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 3);
+
+    // the inbox is listed from the oldest thread left, the pull request was handled elsewhere
+    let inbox = mock_json(
+        &mut server,
+        r"^/notifications\?all=true&since=2025-11-01T08:59:59Z&limit=50&page=1$",
+        r#"[
+            {
+                "id": 3,
+                "repository": {"id": 2, "full_name": "me/dotfiles"},
+                "subject": {"title": "Update the readme", "type": "Commit"},
+                "unread": false,
+                "updated_at": "2025-11-01T12:00:00Z"
+            },
+            {
+                "id": 4,
+                "repository": {"id": 2, "full_name": "me/dotfiles"},
+                "subject": {"title": "Broken link", "type": "Issue"},
+                "unread": true,
+                "updated_at": "2025-11-01T09:00:00Z"
+            }
+        ]"#,
+    )
+    .with_header("x-total-count", "2")
+    .expect(1)
+    .create();
+    let updated = service::reconcile(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    inbox.assert();
+    assert_eq!(updated, 1);
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 2);
+
+    // read by done, its updated_at bumped: still done after a sync
+    let thread = |unread: bool, updated_at: &str| {
+        format!(
+            r#"[
+                {{
+                    "id": 1,
+                    "repository": {{"id": 1, "full_name": "forgejo/forgejo"}},
+                    "subject": {{
+                        "title": "Crash on start",
+                        "url": "SERVER_URL/repos/forgejo/forgejo/issues/1",
+                        "html_url": "https://codeberg.org/forgejo/forgejo/issues/1",
+                        "type": "Issue",
+                        "state": "closed"
+                    }},
+                    "unread": {unread},
+                    "pinned": false,
+                    "updated_at": "{updated_at}",
+                    "url": "SERVER_URL/notifications/threads/1"
+                }}
+            ]"#
+        )
+    };
+    let since = r"^/notifications\?all=true&since=[^&]+&limit=50&page=1$";
+    let listed = mock_json(&mut server, since, &thread(false, "2099-01-01T10:00:00Z"))
+        .with_header("x-total-count", "1")
+        .expect(1)
+        .create();
+    service::sync(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    listed.assert();
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 2);
+    assert!(notifications.iter().all(|n| n.id != "1"));
+
+    // unread again on a new comment: back
+    let listed = mock_json(&mut server, since, &thread(true, "2099-01-02T10:00:00Z"))
+        .with_header("x-total-count", "1")
+        .expect(1)
+        .create();
+    service::sync(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    listed.assert();
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 3);
+    assert!(notifications.iter().any(|n| n.id == "1" && n.unread));
+}
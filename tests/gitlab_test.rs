@@ -0,0 +1,253 @@
+mod common;
+
+use chrono::NaiveDateTime;
+use riirview::config::{Config, Profile};
+use riirview::forge::ProfileForge;
+use riirview::models::{ForgeKind, NotificationState, NotificationType};
+use riirview::service;
+use std::env;
+
+/// Two pages of pending to-do items: a merge request to review, a closed issue, a commit. The
+/// mocks of the pages are returned.
+fn mock_gitlab(server: &mut mockito::Server) -> Vec<mockito::Mock> {
+    let next = format!(
+        r#"<{}/todos?state=pending&per_page=100&page=2>; rel="next""#,
+        server.url()
+    );
+    let first_page = server
+        .mock(
+            "GET",
+            mockito::Matcher::Regex(r"^/todos\?state=pending&per_page=100$".into()),
+        )
+        .with_header("content-type", "application/json")
+        .with_header("link", &next)
+        .with_status(200)
+        .with_body(
+            r#"[
+                {
+                    "id": 102,
+                    "project": {"id": 2, "path_with_namespace": "gitlab-org/gitlab"},
+                    "author": {"username": "root"},
+                    "action_name": "review_requested",
+                    "target_type": "MergeRequest",
+                    "target": {
+                        "title": "Add a to-do API",
+                        "state": "opened",
+                        "author": {"username": "maxine"},
+                        "draft": false,
+                        "labels": ["backend", "api"],
+                        "assignees": [{"username": "root"}],
+                        "milestone": {"title": "18.6"},
+                        "updated_at": "2025-11-02T10:00:00.000Z"
+                    },
+                    "target_url": "https://gitlab.com/gitlab-org/gitlab/-/merge_requests/7",
+                    "state": "pending",
+                    "updated_at": "2025-11-01T10:00:00.000Z"
+                },
+                {
+                    "id": 103,
+                    "project": {"id": 2, "path_with_namespace": "gitlab-org/gitlab"},
+                    "author": {"username": "root"},
+                    "action_name": "mentioned",
+                    "target_type": "Issue",
+                    "target": {"title": "Crash on start", "state": "closed"},
+                    "target_url": "https://gitlab.com/gitlab-org/gitlab/-/issues/1",
+                    "state": "pending",
+                    "updated_at": "2025-11-01T11:00:00.000Z"
+                }
+            ]"#,
+        )
+        .create();
+    let second_page = server
+        .mock(
+            "GET",
+            mockito::Matcher::Regex(r"^/todos\?state=pending&per_page=100&page=2$".into()),
+        )
+        .with_header("content-type", "application/json")
+        .with_status(200)
+        .with_body(
+            r#"[
+                {
+                    "id": 104,
+                    "project": {"id": 3, "path_with_namespace": "group/tool"},
+                    "author": {"username": "bot"},
+                    "action_name": "build_failed",
+                    "target_type": "Commit",
+                    "target": {"title": "Fix the build"},
+                    "target_url": "https://gitlab.com/group/tool/-/commit/abc",
+                    "state": "pending",
+                    "updated_at": "2025-11-01T12:00:00.000Z"
+                }
+            ]"#,
+        )
+        .create();
+    // GitLab has no GitHub media types
+    server
+        .mock("GET", "/user")
+        .match_header("accept", "application/json")
+        .with_header("content-type", "application/json")
+        .with_status(200)
+        .with_body(r#"{"id": 1, "username": "maxine"}"#)
+        .create();
+    vec![first_page, second_page]
+}
+
+#[tokio::test]
+async fn test_gitlab() {
+    unsafe { env::set_var("GITLAB_TOKEN", "glpat-faketoken") };
+
+    let mut github_server = mockito::Server::new_async().await;
+    let mut gitlab_server = mockito::Server::new_async().await;
+    common::mock_github(&mut github_server);
+    common::mock_user(&mut github_server, "octocat");
+    let todo_pages = mock_gitlab(&mut gitlab_server);
+
+    let gitlab_url = gitlab_server.url();
+    let env = common::setup_with(
+        github_server.url(),
+        "[review]\nrule=\"reason\"\nparam=\"review_requested\"\nscore=20\n",
+        |config, rules_path| {
+            let mut gitlab = Profile::new("gitlab".into(), gitlab_url, rules_path.to_path_buf());
+            gitlab.forge = ForgeKind::GitLab;
+            config.profiles.push(gitlab);
+        },
+    );
+    let (pool, clients) = (&env.pool, &env.clients);
+
+    service::sync(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 53);
+
+    let todos = service::get_notifications(&mut pool.get().unwrap(), "forge:gitlab")
+        .await
+        .unwrap();
+    assert_eq!(todos.len(), 3);
+    for todo in &todos {
+        assert_eq!(todo.account, "gitlab");
+        assert_eq!(todo.forge, ForgeKind::GitLab);
+        assert!(todo.unread);
+    }
+
+    // scored with the same rules, first in the list
+    let review = &todos[0];
+    assert_eq!(review.id, "102");
+    assert_eq!(review.score, 20);
+    assert_eq!(review.title, "Add a to-do API");
+    assert_eq!(review.repo, "gitlab-org/gitlab");
+    assert_eq!(review.author, "maxine");
+    assert!(matches!(review.type_, NotificationType::PullRequest));
+    assert!(matches!(review.state, NotificationState::Open));
+    assert!(review.review_requested);
+    assert_eq!(review.milestone.as_deref(), Some("18.6"));
+    assert_eq!(
+        review.url,
+        "https://gitlab.com/gitlab-org/gitlab/-/merge_requests/7"
+    );
+    assert_eq!(
+        review.updated_at.to_string(),
+        "2025-11-02 10:00:00".to_string()
+    );
+
+    let details = service::get_details(&mut pool.get().unwrap(), review).unwrap();
+    assert_eq!(details.labels, vec!["api", "backend"]);
+    assert_eq!(details.assignees, vec!["root"]);
+
+    let mentions = service::get_notifications(&mut pool.get().unwrap(), "forge:gitlab crash")
+        .await
+        .unwrap();
+    assert_eq!(mentions.len(), 1);
+    assert_eq!(mentions[0].reason, "mention");
+    assert!(matches!(mentions[0].state, NotificationState::Resolved));
+
+    let failures = service::get_notifications(&mut pool.get().unwrap(), "ci:failure")
+        .await
+        .unwrap();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].id, "104");
+    assert!(matches!(failures[0].type_, NotificationType::Commit));
+
+    let github = service::get_notifications(&mut pool.get().unwrap(), "forge:github")
+        .await
+        .unwrap();
+    assert_eq!(github.len(), 50);
+
+    // a sync is only needed for the items updated since the last one
+    let gitlab = ProfileForge::new(clients, &Config::get().profiles[1]).unwrap();
+    let before = NaiveDateTime::parse_from_str("2025-11-01 10:30:00", "%Y-%m-%d %H:%M:%S").unwrap();
+    let status = gitlab.check_update(before, None).await.unwrap();
+    assert!(status.need_update);
+    let after = NaiveDateTime::parse_from_str("2025-11-02 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+    let status = gitlab.check_update(after, None).await.unwrap();
+    assert!(!status.need_update);
+
+    // done goes to GitLab, read stays local
+    let done = gitlab_server
+        .mock("POST", "/todos/102/mark_as_done")
+        .with_header("content-type", "application/json")
+        .with_status(200)
+        .with_body(r#"{"id": 102, "state": "done"}"#)
+        .expect(1)
+        .create();
+    service::mark_notification_as_done(&mut pool.get().unwrap(), review)
+        .await
+        .unwrap();
+    service::mark_notification_as_read(&mut pool.get().unwrap(), &mentions[0])
+        .await
+        .unwrap();
+    let pending = service::flush_pending_actions(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    assert_eq!(pending, 0);
+    done.assert();
+
+    let todos = service::get_notifications(&mut pool.get().unwrap(), "forge:gitlab")
+        .await
+        .unwrap();
+    assert_eq!(todos.len(), 2);
+
+    // updated and still pending on GitLab, the next sync doesn't mark it unread again
+    for page in todo_pages {
+        page.remove();
+    }
+    gitlab_server
+        .mock(
+            "GET",
+            mockito::Matcher::Regex(r"^/todos\?state=pending&per_page=100$".into()),
+        )
+        .with_header("content-type", "application/json")
+        .with_status(200)
+        .with_body(
+            r#"[
+                {
+                    "id": 103,
+                    "project": {"id": 2, "path_with_namespace": "gitlab-org/gitlab"},
+                    "author": {"username": "root"},
+                    "action_name": "mentioned",
+                    "target_type": "Issue",
+                    "target": {"title": "Crash on start again", "state": "closed"},
+                    "target_url": "https://gitlab.com/gitlab-org/gitlab/-/issues/1",
+                    "state": "pending",
+                    "updated_at": "2099-01-01T10:00:00.000Z"
+                }
+            ]"#,
+        )
+        .create();
+    service::sync(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    let mentions = service::get_notifications(&mut pool.get().unwrap(), "forge:gitlab crash")
+        .await
+        .unwrap();
+    assert_eq!(mentions[0].title, "Crash on start again");
+    assert!(!mentions[0].unread);
+
+    let report = service::doctor().await;
+    assert!(report.is_ok(), "{report}");
+    assert_eq!(report.profiles[1].0, "gitlab");
+    assert_eq!(report.profiles[1].2, Ok("maxine".to_string()));
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use chrono::{NaiveDate, Utc};
+use diesel::prelude::*;
+use riirview::config::Subscription;
+use riirview::models::HttpCache;
+use riirview::schema::http_cache;
+use riirview::service;
+
+#[tokio::test]
+async fn test_http_cache_pruned() {
+    let mut server = mockito::Server::new_async().await;
+    common::mock_github(&mut server);
+    let feed_url = format!("{}/news.atom", server.url());
+    server.mock("GET", "/news.atom").with_status(304).create();
+
+    let env = common::setup_with(server.url(), "", |config, rules_path| {
+        config.feeds = vec![Subscription {
+            name: "news".into(),
+            url: feed_url.clone(),
+            rules_path: rules_path.to_path_buf(),
+        }];
+    });
+    let pool = &env.pool;
+
+    // left by a thread marked as done long ago, by a feed not modified since, and used by the
+    // last sync
+    let long_ago = NaiveDate::from_ymd_opt(2020, 1, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    let entries = [
+        ("https://api.github.com/repos/a/b/pulls/1", long_ago),
+        (&feed_url, long_ago),
+        (
+            "https://api.github.com/repos/a/b/pulls/2",
+            Utc::now().naive_utc(),
+        ),
+    ];
+    for (cached_url, used_at) in entries {
+        diesel::insert_into(http_cache::table)
+            .values(HttpCache {
+                url: cached_url.to_string(),
+                etag: "\"etag\"".into(),
+                body: String::new(),
+                updated_at: used_at,
+            })
+            .execute(&mut pool.get().unwrap())
+            .unwrap();
+    }
+
+    service::sync(&mut pool.get().unwrap(), &env.clients)
+        .await
+        .unwrap();
+
+    let cached: Vec<String> = http_cache::table
+        .select(http_cache::url)
+        .order(http_cache::url)
+        .load(&mut pool.get().unwrap())
+        .unwrap();
+    assert_eq!(
+        cached,
+        vec![
+            feed_url,
+            "https://api.github.com/repos/a/b/pulls/2".to_string()
+        ]
+    );
+}
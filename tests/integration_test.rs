@@ -1,4 +1,5 @@
 use riirview::config::Config;
+use riirview::gh::Clients;
 use riirview::service;
 use riirview::{get_connection_pool, run_db_migrations};
 use std::env;
@@ -22,6 +23,7 @@ async fn test_e2e() {
     );
 
     let pool = get_connection_pool();
+    let clients = Clients::from_config().unwrap();
     run_db_migrations(&mut pool.get().unwrap());
 
     let mut file = File::open("tests/notifications.json").unwrap();
@@ -53,6 +55,7 @@ async fn test_e2e() {
             mockito::Matcher::Regex(r"^/repos/(.*)/(.*)/pulls/(.*)$".to_string()),
         )
         .with_header("content-type", "application/json")
+        .with_header("etag", r#""pulls-etag""#)
         .with_status(200)
         .with_body_from_request(move |request| {
             let url = format!("{}{}", &server_url, request.path());
@@ -62,6 +65,15 @@ async fn test_e2e() {
                 .into()
         })
         .create();
+    let pulls_not_modified = server
+        .mock(
+            "GET",
+            mockito::Matcher::Regex(r"^/repos/(.*)/(.*)/pulls/(.*)$".to_string()),
+        )
+        .match_header("if-none-match", r#""pulls-etag""#)
+        .with_status(304)
+        .expect_at_least(1)
+        .create();
 
     let mut file = File::open("tests/release.json").unwrap();
     let mut release_data = String::new();
@@ -103,9 +115,11 @@ async fn test_e2e() {
         })
         .create();
 
-    service::sync(&mut pool.get().unwrap()).await.unwrap();
+    service::sync(&mut pool.get().unwrap(), &clients)
+        .await
+        .unwrap();
 
-    let notifications = service::get_notifications(&mut pool.get().unwrap(), &String::new())
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
         .await
         .unwrap();
     assert_eq!(notifications.len(), 50);
@@ -114,28 +128,31 @@ async fn test_e2e() {
     // add boost
     //
 
-    let notification = notifications.get(0).unwrap();
+    let notification = notifications.first().unwrap();
     let first_id = notification.id.clone();
     service::update_score(&mut pool.clone().get().unwrap(), notification, 10)
         .await
         .unwrap();
     assert_eq!(notification.score_boost, 0); // not updated yet
 
-    let notifications = service::get_notifications(&mut pool.get().unwrap(), &String::new())
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
         .await
         .unwrap();
-    let notification = notifications.get(0).unwrap();
+    let notification = notifications.first().unwrap();
     assert_eq!(notification.id, first_id);
     assert_eq!(notification.score_boost, 10); // updated
 
-    // resync
-    service::sync(&mut pool.get().unwrap()).await.unwrap();
+    // resync, PRs come from the http cache
+    service::sync(&mut pool.get().unwrap(), &clients)
+        .await
+        .unwrap();
+    pulls_not_modified.assert();
 
-    let notifications = service::get_notifications(&mut pool.get().unwrap(), &String::new())
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
         .await
         .unwrap();
     assert_eq!(notifications.len(), 50);
-    let notification = notifications.get(0).unwrap();
+    let notification = notifications.first().unwrap();
     assert_eq!(notification.id, first_id);
     assert_eq!(notification.score_boost, 10);
 }
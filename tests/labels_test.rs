@@ -0,0 +1,74 @@
+mod common;
+
+use common::Subjects;
+use riirview::models::NotificationType;
+use riirview::service;
+
+#[tokio::test]
+async fn test_labels() {
+    let mut server = mockito::Server::new_async().await;
+    let mut subjects = Subjects::default();
+    subjects.issue["milestone"] = serde_json::json!({ "title": "v0.7" });
+    common::mock_fixture(
+        &mut server,
+        common::NOTIFICATIONS,
+        "tests/notifications.json",
+    );
+    common::mock_subjects(&mut server, subjects);
+
+    let env = common::setup(
+        server.url(),
+        "[features]\nrule=\"label\"\nparam=\"Enhancement, P0\"\nscore=20\n",
+    );
+    let (pool, clients) = (&env.pool, &env.clients);
+
+    service::sync(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+
+    let enhancements = service::get_notifications(&mut pool.get().unwrap(), "label:enhancement")
+        .await
+        .unwrap();
+    assert_eq!(enhancements.len(), 1);
+    let issue = &enhancements[0];
+    assert_eq!(issue.repo, "astral-sh/uv");
+    assert_eq!(issue.score, 20);
+    assert_eq!(issue.milestone.as_deref(), Some("v0.7"));
+    let details = service::get_details(&mut pool.get().unwrap(), issue).unwrap();
+    assert_eq!(details.labels, vec!["enhancement"]);
+    assert_eq!(details.assignees, vec!["Gankra"]);
+
+    let assigned = service::get_notifications(&mut pool.get().unwrap(), "assignee:gankra")
+        .await
+        .unwrap();
+    assert_eq!(assigned.len(), 1);
+    assert_eq!(assigned[0].id, issue.id);
+
+    // no wildcard in a label or an assignee
+    for query in ["label:enh_ncement", "label:%", "assignee:gank%"] {
+        let notifications = service::get_notifications(&mut pool.get().unwrap(), query)
+            .await
+            .unwrap();
+        assert!(notifications.is_empty(), "{query}");
+    }
+
+    // pull requests have their own label
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    let pr = notifications
+        .iter()
+        .find(|notification| matches!(notification.type_, NotificationType::PullRequest))
+        .unwrap();
+    assert_eq!(pr.score, 0);
+    let details = service::get_details(&mut pool.get().unwrap(), pr).unwrap();
+    assert_eq!(details.labels, vec!["🔥 Need docker"]);
+    assert!(details.assignees.is_empty());
+
+    // a second sync replaces the labels instead of adding them
+    service::sync(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    let details = service::get_details(&mut pool.get().unwrap(), issue).unwrap();
+    assert_eq!(details.labels, vec!["enhancement"]);
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use riirview::models::{NotificationState, NotificationType};
+use riirview::service;
+
+fn thread(id: &str, title: &str, type_: &str, url: &str) -> serde_json::Value {
+    serde_json::json!({
+        "id": id,
+        "unread": true,
+        "reason": "subscribed",
+        "updated_at": "2025-11-01T10:00:00Z",
+        "subject": { "title": title, "url": url, "latest_comment_url": null, "type": type_ },
+        "repository": {
+            "full_name": "rust-lang/cargo",
+            "html_url": "https://github.com/rust-lang/cargo",
+        },
+    })
+}
+
+#[tokio::test]
+async fn test_missing_subject() {
+    let mut server = mockito::Server::new_async().await;
+    let threads = serde_json::json!([
+        thread(
+            "1",
+            "Fix the build",
+            "Commit",
+            "https://api.github.com/repos/rust-lang/cargo/commits/abc"
+        ),
+        thread(
+            "2",
+            "0.90.0",
+            "Release",
+            "https://api.github.com/repos/rust-lang/cargo/releases/1"
+        ),
+    ]);
+    common::mock_body(&mut server, common::NOTIFICATIONS, threads.to_string());
+    server
+        .mock("POST", "/graphql")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"data": {}}"#)
+        .create();
+    // force-pushed away and deleted
+    let gone = server
+        .mock(
+            "GET",
+            mockito::Matcher::Regex("^/repos/rust-lang/cargo/(commits|releases)/".into()),
+        )
+        .with_status(404)
+        .expect(2)
+        .create();
+
+    let env = common::setup(server.url(), "");
+    let pool = &env.pool;
+    service::sync(&mut pool.get().unwrap(), &env.clients)
+        .await
+        .unwrap();
+    gone.assert();
+
+    // kept with the thread data
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 2);
+    let commit = notifications.iter().find(|n| n.id == "1").unwrap();
+    assert!(matches!(commit.type_, NotificationType::Commit));
+    assert!(matches!(commit.state, NotificationState::Open));
+    assert_eq!(commit.title, "Fix the build");
+    // web urls are rewritten to the mock server
+    assert!(commit.url.ends_with("/rust-lang/cargo"));
+    let release = notifications.iter().find(|n| n.id == "2").unwrap();
+    assert!(matches!(release.type_, NotificationType::Release));
+    assert!(release.author.is_empty());
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use common::backdate_pending_actions;
+use riirview::config::{Config, Profile};
+use riirview::gh::Clients;
+use riirview::service;
+use std::env;
+
+/// Same config with the profile on `api_url`, and clients for it
+fn switch_server(api_url: String) -> Clients {
+    let mut config = Config::get();
+    let profile = &config.profiles[0];
+    config.profiles[0] = Profile::new(profile.name.clone(), api_url, profile.rules_path.clone());
+    Config::set_for_test(config);
+    Clients::from_config().unwrap()
+}
+
+#[tokio::test]
+async fn test_offline() {
+    unsafe { env::set_var("RIIRVIEW_HTTP_RETRIES", "0") };
+
+    let mut server = mockito::Server::new_async().await;
+    common::mock_github(&mut server);
+
+    let env = common::setup(server.url(), "");
+    let pool = &env.pool;
+
+    service::sync(&mut pool.get().unwrap(), &env.clients)
+        .await
+        .unwrap();
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    let notification = &notifications[0];
+
+    //
+    // offline for longer than all the attempts: kept in the queue, not parked
+    //
+
+    // nothing listens there
+    let clients = switch_server("http://127.0.0.1:1".into());
+    service::mark_notification_as_done(&mut pool.get().unwrap(), notification)
+        .await
+        .unwrap();
+    for _ in 0..service::MAX_PENDING_ATTEMPTS * 2 {
+        backdate_pending_actions(&mut pool.get().unwrap());
+        let pending = service::flush_pending_actions(&mut pool.get().unwrap(), &clients)
+            .await
+            .unwrap();
+        assert_eq!(pending, 1);
+    }
+    assert!(
+        service::parked_action_error(&mut pool.get().unwrap())
+            .unwrap()
+            .is_none()
+    );
+
+    // not replayed again before the base delay
+    let pending = service::flush_pending_actions(&mut pool.get().unwrap(), &clients)
+        .await
+        .unwrap();
+    assert_eq!(pending, 1);
+
+    //
+    // back online: replayed
+    //
+
+    let clients = switch_server(server.url());
+    let done = server
+        .mock(
+            "DELETE",
+            format!("/notifications/threads/{}", notification.id).as_str(),
+        )
+        .with_status(205)
+        .expect(1)
+        .create();
+    backdate_pending_actions(&mut pool.get().unwrap());
+    let pending = service::flush_pending_actions(&mut pool.get().unwrap(), &clients)
+        .await
+        .unwrap();
+    assert_eq!(pending, 0);
+    done.assert();
+}
@@ -0,0 +1,208 @@
+mod common;
+
+use common::backdate_pending_actions;
+use riirview::service;
+use std::env;
+
+#[tokio::test]
+async fn test_pending_actions() {
+    unsafe { env::set_var("RIIRVIEW_HTTP_RETRIES", "0") };
+
+    let mut server = mockito::Server::new_async().await;
+    common::mock_github(&mut server);
+
+    let env = common::setup(server.url(), "");
+    let (pool, clients) = (&env.pool, &env.clients);
+
+    service::sync(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 50);
+
+    //
+    // GitHub unavailable: applied locally, kept in the queue
+    //
+
+    let notification = &notifications[0];
+    let path = format!("/notifications/threads/{}", notification.id);
+    let unavailable = server
+        .mock("DELETE", path.as_str())
+        .with_status(503)
+        .expect(1)
+        .create();
+
+    service::mark_notification_as_done(&mut pool.get().unwrap(), notification)
+        .await
+        .unwrap();
+    assert_eq!(
+        service::count_pending_actions(&mut pool.get().unwrap()).unwrap(),
+        1
+    );
+    let pending = service::flush_pending_actions(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    assert_eq!(pending, 1);
+
+    // not replayed before its backoff is over
+    let pending = service::flush_pending_actions(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    assert_eq!(pending, 1);
+    unavailable.assert();
+
+    // a sync doesn't bring it back
+    service::sync(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 49);
+    assert!(notifications.iter().all(|n| n.id != notification.id));
+
+    //
+    // back online
+    //
+
+    let done = server
+        .mock("DELETE", path.as_str())
+        .with_status(205)
+        .expect(1)
+        .create();
+    backdate_pending_actions(&mut pool.get().unwrap());
+    let pending = service::flush_pending_actions(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    assert_eq!(pending, 0);
+    done.assert();
+
+    //
+    // refused for good: dropped
+    //
+
+    let notification = &notifications[0];
+    let not_found = server
+        .mock(
+            "PATCH",
+            format!("/notifications/threads/{}", notification.id).as_str(),
+        )
+        .with_status(404)
+        .expect(1)
+        .create();
+    service::mark_notification_as_read(&mut pool.get().unwrap(), notification)
+        .await
+        .unwrap();
+    let pending = service::flush_pending_actions(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    assert_eq!(pending, 0);
+    not_found.assert();
+
+    //
+    // mute, then unmute from the muted view
+    //
+
+    let notification = &notifications[1];
+    let thread = format!("/notifications/threads/{}", notification.id);
+    let subscription = format!("{thread}/subscription");
+    let ignored = server
+        .mock("PUT", subscription.as_str())
+        .match_body(mockito::Matcher::Json(
+            serde_json::json!({ "ignored": true }),
+        ))
+        .with_status(200)
+        .expect(1)
+        .create();
+    let done = server
+        .mock("DELETE", thread.as_str())
+        .with_status(205)
+        .expect(1)
+        .create();
+    service::mute_notification(&mut pool.get().unwrap(), notification)
+        .await
+        .unwrap();
+    let pending = service::flush_pending_actions(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    assert_eq!(pending, 0);
+    ignored.assert();
+    done.assert();
+
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 48);
+    let muted = service::get_notifications(&mut pool.get().unwrap(), "is:muted")
+        .await
+        .unwrap();
+    assert_eq!(muted.len(), 1);
+    assert!(muted[0].muted);
+
+    let unmuted = server
+        .mock("PUT", subscription.as_str())
+        .match_body(mockito::Matcher::Json(
+            serde_json::json!({ "ignored": false }),
+        ))
+        .with_status(200)
+        .expect(1)
+        .create();
+    service::unmute_notification(&mut pool.get().unwrap(), &muted[0])
+        .await
+        .unwrap();
+    let pending = service::flush_pending_actions(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    assert_eq!(pending, 0);
+    unmuted.assert();
+    let muted = service::get_notifications(&mut pool.get().unwrap(), "is:muted")
+        .await
+        .unwrap();
+    assert!(muted.is_empty());
+
+    //
+    // failing again and again: parked with its error, until redone
+    //
+
+    let notification = &notifications[2];
+    let thread = format!("/notifications/threads/{}", notification.id);
+    let unavailable = server
+        .mock("PATCH", thread.as_str())
+        .with_status(503)
+        .expect(service::MAX_PENDING_ATTEMPTS as usize)
+        .create();
+    service::mark_notification_as_read(&mut pool.get().unwrap(), notification)
+        .await
+        .unwrap();
+    for _ in 0..service::MAX_PENDING_ATTEMPTS + 1 {
+        backdate_pending_actions(&mut pool.get().unwrap());
+        let pending = service::flush_pending_actions(&mut pool.get().unwrap(), clients)
+            .await
+            .unwrap();
+        assert_eq!(pending, 1);
+    }
+    unavailable.assert();
+    let parked = service::parked_action_error(&mut pool.get().unwrap()).unwrap();
+    assert!(parked.unwrap().contains("503"));
+
+    let read = server
+        .mock("PATCH", thread.as_str())
+        .with_status(205)
+        .expect(1)
+        .create();
+    service::mark_notification_as_read(&mut pool.get().unwrap(), notification)
+        .await
+        .unwrap();
+    let pending = service::flush_pending_actions(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    assert_eq!(pending, 0);
+    read.assert();
+    assert!(
+        service::parked_action_error(&mut pool.get().unwrap())
+            .unwrap()
+            .is_none()
+    );
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use riirview::service;
+
+const LAST_MODIFIED: &str = "Thu, 16 Oct 2025 10:00:00 GMT";
+
+#[tokio::test]
+async fn test_reconcile() {
+    let mut server = mockito::Server::new_async().await;
+    common::mock_github(&mut server);
+
+    let env = common::setup(server.url(), "");
+    let (pool, clients) = (&env.pool, &env.clients);
+
+    service::sync(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 50);
+    // bounded by the oldest thread still in the inbox
+    let since = notifications
+        .iter()
+        .map(|notification| notification.updated_at)
+        .min()
+        .unwrap()
+        - chrono::Duration::seconds(1);
+    let since = since.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+
+    // only 10 threads left in the inbox, two pages, the first one read elsewhere
+    let mut threads: Vec<serde_json::Value> =
+        serde_json::from_value(common::load_fixture("tests/notifications.json")).unwrap();
+    threads.truncate(10);
+    threads[0]["unread"] = serde_json::Value::Bool(false);
+    let read_id = threads[0]["id"].as_str().unwrap().to_string();
+    let second_page = threads.split_off(5);
+
+    let first = server
+        .mock("GET", "/notifications")
+        .match_query(mockito::Matcher::AllOf(vec![
+            mockito::Matcher::UrlEncoded("all".into(), "true".into()),
+            mockito::Matcher::UrlEncoded("per_page".into(), "50".into()),
+            mockito::Matcher::UrlEncoded("since".into(), since),
+        ]))
+        .with_header("content-type", "application/json")
+        .with_header("last-modified", LAST_MODIFIED)
+        .with_header(
+            "link",
+            &format!(
+                r#"<{}/notifications?all=true&per_page=50&page=2>; rel="next""#,
+                server.url()
+            ),
+        )
+        .with_body(serde_json::to_string(&threads).unwrap())
+        .expect(1)
+        .create();
+    let second = server
+        .mock("GET", "/notifications")
+        .match_query(mockito::Matcher::UrlEncoded("page".into(), "2".into()))
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::to_string(&second_page).unwrap())
+        .expect(1)
+        .create();
+
+    let updated = service::reconcile(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    first.assert();
+    second.assert();
+    assert_eq!(updated, 41);
+
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 10);
+    for notification in notifications {
+        assert_eq!(notification.unread, notification.id != read_id);
+    }
+
+    // nothing changed since: the listing isn't fetched again
+    let not_modified = server
+        .mock("GET", "/notifications")
+        .match_query(mockito::Matcher::UrlEncoded("all".into(), "true".into()))
+        .match_header("if-modified-since", LAST_MODIFIED)
+        .with_status(304)
+        .expect(1)
+        .create();
+    let updated = service::reconcile(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    not_modified.assert();
+    assert_eq!(updated, 0);
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use riirview::config::{Config, Recording};
+use riirview::gh::Clients;
+use riirview::service;
+use riirview::{get_connection_pool, run_db_migrations};
+use std::env;
+use std::fs;
+use tempfile::NamedTempFile;
+
+#[tokio::test]
+async fn test_record_replay() {
+    let mut server = mockito::Server::new_async().await;
+    common::mock_github(&mut server);
+
+    let recording_dir = tempfile::tempdir().unwrap();
+    let env = common::setup_with(
+        server.url(),
+        &fs::read_to_string("tests/rules.toml").unwrap(),
+        |config, _| config.recording = Some(Recording::Record(recording_dir.path().into())),
+    );
+    service::sync(&mut env.pool.get().unwrap(), &env.clients)
+        .await
+        .unwrap();
+    let recorded = service::get_notifications(&mut env.pool.get().unwrap(), "")
+        .await
+        .unwrap();
+
+    let files: Vec<_> = fs::read_dir(recording_dir.path())
+        .unwrap()
+        .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+        .collect();
+    assert!(files.len() > 2);
+    assert!(files.iter().all(|file| !file.contains("faketoken")));
+    assert!(files.iter().any(|file| file.contains("\"POST\"")));
+
+    // nothing left to answer on the network, and no token
+    server.reset();
+    unsafe { env::remove_var("GH_TOKEN") };
+    let replay_db_file = NamedTempFile::new().unwrap();
+    let mut config = Config::get();
+    config.db_path = replay_db_file.path().to_str().unwrap().to_string();
+    config.recording = Some(Recording::Replay(recording_dir.path().into()));
+    Config::set_for_test(config);
+
+    let pool = get_connection_pool();
+    run_db_migrations(&mut pool.get().unwrap());
+    service::sync(&mut pool.get().unwrap(), &Clients::from_config().unwrap())
+        .await
+        .unwrap();
+    let replayed = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+
+    assert_eq!(replayed.len(), recorded.len());
+    for (replayed, recorded) in replayed.iter().zip(&recorded) {
+        assert_eq!(replayed.id, recorded.id);
+        assert_eq!(replayed.title, recorded.title);
+        assert_eq!(replayed.author, recorded.author);
+        assert_eq!(replayed.score, recorded.score);
+    }
+}
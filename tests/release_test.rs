@@ -0,0 +1,48 @@
+mod common;
+
+use common::Subjects;
+use riirview::models::NotificationType;
+use riirview::service;
+
+#[tokio::test]
+async fn test_release_rule() {
+    let mut server = mockito::Server::new_async().await;
+    let mut subjects = Subjects::default();
+    subjects.release["tag_name"] = "v2.0.0-rc.1".into();
+    subjects.release["prerelease"] = true.into();
+    common::mock_fixture(
+        &mut server,
+        common::NOTIFICATIONS,
+        "tests/notifications.json",
+    );
+    common::mock_subjects(&mut server, subjects);
+
+    let env = common::setup(
+        server.url(),
+        "[majors]\nrule=\"release\"\nparam=\"major\"\nscore=20\n\
+         [noise]\nrule=\"release\"\nparam=\"patch, prerelease\"\nscore=-5\n",
+    );
+    let pool = &env.pool;
+
+    service::sync(&mut pool.get().unwrap(), &env.clients)
+        .await
+        .unwrap();
+
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    let releases: Vec<_> = notifications
+        .iter()
+        .filter(|notification| matches!(notification.type_, NotificationType::Release))
+        .collect();
+    assert_eq!(releases.len(), 3);
+    for release in releases {
+        assert_eq!(release.release_tag.as_deref(), Some("v2.0.0-rc.1"));
+        assert!(release.prerelease);
+        assert_eq!(
+            release.published_at.unwrap().to_string(),
+            "2025-04-05 00:37:46"
+        );
+        assert_eq!(release.score, 15);
+    }
+}
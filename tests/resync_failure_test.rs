@@ -0,0 +1,49 @@
+mod common;
+
+use chrono::NaiveDate;
+use riirview::config::Profile;
+use riirview::service;
+use std::env;
+
+#[tokio::test]
+async fn test_resync_failure() {
+    unsafe { env::set_var("RIIRVIEW_HTTP_RETRIES", "0") };
+
+    let mut server = mockito::Server::new_async().await;
+    common::mock_github(&mut server);
+
+    // nothing is mocked under /broken, resynced before the working profile
+    let broken_url = format!("{}/broken", server.url());
+    let env = common::setup_with(server.url(), "", |config, rules_path| {
+        config.profiles.insert(
+            0,
+            Profile::new("broken".into(), broken_url, rules_path.into()),
+        );
+    });
+    let (pool, clients) = (&env.pool, &env.clients);
+
+    let since = NaiveDate::from_ymd_opt(2025, 1, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    assert!(
+        service::resync(&mut pool.get().unwrap(), clients, since)
+            .await
+            .is_err()
+    );
+
+    // the other profile is resynced anyway
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 50);
+
+    let sync_states = service::get_sync_states(&mut pool.get().unwrap()).unwrap();
+    assert_eq!(sync_states.len(), 2);
+    let broken = sync_states.iter().find(|s| s.account == "broken").unwrap();
+    assert!(broken.error.is_some());
+    assert!(broken.succeeded_at.is_none());
+    let default = sync_states.iter().find(|s| s.account != "broken").unwrap();
+    assert!(default.error.is_none());
+    assert_eq!(default.fetched, 50);
+}
@@ -0,0 +1,99 @@
+use chrono::NaiveDate;
+mod common;
+
+use riirview::service;
+use std::fs;
+
+#[tokio::test]
+async fn test_resync() {
+    let mut server = mockito::Server::new_async().await;
+    common::mock_github(&mut server);
+
+    let env = common::setup(server.url(), "");
+    let (pool, clients) = (&env.pool, &env.clients);
+
+    service::sync(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 50);
+
+    let boosted = notifications[0].clone();
+    service::update_score(&mut pool.get().unwrap(), &boosted, 5)
+        .await
+        .unwrap();
+    let handled = notifications[1].clone();
+    service::mark_notification_as_done(&mut pool.get().unwrap(), &handled)
+        .await
+        .unwrap();
+
+    // new rules, one thread renamed and a new one
+    fs::write(
+        env.rule_file.path(),
+        "[project1]\nrule=\"repo\"\nparam=\"cogip/project1\"\nscore=50\n",
+    )
+    .unwrap();
+    let mut threads: Vec<serde_json::Value> =
+        serde_json::from_value(common::load_fixture("tests/notifications.json")).unwrap();
+    let renamed_id = threads[2]["id"].as_str().unwrap().to_string();
+    threads[2]["subject"]["title"] = "renamed".into();
+    let mut new_thread = threads[0].clone();
+    new_thread["id"] = "1".into();
+    threads.push(new_thread);
+    let body = serde_json::to_string(&threads)
+        .unwrap()
+        .replace("https://api.github.com", &server.url());
+    let refetch = server
+        .mock("GET", "/notifications")
+        .match_query(mockito::Matcher::AllOf(vec![
+            mockito::Matcher::UrlEncoded("all".into(), "true".into()),
+            mockito::Matcher::UrlEncoded("since".into(), "2025-01-01T00:00:00Z".into()),
+        ]))
+        .with_header("content-type", "application/json")
+        .with_body(body)
+        .expect(1)
+        .create();
+
+    let since = NaiveDate::from_ymd_opt(2025, 1, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    let report = service::resync(&mut pool.get().unwrap(), clients, since)
+        .await
+        .unwrap();
+    refetch.assert();
+    assert_eq!(report.fetched, 51);
+    assert_eq!(report.added.len(), 1);
+    let (_, renamed_changes) = report
+        .updated
+        .iter()
+        .find(|(notification, _)| notification.ends_with(": renamed"))
+        .unwrap();
+    assert!(
+        renamed_changes
+            .iter()
+            .any(|change| change.starts_with("title "))
+    );
+
+    let notifications = service::get_notifications(&mut pool.get().unwrap(), "")
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 50);
+    assert!(notifications.iter().all(|n| n.id != handled.id));
+    for notification in &notifications {
+        let expected = if notification.repo == "cogip/project1" {
+            50
+        } else {
+            0
+        };
+        assert_eq!(notification.score, expected, "{}", notification.repo);
+        if notification.id == boosted.id {
+            assert_eq!(notification.score_boost, 5);
+        }
+        if notification.id == renamed_id {
+            assert_eq!(notification.title, "renamed");
+        }
+    }
+}
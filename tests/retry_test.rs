@@ -0,0 +1,88 @@
+mod common;
+
+use riirview::config::{Config, RetryPolicy};
+use riirview::gh::{self, Client};
+
+#[tokio::test]
+async fn test_retry() {
+    let mut server = mockito::Server::new_async().await;
+
+    // short delays, the retries are not under test
+    let _env = common::setup_with(server.url(), "", |config, _| {
+        config.retry = RetryPolicy {
+            max_retries: 3,
+            base_delay_ms: 1,
+            max_delay_ms: 10,
+        }
+    });
+    let client = Client::new(&Config::get().profiles[0]).unwrap();
+
+    //
+    // 5xx are retried
+    //
+
+    // mocks are matched in creation order until their expected hits are reached
+    let bad_gateway = server
+        .mock("GET", "/notifications")
+        .with_status(502)
+        .expect(2)
+        .create();
+    let ok = server
+        .mock("GET", "/notifications")
+        .with_header("content-type", "application/json")
+        .with_status(200)
+        .with_body("[]")
+        .expect(1)
+        .create();
+
+    let (notifications, _) = gh::fetch_notifications(&client, None).await.unwrap();
+    assert!(notifications.is_empty());
+    bad_gateway.assert();
+    ok.assert();
+
+    //
+    // give up after max retries
+    //
+
+    let unavailable = server
+        .mock("PATCH", "/notifications/threads/1")
+        .with_status(503)
+        .expect(Config::get().retry.max_retries as usize + 1)
+        .create();
+
+    assert!(gh::mark_as_read(&client, &"1".to_string()).await.is_err());
+    unavailable.assert();
+
+    //
+    // 4xx are not retried
+    //
+
+    let not_found = server
+        .mock("PATCH", "/notifications/threads/2")
+        .with_status(404)
+        .expect(1)
+        .create();
+
+    assert!(gh::mark_as_read(&client, &"2".to_string()).await.is_err());
+    not_found.assert();
+
+    //
+    // rate limited until the end
+    //
+
+    let rate_limited = server
+        .mock("PATCH", "/notifications/threads/3")
+        .with_status(429)
+        .with_header("retry-after", "0")
+        .expect(4)
+        .create();
+
+    let err = gh::mark_as_read(&client, &"3".to_string())
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err.downcast_ref(), Some(gh::Error::RateLimited(_))),
+        "{err:?}"
+    );
+    rate_limited.assert();
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::Subjects;
+use riirview::service;
+
+#[tokio::test]
+async fn test_review_requested() {
+    let mut server = mockito::Server::new_async().await;
+    // every pull request waits for a review of the core team
+    let mut subjects = Subjects::default();
+    subjects.pull["requested_teams"] = serde_json::json!([{ "slug": "core" }]);
+    common::mock_fixture(
+        &mut server,
+        common::NOTIFICATIONS,
+        "tests/notifications.json",
+    );
+    common::mock_subjects(&mut server, subjects);
+    let user = server
+        .mock("GET", "/user")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"login": "me"}"#)
+        .expect(1)
+        .create();
+    let teams = server
+        .mock("GET", "/user/teams")
+        .match_query(mockito::Matcher::Any)
+        .with_header("content-type", "application/json")
+        .with_body(r#"[{"slug": "core", "organization": {"login": "cogip"}}]"#)
+        .expect(1)
+        .create();
+
+    let env = common::setup(
+        server.url(),
+        "[to_review]\nrule=\"review\"\nparam=\"requested\"\nscore=50\n",
+    );
+    let (pool, clients) = (&env.pool, &env.clients);
+
+    service::sync(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    // the user is fetched once per session
+    service::sync(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    user.assert();
+    teams.assert();
+
+    // the team belongs to cogip, not to the other organizations
+    let requested = service::get_notifications(&mut pool.get().unwrap(), "review:requested")
+        .await
+        .unwrap();
+    assert_eq!(requested.len(), 45);
+    for notification in &requested {
+        assert!(notification.review_requested);
+        assert!(notification.repo.starts_with("cogip/"));
+        assert_eq!(notification.score, 50);
+    }
+
+    // review decision and checks are only known through GraphQL
+    let failing = service::get_notifications(&mut pool.get().unwrap(), "ci:failure")
+        .await
+        .unwrap();
+    assert!(failing.is_empty());
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::Subjects;
+use riirview::models::{NotificationState, StateReason};
+use riirview::service;
+
+#[tokio::test]
+async fn test_issue_closed_as_not_planned() {
+    let mut server = mockito::Server::new_async().await;
+    // nothing resolved with graphql, the issue is fetched with rest
+    let mut subjects = Subjects::default();
+    subjects.issue["state"] = "closed".into();
+    subjects.issue["state_reason"] = "not_planned".into();
+    common::mock_fixture(
+        &mut server,
+        common::NOTIFICATIONS,
+        "tests/notifications.json",
+    );
+    common::mock_subjects(&mut server, subjects);
+
+    let env = common::setup(server.url(), "");
+    let pool = &env.pool;
+
+    service::sync(&mut pool.get().unwrap(), &env.clients)
+        .await
+        .unwrap();
+
+    let canceled = service::get_notifications(&mut pool.get().unwrap(), "state:canceled")
+        .await
+        .unwrap();
+    assert_eq!(canceled.len(), 1);
+    assert_eq!(canceled[0].repo, "astral-sh/uv");
+    assert!(matches!(canceled[0].state, NotificationState::Canceled));
+    assert_eq!(canceled[0].state_reason, Some(StateReason::NotPlanned));
+    assert!(canceled[0].duplicate_of.is_none());
+}
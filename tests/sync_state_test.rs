@@ -0,0 +1,89 @@
+mod common;
+
+use riirview::service;
+use std::env;
+
+const LAST_MODIFIED: &str = "Sat, 04 Oct 2025 09:00:00 GMT";
+
+#[tokio::test]
+async fn test_sync_state() {
+    unsafe { env::set_var("RIIRVIEW_HTTP_RETRIES", "0") };
+
+    let mut server = mockito::Server::new_async().await;
+    common::fixture_mock(
+        &mut server,
+        common::NOTIFICATIONS,
+        "tests/notifications.json",
+    )
+    .with_header("last-modified", LAST_MODIFIED)
+    .with_header("x-poll-interval", "60")
+    .with_header("x-ratelimit-remaining", "4990")
+    .with_header("x-ratelimit-used", "10")
+    .create();
+    common::mock_subjects(&mut server, common::Subjects::default());
+
+    let env = common::setup(server.url(), "");
+    let (pool, clients) = (&env.pool, &env.clients);
+    assert!(service::last_synced(&mut pool.get().unwrap()).is_none());
+
+    service::sync(&mut pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    let states = service::get_sync_states(&mut pool.get().unwrap()).unwrap();
+    assert_eq!(states.len(), 1);
+    let state = &states[0];
+    assert_eq!(state.account, "default");
+    assert_eq!(state.fetched, 50);
+    assert_eq!(state.last_modified.as_deref(), Some(LAST_MODIFIED));
+    assert_eq!(state.poll_interval, Some(60));
+    assert_eq!(state.ratelimit_remaining, Some(4990));
+    assert_eq!(state.ratelimit_used, Some(10));
+    assert!(state.error.is_none());
+    assert_eq!(
+        service::last_synced(&mut pool.get().unwrap()),
+        state.succeeded_at
+    );
+    // the server date, not the newest notification
+    let since = state.since.unwrap();
+    assert!((chrono::Utc::now().naive_utc() - since).num_minutes() < 5);
+
+    // the next check and sync start from the recorded values
+    let head = server
+        .mock("HEAD", "/notifications")
+        .match_header("if-modified-since", LAST_MODIFIED)
+        .with_status(304)
+        .with_header("x-poll-interval", "60")
+        .with_header("x-ratelimit-remaining", "4989")
+        .with_header("x-ratelimit-used", "11")
+        .expect(1)
+        .create();
+    let status = service::check_update_and_limit(pool.get().unwrap(), clients)
+        .await
+        .unwrap();
+    head.assert();
+    assert!(!status.need_update);
+
+    let unavailable = server
+        .mock("GET", "/notifications")
+        .match_query(mockito::Matcher::UrlEncoded(
+            "since".into(),
+            since.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        ))
+        .with_status(503)
+        .expect(1)
+        .create();
+    assert!(
+        service::sync(&mut pool.get().unwrap(), clients)
+            .await
+            .is_err()
+    );
+    unavailable.assert();
+
+    // the failure is recorded, the last success is kept
+    let failed = &service::get_sync_states(&mut pool.get().unwrap()).unwrap()[0];
+    assert!(failed.error.is_some());
+    assert_eq!(failed.fetched, 0);
+    assert_eq!(failed.since, Some(since));
+    assert_eq!(failed.succeeded_at, state.succeeded_at);
+    assert_eq!(failed.last_modified.as_deref(), Some(LAST_MODIFIED));
+}
@@ -0,0 +1,70 @@
+use diesel::RunQueryDsl;
+use diesel_migrations::{FileBasedMigrations, MigrationHarness};
+use riirview::config::Config;
+use riirview::gh::Clients;
+use riirview::service;
+use riirview::{get_connection_pool, run_db_migrations};
+use std::env;
+use tempfile::NamedTempFile;
+
+/// init, add_reason and add_http_cache: the schema before accounts
+const PRE_PROFILE_MIGRATIONS: usize = 3;
+
+#[tokio::test]
+async fn test_upgrade_pre_profile_db() {
+    unsafe { env::set_var("GH_TOKEN", "faketoken") };
+
+    let mut server = mockito::Server::new_async().await;
+    let done = server
+        .mock("DELETE", "/notifications/threads/1")
+        .with_status(205)
+        .expect(1)
+        .create();
+
+    // only a `personal` profile, none named `default`
+    let db_file = NamedTempFile::new().unwrap();
+    let mut config = Config::init_for_test(
+        server.url(),
+        db_file.path().to_str().unwrap().to_string(),
+        "tests/rules.toml".into(),
+    );
+    config.profiles[0].name = "personal".into();
+    Config::set_for_test(config);
+
+    let pool = get_connection_pool();
+    let mut connection = pool.get().unwrap();
+    for _ in 0..PRE_PROFILE_MIGRATIONS {
+        connection
+            .run_next_migration(FileBasedMigrations::from_path("migrations").unwrap())
+            .unwrap();
+    }
+    diesel::sql_query(
+        "INSERT INTO notifications (id, title, repo, url, type, state, author, updated_at, unread, \
+         done, score, score_boost, reason) VALUES ('1', 'Fix the build', 'rust-lang/rust', \
+         'https://github.com/rust-lang/rust/pull/1', 'PullRequest', 'Open', 'bob', \
+         '2025-01-01 00:00:00', 1, 0, 5, 0, 'mention')",
+    )
+    .execute(&mut connection)
+    .unwrap();
+
+    run_db_migrations(&mut connection);
+    assert_eq!(service::adopt_default_account(&mut connection).unwrap(), 1);
+    assert_eq!(service::adopt_default_account(&mut connection).unwrap(), 0);
+
+    let legacy = service::get_notifications(&mut connection, "account:personal")
+        .await
+        .unwrap();
+    assert_eq!(legacy.len(), 1);
+    assert_eq!(legacy[0].title, "Fix the build");
+    assert_eq!(legacy[0].score, 5);
+
+    // its actions go through the profile
+    service::mark_notification_as_done(&mut connection, &legacy[0])
+        .await
+        .unwrap();
+    let pending = service::flush_pending_actions(&mut connection, &Clients::from_config().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(pending, 0);
+    done.assert();
+}